@@ -16,13 +16,16 @@
 //!
 //! See the submodules for each phase for more details on the implementation
 //! and references used.
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use log::{debug, info};
 use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableDiGraph};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction::{Incoming, Outgoing};
 
-use crate::configure::{Config, CrossingMinimization, RankingType};
-use crate::{util::weakly_connected_components, Layout, Layouts};
+use crate::configure::{Config, RankingType};
+use crate::layout::{Layout, RouteStyle, RoutedEdge, SelfLoopGeometry, SimplexExchange};
+use crate::util::weakly_connected_components;
 use p0_cycle_removal as p0;
 use p1_layering as p1;
 use p2_reduce_crossings as p2;
@@ -30,6 +33,8 @@ use p3_calculate_coordinates as p3;
 
 use self::p3_calculate_coordinates::VDir;
 
+pub(crate) use p2_reduce_crossings::bilayer_crossings;
+
 mod p0_cycle_removal;
 mod p1_layering;
 mod p2_reduce_crossings;
@@ -46,11 +51,26 @@ pub(super) struct Vertex {
     parent: Option<NodeIndex>,
     is_tree_vertex: bool,
     is_dummy: bool,
+    /// Whether the vertex is a dummy inserted by [insert_distributors]. Unlike
+    /// the dummies of long edges, it is kept if dummy vertices are removed.
+    is_distributor: bool,
     root: NodeIndex,
     align: NodeIndex,
     shift: f64,
     sink: NodeIndex,
     block_max_vertex_width: f64,
+    pin: Option<Pin>,
+    /// The id of the vertex this vertex should be vertically aligned with.
+    align_with: Option<usize>,
+    /// The rank the vertex should be placed in, relative to the other vertices
+    /// with a fixed rank.
+    fixed_rank: Option<i32>,
+    /// Whether the spanning tree of network simplex is rooted at the vertex,
+    /// which places it on the first rank.
+    ranking_root: bool,
+    /// The rank the initial ranking of the vertex starts from, if it is
+    /// already known to be feasible.
+    initial_rank: Option<i32>,
 }
 
 impl Vertex {
@@ -61,6 +81,41 @@ impl Vertex {
             ..Default::default()
         }
     }
+
+    /// Places the vertex in the first or last layer of the layout.
+    pub(super) fn pin(&mut self, pin: Pin) {
+        self.pin = Some(pin);
+    }
+
+    /// Aligns the vertex vertically with the vertex with id `id`, if possible.
+    pub(super) fn align_with(&mut self, id: usize) {
+        self.align_with = Some(id);
+    }
+
+    /// Places the vertex in rank `rank`, if possible.
+    pub(super) fn fix_rank(&mut self, rank: i32) {
+        self.fixed_rank = Some(rank);
+    }
+
+    /// Roots the spanning tree of network simplex at the vertex and places it
+    /// on the first rank. The vertex needs to be a source.
+    pub(super) fn make_ranking_root(&mut self) {
+        self.ranking_root = true;
+    }
+
+    /// Starts ranking with the vertex in rank `rank`, or further down if its
+    /// incoming edges require it. This skips building an initial ranking for
+    /// graphs whose feasible ranking is already known.
+    pub(super) fn start_at_rank(&mut self, rank: i32) {
+        self.initial_rank = Some(rank);
+    }
+}
+
+/// The layer a vertex is pinned to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Pin {
+    First,
+    Last,
 }
 
 impl Default for Vertex {
@@ -75,11 +130,17 @@ impl Default for Vertex {
             parent: None,
             is_tree_vertex: false,
             is_dummy: false,
+            is_distributor: false,
             root: 0.into(),
             align: 0.into(),
             shift: f64::INFINITY,
             sink: 0.into(),
             block_max_vertex_width: 0.0,
+            pin: None,
+            align_with: None,
+            fixed_rank: None,
+            ranking_root: false,
+            initial_rank: None,
         }
     }
 }
@@ -87,9 +148,20 @@ impl Default for Vertex {
 #[derive(Clone, Copy, Debug)]
 pub(super) struct Edge {
     weight: i32,
-    cut_value: Option<i32>,
+    cut_value: Option<i64>,
     is_tree_edge: bool,
     has_type_1_conflict: bool,
+    reversed: bool,
+    /// Overrides the minimum length of the graph for this edge during ranking.
+    minimum_length: Option<i32>,
+    route_style: RouteStyle,
+    /// Whether no other edge may cross this edge.
+    uncrossed: bool,
+    /// The number of parallel edges of the input graph this edge represents.
+    multiplicity: usize,
+    /// During coordinate assignment, vertices are aligned along edges with a
+    /// higher priority first, which keeps chains of such edges vertical.
+    priority: u32,
 }
 
 impl Default for Edge {
@@ -99,18 +171,187 @@ impl Default for Edge {
             cut_value: None,
             is_tree_edge: false,
             has_type_1_conflict: false,
+            reversed: false,
+            minimum_length: None,
+            route_style: RouteStyle::Through,
+            uncrossed: false,
+            multiplicity: 1,
+            priority: 0,
         }
     }
 }
 
-pub(super) fn start(mut graph: StableDiGraph<Vertex, Edge>, config: &Config) -> Layouts<usize> {
+impl Edge {
+    pub(super) fn with_route_style(route_style: RouteStyle) -> Self {
+        Self {
+            route_style,
+            ..Default::default()
+        }
+    }
+
+    /// Sets the weight of the edge. Heavier edges are kept shorter during
+    /// ranking and straighter during crossing reduction and coordinate
+    /// assignment.
+    pub(super) fn with_weight(self, weight: i32) -> Self {
+        Self { weight, ..self }
+    }
+
+    /// Sets the minimum number of ranks the edge needs to span, overriding
+    /// the minimum length of the graph.
+    pub(super) fn with_minimum_length(self, minimum_length: i32) -> Self {
+        Self {
+            minimum_length: Some(minimum_length),
+            ..self
+        }
+    }
+
+    /// Sets the priority of the edge during coordinate assignment.
+    pub(super) fn with_priority(self, priority: u32) -> Self {
+        Self { priority, ..self }
+    }
+
+    /// Sets whether the edge must not be crossed by any other edge.
+    pub(super) fn with_uncrossed(self, uncrossed: bool) -> Self {
+        Self { uncrossed, ..self }
+    }
+
+    /// Returns the minimum number of ranks the edge needs to span, which is
+    /// `default` unless it is overridden for this edge.
+    fn minimum_length(&self, default: i32) -> i32 {
+        self.minimum_length.unwrap_or(default)
+    }
+}
+
+/// Evaluates `$phase` and, if the `timing` feature is enabled, records how long
+/// it took in `$timings.$field`.
+macro_rules! time_phase {
+    ($timings:ident.$field:ident, $phase:expr) => {{
+        #[cfg(feature = "timing")]
+        let start = std::time::Instant::now();
+        let result = $phase;
+        #[cfg(feature = "timing")]
+        {
+            $timings.$field = start.elapsed();
+        }
+        result
+    }};
+}
+
+/// Calculates the layout of each weakly connected component of `graph`.
+///
+/// `order_constraints` contains pairs of vertex ids `(a, b)`, where `a` has
+/// to be placed left of `b` if both are in the same layer. They need to be
+/// transitively closed and free of cycles.
+pub(super) fn start(
+    mut graph: StableDiGraph<Vertex, Edge>,
+    config: &Config,
+    order_constraints: &HashSet<(usize, usize)>,
+) -> Vec<Layout<usize>> {
     init_graph(&mut graph);
     weakly_connected_components(graph)
         .into_iter()
-        .map(|g| build_layout(g, config))
+        .map(|g| {
+            if config.optimize_area {
+                build_smallest_layout(g, config, order_constraints)
+            } else {
+                build_layout(g, config, order_constraints)
+            }
+        })
         .collect()
 }
 
+/// Builds a layout for each ranking type, with and without limiting the width
+/// of the layers to the square root of the number of vertices, and returns the
+/// one with the smallest area. All other settings of `config` are kept, and a
+/// smaller maximum layer width is never raised. Prefers the layout built with
+/// `config` if multiple layouts have the same area.
+fn build_smallest_layout(
+    graph: StableDiGraph<Vertex, Edge>,
+    config: &Config,
+    order_constraints: &HashSet<(usize, usize)>,
+) -> Layout<usize> {
+    let square_width = (graph.node_count() as f64).sqrt().ceil() as usize;
+    let split_width = config
+        .max_layer_width
+        .map_or(square_width, |width| width.min(square_width));
+    let ranking_types = [
+        config.ranking_type,
+        RankingType::MinimizeEdgeLength,
+        RankingType::Original,
+        RankingType::Up,
+        RankingType::Down,
+        RankingType::CoffmanGraham,
+    ];
+    let mut candidates = Vec::new();
+    for ranking_type in ranking_types {
+        for max_layer_width in [config.max_layer_width, Some(split_width)] {
+            if !candidates.contains(&(ranking_type, max_layer_width)) {
+                candidates.push((ranking_type, max_layer_width));
+            }
+        }
+    }
+
+    // the first candidate is `config` itself, which is kept on ties
+    candidates
+        .into_iter()
+        .skip(1)
+        .map(|(ranking_type, max_layer_width)| {
+            let candidate = Config {
+                ranking_type,
+                max_layer_width,
+                ..*config
+            };
+            build_layout(graph.clone(), &candidate, order_constraints)
+        })
+        .fold(
+            build_layout(graph.clone(), config, order_constraints),
+            |smallest, layout| {
+                if area(&layout) < area(&smallest) {
+                    layout
+                } else {
+                    smallest
+                }
+            },
+        )
+}
+
+/// Returns the area of the bounding box of all vertices, including their
+/// sizes, and all edge routes.
+fn area(layout: &Layout<usize>) -> f64 {
+    let ((min_x, min_y), (max_x, max_y)) = layout.bounding_box();
+    let (min_x, min_y, max_x, max_y) = layout.vertices.iter().zip(&layout.sizes).fold(
+        (min_x, min_y, max_x, max_y),
+        |(min_x, min_y, max_x, max_y), ((_, (x, y)), (w, h))| {
+            (
+                min_x.min(x - w / 2.0),
+                min_y.min(y - h / 2.0),
+                max_x.max(x + w / 2.0),
+                max_y.max(y + h / 2.0),
+            )
+        },
+    );
+    (max_x - min_x) * (max_y - min_y)
+}
+
+/// Returns the number of layers of the initial longest path ranking, which
+/// skips all phases after cycle removal and initial ranking.
+pub(super) fn estimate_layers(mut graph: StableDiGraph<Vertex, Edge>, config: &Config) -> usize {
+    info!(target: "layouting", "Estimating number of layers");
+    graph.retain_edges(|g, e| g.edge_endpoints(e).is_some_and(|(t, h)| t != h));
+    execute_phase_0(&mut graph);
+    if let Some(reversed_minimum_length) = config.reversed_minimum_length {
+        for edge in graph.edge_weights_mut().filter(|e| e.reversed) {
+            edge.minimum_length = Some(reversed_minimum_length as i32);
+        }
+    }
+    p1::ranking::init_rank(&mut graph, config.minimum_length as i32);
+    graph
+        .node_weights()
+        .map(|v| v.rank as usize + 1)
+        .max()
+        .unwrap_or(0)
+}
+
 fn init_graph(graph: &mut StableDiGraph<Vertex, Edge>) {
     info!("Initializing graphs vertex weights");
     for id in graph.node_indices().collect::<Vec<_>>() {
@@ -121,7 +362,11 @@ fn init_graph(graph: &mut StableDiGraph<Vertex, Edge>) {
     }
 }
 
-fn build_layout(mut graph: StableDiGraph<Vertex, Edge>, config: &Config) -> Layout {
+fn build_layout(
+    mut graph: StableDiGraph<Vertex, Edge>,
+    config: &Config,
+    order_constraints: &HashSet<(usize, usize)>,
+) -> Layout<usize> {
     info!(target: "layouting", "Start building layout");
     info!(target: "layouting", "Configuration is: {:?}", config);
 
@@ -133,78 +378,518 @@ fn build_layout(mut graph: StableDiGraph<Vertex, Edge>, config: &Config) -> Layo
         vertex.size.1 += config.vertex_spacing;
     }
 
-    // we don't remember the edges that where reversed for now, since they are
-    // currently not needed
-    let _ = execute_phase_0(&mut graph);
+    // map constraints to the vertices of this component
+    let id_to_vertex = graph
+        .node_indices()
+        .map(|v| (graph[v].id, v))
+        .collect::<HashMap<_, _>>();
+    let order_constraints = order_constraints
+        .iter()
+        .filter_map(|(a, b)| Some((*id_to_vertex.get(a)?, *id_to_vertex.get(b)?)))
+        .collect();
+
+    // self-loops don't influence the layout, they are drawn next to their vertex
+    let self_loops = graph
+        .edge_indices()
+        .filter_map(|e| graph.edge_endpoints(e).filter(|(t, h)| t == h).map(|_| e))
+        .collect::<Vec<_>>();
+    let self_loops = self_loops
+        .into_iter()
+        .map(|e| {
+            let (v, _) = graph.edge_endpoints(e).unwrap();
+            graph.remove_edge(e);
+            graph[v].id
+        })
+        .collect::<Vec<_>>();
+    merge_parallel_edges(&mut graph);
+
+    #[cfg(feature = "timing")]
+    let mut timings = crate::layout::PhaseTimings::default();
 
-    execute_phase_1(
-        &mut graph,
+    // reversed edges are marked, so we don't need to remember them here
+    let _ = time_phase!(timings.cycle_removal, execute_phase_0(&mut graph));
+    let minimum_lengths = minimum_lengths(
+        &graph,
         config.minimum_length as i32,
-        config.ranking_type,
+        config.reversed_minimum_length.map(|l| l as i32),
     );
+    if let Some(max_fan_out) = config.max_fan_out.filter(|n| *n > 1) {
+        let dummy_size = if config.dummy_vertices {
+            config.dummy_size
+        } else {
+            0.0
+        };
+        insert_distributors(&mut graph, max_fan_out, dummy_size);
+    }
 
-    let layers = execute_phase_2(
-        &mut graph,
-        config.minimum_length as i32,
-        config.dummy_vertices.then_some(config.dummy_size),
-        config.c_minimization,
-        config.transpose,
+    #[cfg_attr(not(feature = "trace"), allow(unused_variables))]
+    let (simplex_trace, simplex_iterations, max_abs_cutvalue) =
+        time_phase!(timings.ranking, execute_phase_1(&mut graph, config));
+
+    let (layers, dummy_size) = time_phase!(
+        timings.crossing_reduction,
+        execute_phase_2(&mut graph, config, &order_constraints)
     );
 
-    let layout = execute_phase_3(&mut graph, layers);
+    let mut layout = time_phase!(
+        timings.coordinate_assignment,
+        execute_phase_3(
+            &mut graph,
+            layers,
+            config.vertex_spacing,
+            dummy_size,
+            config.parallel_edge_spacing
+        )
+    );
+    layout.minimum_lengths = layout
+        .edges
+        .iter()
+        .map(|e| {
+            let key = if e.reversed {
+                (e.head, e.tail)
+            } else {
+                (e.tail, e.head)
+            };
+            minimum_lengths
+                .get(&key)
+                .copied()
+                .unwrap_or(config.minimum_length as usize)
+        })
+        .collect();
+    layout.self_loops = self_loop_geometry(&layout, &self_loops, config.self_loop_size);
+    layout.simplex_iterations = simplex_iterations;
+    layout.max_abs_cutvalue = max_abs_cutvalue;
+    #[cfg(feature = "trace")]
+    {
+        layout.simplex_trace = simplex_trace;
+    }
+    #[cfg(feature = "timing")]
+    {
+        debug!(target: "layouting", "Phase timings: {:?}", timings);
+        layout.timings = timings;
+    }
     debug!(target: "layouting", "Coordinates: {:?}\nwidth: {}, height:{}",
-        layout.0,
-        layout.1,
-        layout.2
+        layout.vertices,
+        layout.width,
+        layout.height
     );
     layout
 }
 
+/// Returns the minimum number of ranks each edge of the acyclic graph spans,
+/// by the ids of its tail and head. Of parallel edges, the largest minimum
+/// length is kept.
+fn minimum_lengths(
+    graph: &StableDiGraph<Vertex, Edge>,
+    minimum_length: i32,
+    reversed_minimum_length: Option<i32>,
+) -> HashMap<(usize, usize), usize> {
+    let mut minimum_lengths = HashMap::new();
+    for edge in graph.edge_indices() {
+        let (tail, head) = graph.edge_endpoints(edge).unwrap();
+        let edge_minimum_length = match reversed_minimum_length {
+            Some(reversed_minimum_length) if graph[edge].reversed => reversed_minimum_length,
+            _ => graph[edge].minimum_length(minimum_length),
+        };
+        let entry = minimum_lengths
+            .entry((graph[tail].id, graph[head].id))
+            .or_default();
+        *entry = (edge_minimum_length.max(0) as usize).max(*entry);
+    }
+    minimum_lengths
+}
+
+/// Merges edges with the same tail and head into a single edge, which keeps
+/// the largest weight of them, so ranking handles it exactly like a single
+/// edge. Only edges with the same route style, minimum length and which are
+/// either all uncrossed or not are merged. The number of merged edges is kept
+/// as the multiplicity of the edge, which crossing reduction weighs the edge
+/// with and [execute_phase_3] uses to restore them.
+fn merge_parallel_edges(graph: &mut StableDiGraph<Vertex, Edge>) {
+    let mut merged = HashMap::<_, Vec<EdgeIndex>>::new();
+    for e in graph.edge_indices().collect::<Vec<_>>() {
+        let endpoints = graph.edge_endpoints(e).unwrap();
+        let Edge {
+            weight,
+            minimum_length,
+            route_style,
+            uncrossed,
+            priority,
+            ..
+        } = graph[e];
+        let kept = merged.entry(endpoints).or_default();
+        match kept.iter().find(|k| {
+            let k = &graph[**k];
+            k.minimum_length == minimum_length
+                && k.route_style == route_style
+                && k.uncrossed == uncrossed
+        }) {
+            Some(k) => {
+                let k = *k;
+                graph.remove_edge(e);
+                graph[k].weight = graph[k].weight.max(weight);
+                graph[k].multiplicity += 1;
+                graph[k].priority = graph[k].priority.max(priority);
+            }
+            None => kept.push(e),
+        }
+    }
+}
+
+/// Groups the children of vertices with more than `max_fan_out` outgoing
+/// edges below dummy vertices, so that no vertex has more than `max_fan_out`
+/// children. If there are more than `max_fan_out` groups, the dummy vertices
+/// are grouped again, forming a tree. Only edges in their original direction
+/// are grouped, edges reversed to remove cycles and edges routed along the
+/// side keep their tail.
+fn insert_distributors(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    max_fan_out: usize,
+    dummy_size: f64,
+) {
+    for v in graph.node_indices().collect::<Vec<_>>() {
+        let mut children = graph
+            .edges_directed(v, Outgoing)
+            .filter(|e| !e.weight().reversed && e.weight().route_style == RouteStyle::Through)
+            .map(|e| e.id())
+            .collect::<Vec<_>>();
+        if children.len() <= max_fan_out {
+            continue;
+        }
+        debug!(target: "layouting",
+            "Grouping {} children of vertex {} below distributors",
+            children.len(),
+            v.index()
+        );
+        children.sort_by_key(|e| graph.edge_endpoints(*e).unwrap().1);
+        while children.len() > max_fan_out {
+            children = children
+                .chunks(max_fan_out)
+                .map(|group| {
+                    let d = graph.add_node(Vertex {
+                        is_dummy: true,
+                        is_distributor: true,
+                        size: (dummy_size, 0.0),
+                        ..Default::default()
+                    });
+                    graph[d].align = d;
+                    graph[d].root = d;
+                    graph[d].sink = d;
+                    for e in group {
+                        let head = graph.edge_endpoints(*e).unwrap().1;
+                        let edge = graph.remove_edge(*e).unwrap();
+                        graph.add_edge(d, head, edge);
+                    }
+                    graph.add_edge(v, d, Edge::default())
+                })
+                .collect();
+        }
+    }
+}
+
+/// Returns the edges of each path from `tail` through dummy vertices to a
+/// vertex of the input graph, ordered by the outgoing edges of `tail`. Each
+/// path corresponds to one edge of the input graph. Paths are chains, unless
+/// they start below a distributor inserted by [insert_distributors].
+fn dummy_paths(graph: &StableDiGraph<Vertex, Edge>, tail: NodeIndex) -> Vec<Vec<EdgeIndex>> {
+    let mut paths = Vec::new();
+    let mut stack = graph
+        .edges_directed(tail, Outgoing)
+        .map(|e| vec![e.id()])
+        .collect::<Vec<_>>();
+    stack.reverse();
+    while let Some(path) = stack.pop() {
+        let head = graph.edge_endpoints(path[path.len() - 1]).unwrap().1;
+        if !graph[head].is_dummy {
+            paths.push(path);
+            continue;
+        }
+        let mut next = graph
+            .edges_directed(head, Outgoing)
+            .map(|e| [path.as_slice(), &[e.id()]].concat())
+            .collect::<Vec<_>>();
+        next.reverse();
+        stack.append(&mut next);
+    }
+    paths
+}
+
+/// Places a self-loop of `size` at the right side of each vertex in
+/// `self_loops`. Multiple self-loops of a vertex are nested.
+fn self_loop_geometry(
+    layout: &Layout<usize>,
+    self_loops: &[usize],
+    size: f64,
+) -> Vec<(usize, SelfLoopGeometry)> {
+    let mut count = HashMap::new();
+    self_loops
+        .iter()
+        .map(|id| {
+            let i = layout.vertices.iter().position(|(v, _)| v == id).unwrap();
+            let ((x, y), (width, _)) = (layout.vertices[i].1, layout.sizes[i]);
+            let nesting = count.entry(*id).or_insert(0);
+            *nesting += 1;
+            let radius = size / 2.0 * *nesting as f64;
+            let center = (x + width / 2.0, y);
+            let geometry = SelfLoopGeometry {
+                center,
+                radius,
+                start: (center.0, y - radius),
+                end: (center.0, y + radius),
+            };
+            (*id, geometry)
+        })
+        .collect()
+}
+
 fn execute_phase_0(graph: &mut StableDiGraph<Vertex, Edge>) -> Vec<EdgeIndex> {
     info!(target: "layouting", "Executing phase 0: Cycle Removal");
     p0::remove_cycles(graph)
 }
 
-/// Assign each vertex a rank
+/// Assign each vertex a rank, as configured by `config`. Reversed edges span
+/// at least `reversed_minimum_length` ranks instead of `minimum_length`, if it is
+/// [Some]. If `virtual_super_source` is true, all sources
+/// are connected to a temporary vertex, which is removed after ranking. If
+/// `balance_ranks` is true, vertices which can be moved without changing the
+/// total edge length are centered between their neighbors. Ranks
+/// with more than `max_layer_width` vertices are split afterwards, according to `layer_splitting`.
+/// If `target_aspect` is [Some], ranks are split as well, until the ratio of
+/// the widest rank to the number of ranks is at most `target_aspect`.
+/// Finally, pinned vertices are moved to the first or last rank. Ranking is
+/// skipped if the initial ranking already spans each edge with its minimum
+/// length.
+///
+/// Returns the `(leaving, entering)` tree edges of each exchange done by
+/// network simplex, as `(tail, head)` ids of the vertices of the acyclic
+/// graph, together with the number of exchanges and the most negative cut
+/// value left, see [Layout::max_abs_cutvalue]. Exchanges involving the
+/// virtual super source, the rank anchor or the edges connecting the root are
+/// left out of the trace, but not the count.
 fn execute_phase_1(
     graph: &mut StableDiGraph<Vertex, Edge>,
-    minimum_length: i32,
-    ranking_type: RankingType,
-) {
+    config: &Config,
+) -> (Vec<SimplexExchange<usize>>, usize, i32) {
     info!(target: "layouting", "Executing phase 1: Ranking");
-    p1::rank(graph, minimum_length, ranking_type);
+    let minimum_length = config.minimum_length as i32;
+    let ranking_type = config.ranking_type;
+    if let Some(reversed_minimum_length) = config.reversed_minimum_length {
+        for edge in graph.edge_weights_mut().filter(|e| e.reversed) {
+            edge.minimum_length = Some(reversed_minimum_length as i32);
+        }
+    }
+    let root = graph.node_indices().find(|v| graph[*v].ranking_root);
+    let root_edges = match root {
+        Some(root) if ranking_type == RankingType::MinimizeEdgeLength => {
+            add_root_edges(graph, root)
+        }
+        _ => Vec::new(),
+    };
+    let super_source =
+        if config.virtual_super_source && ranking_type == RankingType::MinimizeEdgeLength {
+            add_super_source(graph)
+        } else {
+            None
+        };
+    let anchor = add_rank_anchor(graph);
+    // the initial ranking of a properly layered graph is already optimal, but
+    // it may still exceed the width of the Coffman-Graham ranking
+    p1::ranking::init_rank(graph, minimum_length);
+    let exchanges = if ranking_type != RankingType::CoffmanGraham
+        && is_properly_layered(graph, minimum_length)
+    {
+        info!(target: "layouting", "All edges are tight, skipping ranking");
+        Vec::new()
+    } else {
+        p1::rank(
+            graph,
+            config,
+            // root the spanning tree at the virtual vertex, if there is one
+            super_source.or(anchor).or(root),
+        )
+    };
+    if config.balance_ranks && ranking_type == RankingType::MinimizeEdgeLength {
+        p1::balance(graph, minimum_length);
+    }
+    let endpoints = |edge| {
+        let (tail, head) = graph.edge_endpoints(edge).unwrap();
+        let is_virtual = |v| Some(v) == super_source || Some(v) == anchor;
+        (!is_virtual(tail) && !is_virtual(head) && !root_edges.contains(&edge))
+            .then_some((graph[tail].id, graph[head].id))
+    };
+    let simplex_iterations = exchanges.len();
+    // the virtual edges are still part of the tree the cut values belong to
+    let max_abs_cutvalue = i32::try_from(p1::max_abs_cutvalue(graph)).unwrap_or(i32::MAX);
+    let simplex_trace = exchanges
+        .into_iter()
+        .filter_map(|(leaving, entering)| Some((endpoints(leaving)?, endpoints(entering)?)))
+        .collect();
+    for edge in root_edges {
+        graph.remove_edge(edge);
+    }
+    if super_source.is_some() || anchor.is_some() {
+        for v in super_source.into_iter().chain(anchor) {
+            graph.remove_node(v);
+        }
+        p1::normalize(graph);
+    }
+    let aspect_width = config
+        .target_aspect
+        .and_then(|a| p1::width_for_aspect(graph, a));
+    if let Some(max_layer_width) = config.max_layer_width.into_iter().chain(aspect_width).min() {
+        p1::split_wide_ranks(graph, max_layer_width, config.layer_splitting);
+    }
+    p1::apply_pins(graph);
+    // the following phases split all edges longer than the minimum length
+    for edge in graph.edge_weights_mut() {
+        edge.minimum_length = None;
+    }
+    (simplex_trace, simplex_iterations, max_abs_cutvalue)
+}
+
+/// Connects `root` to all other vertices via edges of length 0 without
+/// weight, so no vertex can be ranked above it. Returns the added edges.
+fn add_root_edges(graph: &mut StableDiGraph<Vertex, Edge>, root: NodeIndex) -> Vec<EdgeIndex> {
+    debug!(target: "layouting", "Keeping all vertices below ranking root {}", root.index());
+    let vertices = graph
+        .node_indices()
+        .filter(|v| *v != root)
+        .collect::<Vec<_>>();
+    vertices
+        .into_iter()
+        .map(|v| {
+            graph.add_edge(
+                root,
+                v,
+                Edge::default().with_weight(0).with_minimum_length(0),
+            )
+        })
+        .collect()
+}
+
+/// Connects all sources of the graph to a new vertex, if there is more than
+/// one source. The edges are weighted heavier than all other edges combined,
+/// so minimizing edge lengths always keeps them tight.
+fn add_super_source(graph: &mut StableDiGraph<Vertex, Edge>) -> Option<NodeIndex> {
+    let sources = graph
+        .node_indices()
+        .filter(|v| graph.neighbors_directed(*v, Incoming).next().is_none())
+        .collect::<Vec<_>>();
+    if sources.len() < 2 {
+        return None;
+    }
+    debug!(target: "layouting", "Connecting {} sources to virtual super source", sources.len());
+    let weight = heavier_than_all_edges(graph);
+    let super_source = graph.add_node(Vertex::default());
+    graph[super_source].root = super_source;
+    graph[super_source].align = super_source;
+    graph[super_source].sink = super_source;
+    for source in sources {
+        graph.add_edge(
+            super_source,
+            source,
+            Edge {
+                weight,
+                ..Default::default()
+            },
+        );
+    }
+    Some(super_source)
+}
+
+/// Connects all vertices to a new vertex, if any vertex has a fixed rank.
+/// Each vertex with a fixed rank is placed that many ranks below the anchor
+/// via an edge weighted heavier than all other edges combined, so minimizing
+/// edge lengths keeps it tight. All other vertices are kept below the anchor
+/// via edges of length 0 without weight.
+fn add_rank_anchor(graph: &mut StableDiGraph<Vertex, Edge>) -> Option<NodeIndex> {
+    if graph.node_weights().all(|v| v.fixed_rank.is_none()) {
+        return None;
+    }
+    debug!(target: "layouting", "Connecting vertices to virtual rank anchor");
+    let weight = heavier_than_all_edges(graph);
+    let vertices = graph.node_indices().collect::<Vec<_>>();
+    let anchor = graph.add_node(Vertex::default());
+    graph[anchor].root = anchor;
+    graph[anchor].align = anchor;
+    graph[anchor].sink = anchor;
+    for v in vertices {
+        let edge = match graph[v].fixed_rank {
+            Some(rank) => Edge::default()
+                .with_weight(weight)
+                .with_minimum_length(rank),
+            None => Edge::default().with_weight(0).with_minimum_length(0),
+        };
+        graph.add_edge(anchor, v, edge);
+    }
+    Some(anchor)
 }
 
-/// Reorder vertices in ranks to reduce crossings. If `dummy_size` is [Some],
-/// dummies will be passed along to the next phase.
+/// Returns a weight heavier than all edges of the graph combined, which is
+/// capped at [i32::MAX].
+fn heavier_than_all_edges(graph: &StableDiGraph<Vertex, Edge>) -> i32 {
+    graph
+        .edge_weights()
+        .fold(1, |weight: i32, e| weight.saturating_add(e.weight))
+}
+
+/// Reorder vertices in ranks to reduce crossings, as configured by `config`,
+/// while keeping the `order_constraints`. If `dummy_vertices` is set,
+/// dummies will be passed along to the next phase. If more than `max_dummies`
+/// dummies would be needed, none are inserted. `median_weight` is only used
+/// by the median heuristic. If `adaptive_sweeps` is set, the number of sweeps
+/// depends on the size of the graph. At most `max_sweeps` sweeps are done, if
+/// it is [Some]. If `stable_source_order` is set, ties are broken by the order
+/// of the sources.
+///
+/// Returns the order of each rank, together with the size of the dummy
+/// vertices, if they are passed along.
 fn execute_phase_2(
     graph: &mut StableDiGraph<Vertex, Edge>,
-    minimum_length: i32,
-    dummy_size: Option<f64>,
-    crossing_minimization: CrossingMinimization,
-    transpose: bool,
-) -> Vec<Vec<NodeIndex>> {
+    config: &Config,
+    order_constraints: &p2::OrderConstraints,
+) -> (Vec<Vec<NodeIndex>>, Option<f64>) {
     info!(target: "layouting", "Executing phase 2: Crossing Reduction");
+    let minimum_length = config.minimum_length as i32;
+    let mut dummy_size = config.dummy_vertices.then_some(config.dummy_size);
     info!(target: "layouting",
         "dummy vertex size: {:?}, heuristic for crossing minimization: {:?}, using transpose: {}",
         dummy_size,
-        crossing_minimization,
-        transpose
+        config.c_minimization,
+        config.transpose
     );
 
-    p2::insert_dummy_vertices(graph, minimum_length, dummy_size.unwrap_or(0.0));
-    let mut order = p2::ordering(graph, crossing_minimization, transpose);
+    let dummy_count = p2::count_dummy_vertices(graph, minimum_length);
+    if is_properly_layered(graph, minimum_length) {
+        info!(target: "layouting", "Graph is properly layered, no dummy vertices needed");
+    } else if config.max_dummies.is_some_and(|max| dummy_count > max) {
+        info!(target: "layouting",
+            "{dummy_count} dummy vertices exceed the maximum of {}, routing long edges directly",
+            config.max_dummies.unwrap()
+        );
+        dummy_size = None;
+    } else {
+        p2::insert_dummy_vertices(graph, minimum_length, dummy_size.unwrap_or(0.0));
+    }
+    let mut order = p2::ordering(graph, order_constraints, &config.into());
+    p2::separate_uncrossed_edges(graph, &mut order, order_constraints);
     if dummy_size.is_none() {
         p2::remove_dummy_vertices(graph, &mut order);
     }
-    order
+    (order, dummy_size)
 }
 
 /// calculate the final coordinates for each vertex, after the graph was layered and crossings where minimized.
+/// `vertex_spacing` is subtracted from the vertex sizes again, so the layout contains the original sizes.
+/// `dummy_size` is the size of the dummy vertices, if there are any.
+/// Parallel edges are separated by `parallel_edge_spacing`.
 fn execute_phase_3(
     graph: &mut StableDiGraph<Vertex, Edge>,
     mut layers: Vec<Vec<NodeIndex>>,
-) -> Layout {
+    vertex_spacing: f64,
+    dummy_size: Option<f64>,
+    parallel_edge_spacing: f64,
+) -> Layout<usize> {
     info!(target: "layouting", "Executing phase 3: Coordinate Calculation");
     for n in graph.node_indices().collect::<Vec<_>>() {
         if graph[n].is_dummy {
@@ -213,6 +898,7 @@ fn execute_phase_3(
     }
     let width = layers.iter().map(|l| l.len()).max().unwrap_or(0) as f64;
     let height = layers.len() as f64;
+    let layer_crossings = p2::crossings(graph, &layers);
     let mut layouts = p3::create_layouts(graph, &mut layers);
 
     p3::align_to_smallest_width_layout(&mut layouts);
@@ -249,29 +935,276 @@ fn execute_phase_3(
         current_rank_top_offset += max_height;
     }
 
-    let mut v = x_coordinates.iter().collect::<Vec<_>>();
-    v.sort_by(|a, b| a.0.index().cmp(&b.0.index()));
-    // format to NodeIndex: (x, y), width, height
-    (
-        x_coordinates
-            .into_iter()
-            .filter(|(v, _)| !graph[*v].is_dummy)
-            // calculate y coordinate
-            .map(|(v, x)| {
-                (
-                    graph[v].id,
-                    (x, *rank_to_y_offset.get(&graph[v].rank).unwrap()),
-                )
-            })
-            .collect::<Vec<_>>(),
+    let coordinates = x_coordinates
+        .into_iter()
+        // calculate y coordinate
+        .map(|(v, x)| (v, (x, *rank_to_y_offset.get(&graph[v].rank).unwrap())))
+        .collect::<HashMap<_, _>>();
+    let mut edges = route_edges(graph, &coordinates);
+    route_side_edges(graph, &coordinates, &mut edges);
+    let mut edges = restore_parallel_edges(graph, edges);
+    let edge_dummies = restore_parallel_edges(graph, edge_dummies(graph));
+    if parallel_edge_spacing > 0.0 {
+        separate_parallel_edges(&mut edges, parallel_edge_spacing);
+    }
+
+    // order the vertices of each layer from left to right
+    let mut layers_with_dummies = vec![Vec::new(); layers.len()];
+    for (v, (x, _)) in &coordinates {
+        layers_with_dummies[graph[*v].rank as usize].push((*x, *v));
+    }
+    let layers_with_dummies = layers_with_dummies
+        .into_iter()
+        .map(|mut l| {
+            l.sort_by(|a, b| a.0.total_cmp(&b.0));
+            l.into_iter().map(|(_, v)| v).collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let positions = layers_with_dummies
+        .iter()
+        .flat_map(|l| l.iter().enumerate().map(|(pos, v)| (*v, pos)))
+        .collect::<HashMap<_, _>>();
+    let mut layer_edges = vec![Vec::new(); layers_with_dummies.len().saturating_sub(1)];
+    for edge in graph.edge_indices() {
+        let (tail, head) = graph.edge_endpoints(edge).unwrap();
+        if graph[head].rank == graph[tail].rank + 1 {
+            layer_edges[graph[tail].rank as usize].push((positions[&tail], positions[&head]));
+        }
+    }
+    let edge_dummies = edge_dummies
+        .into_iter()
+        .map(|dummies| {
+            dummies
+                .into_iter()
+                .map(|d| (graph[d].rank as usize, positions[&d]))
+                .collect()
+        })
+        .collect();
+    let layer_xs = layers_with_dummies
+        .iter()
+        .map(|l| l.iter().map(|v| coordinates[v].0).collect())
+        .collect();
+    let layers_with_dummies = layers_with_dummies
+        .into_iter()
+        .map(|l| {
+            l.into_iter()
+                .map(|v| (graph[v].id, graph[v].is_dummy))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let layers = layers_with_dummies
+        .iter()
+        .map(|l| {
+            l.iter()
+                .filter(|(_, is_dummy)| !is_dummy)
+                .map(|(id, _)| *id)
+                .collect()
+        })
+        .collect();
+
+    let (vertices, sizes) = coordinates
+        .into_iter()
+        .filter(|(v, _)| !graph[*v].is_dummy)
+        .map(|(v, coords)| {
+            let (width, height) = graph[v].size;
+            (
+                (graph[v].id, coords),
+                (width - vertex_spacing, height - vertex_spacing),
+            )
+        })
+        .unzip();
+
+    Layout {
+        vertices,
+        sizes,
+        edges,
+        layers,
+        layers_with_dummies,
+        layer_xs,
+        layer_edges,
+        edge_dummies,
+        minimum_lengths: Vec::new(),
+        vertex_spacing,
+        dummy_size,
+        truncated_edges: Vec::new(),
+        self_loops: Vec::new(),
         width,
         height,
-    )
+        simplex_iterations: 0,
+        max_abs_cutvalue: 0,
+        layer_crossings,
+        #[cfg(feature = "timing")]
+        timings: Default::default(),
+        #[cfg(feature = "trace")]
+        simplex_trace: Vec::new(),
+    }
+}
+
+/// Follows the edges of each vertex through dummy vertices, in order to
+/// determine the points each edge of the input graph is routed through.
+fn route_edges(
+    graph: &StableDiGraph<Vertex, Edge>,
+    coordinates: &HashMap<NodeIndex, (f64, f64)>,
+) -> Vec<RoutedEdge<usize>> {
+    let mut edges = Vec::new();
+    for tail in graph.node_indices().filter(|v| !graph[*v].is_dummy) {
+        for path in dummy_paths(graph, tail) {
+            let mut points = vec![coordinates[&tail]];
+            let mut head = tail;
+            for edge in &path {
+                head = graph.edge_endpoints(*edge).unwrap().1;
+                points.push(coordinates[&head]);
+            }
+
+            // present reversed edges in their original direction
+            let reversed = graph[path[0]].reversed;
+            let (tail, head) = if reversed {
+                points.reverse();
+                (head, tail)
+            } else {
+                (tail, head)
+            };
+            edges.push(RoutedEdge {
+                tail: graph[tail].id,
+                head: graph[head].id,
+                points,
+                reversed,
+            });
+        }
+    }
+    edges
+}
+
+/// Returns the dummy vertices each edge returned by [route_edges] passes
+/// through, ordered like its points.
+fn edge_dummies(graph: &StableDiGraph<Vertex, Edge>) -> Vec<Vec<NodeIndex>> {
+    graph
+        .node_indices()
+        .filter(|v| !graph[*v].is_dummy)
+        .flat_map(|tail| dummy_paths(graph, tail))
+        .map(|path| {
+            let mut dummies = path
+                .iter()
+                .map(|e| graph.edge_endpoints(*e).unwrap().1)
+                .filter(|v| graph[*v].is_dummy)
+                .collect::<Vec<_>>();
+            if graph[path[0]].reversed {
+                dummies.reverse();
+            }
+            dummies
+        })
+        .collect()
+}
+
+/// Adds a point at the left or right margin of each layer crossed by an edge
+/// with [RouteStyle::Side], depending on which margin is closer to its tail
+/// and head. Edges are expected in the order returned by [route_edges].
+fn route_side_edges(
+    graph: &StableDiGraph<Vertex, Edge>,
+    coordinates: &HashMap<NodeIndex, (f64, f64)>,
+    edges: &mut [RoutedEdge<usize>],
+) {
+    // left margin, right margin and y coordinate of each rank
+    let mut margins = HashMap::<i32, (f64, f64, f64)>::new();
+    for (v, (x, y)) in coordinates {
+        let half_width = graph[*v].size.0 / 2.0;
+        let margin =
+            margins
+                .entry(graph[*v].rank)
+                .or_insert((f64::INFINITY, f64::NEG_INFINITY, *y));
+        margin.0 = margin.0.min(x - half_width);
+        margin.1 = margin.1.max(x + half_width);
+    }
+    let (left, right) = margins
+        .values()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(l, r), m| {
+            (l.min(m.0), r.max(m.1))
+        });
+
+    let side_edges = graph
+        .node_indices()
+        .filter(|v| !graph[*v].is_dummy)
+        .flat_map(|v| dummy_paths(graph, v))
+        .enumerate()
+        .filter(|(_, path)| path.len() == 1 && graph[path[0]].route_style == RouteStyle::Side);
+    for (i, path) in side_edges {
+        let (tail, head) = graph.edge_endpoints(path[0]).unwrap();
+        let (upper, lower) = (graph[tail].rank, graph[head].rank);
+        let points = &mut edges[i].points;
+        let (first, last) = (points[0], points[points.len() - 1]);
+        let is_left = (first.0 + last.0) / 2.0 - left < right - (first.0 + last.0) / 2.0;
+        let mut waypoints = (upper + 1..lower)
+            .filter_map(|r| margins.get(&r))
+            .map(|(l, r, y)| (if is_left { *l } else { *r }, *y))
+            .collect::<Vec<_>>();
+        // points of reversed edges are already ordered from head to tail
+        if first.1 > last.1 {
+            waypoints.reverse();
+        }
+        points.splice(1..points.len() - 1, waypoints);
+    }
+}
+
+/// Repeats the route of each edge merged by [merge_parallel_edges], or any
+/// other data given per edge, once for each edge of the input graph it
+/// represents. Edges are expected in the order returned by [route_edges].
+fn restore_parallel_edges<E: Clone>(graph: &StableDiGraph<Vertex, Edge>, edges: Vec<E>) -> Vec<E> {
+    // the last edge of a path below a distributor is the original edge
+    let multiplicities = graph
+        .node_indices()
+        .filter(|v| !graph[*v].is_dummy)
+        .flat_map(|v| dummy_paths(graph, v))
+        .map(|path| graph[path[path.len() - 1]].multiplicity);
+    edges
+        .into_iter()
+        .zip(multiplicities)
+        .flat_map(|(edge, n)| std::iter::repeat_n(edge, n))
+        .collect()
+}
+
+/// Offsets the routes of parallel edges horizontally by `spacing`, so they
+/// are not drawn on top of each other. Edges in opposite directions between
+/// the same vertices count as parallel as well. Routes through dummy vertices
+/// are offset at each dummy vertex.
+fn separate_parallel_edges(edges: &mut [RoutedEdge<usize>], spacing: f64) {
+    let mut parallel = HashMap::<_, Vec<_>>::new();
+    for (i, edge) in edges.iter().enumerate() {
+        let key = (edge.tail.min(edge.head), edge.tail.max(edge.head));
+        parallel.entry(key).or_default().push(i);
+    }
+
+    for group in parallel.into_values().filter(|g| g.len() > 1) {
+        let n = group.len();
+        for (k, i) in group.into_iter().enumerate() {
+            let offset = (k as f64 - (n - 1) as f64 / 2.0) * spacing;
+            let points = &mut edges[i].points;
+            if offset == 0.0 {
+                continue;
+            }
+            if points.len() == 2 {
+                let ((x1, y1), (x2, y2)) = (points[0], points[1]);
+                points.insert(1, ((x1 + x2) / 2.0 + offset, (y1 + y2) / 2.0));
+            } else {
+                let n = points.len();
+                for (x, _) in &mut points[1..n - 1] {
+                    *x += offset;
+                }
+            }
+        }
+    }
 }
 
 fn slack(graph: &StableDiGraph<Vertex, Edge>, edge: EdgeIndex, minimum_length: i32) -> i32 {
     let (tail, head) = graph.edge_endpoints(edge).unwrap();
-    graph[head].rank - graph[tail].rank - minimum_length
+    graph[head].rank - graph[tail].rank - graph[edge].minimum_length(minimum_length)
+}
+
+/// Returns true if every edge spans exactly `minimum_length` ranks. In that
+/// case the ranking is already optimal and no dummy vertices are needed.
+fn is_properly_layered(graph: &StableDiGraph<Vertex, Edge>, minimum_length: i32) -> bool {
+    graph
+        .edge_indices()
+        .all(|e| slack(graph, e, minimum_length) == 0)
 }
 
 #[allow(dead_code)]
@@ -1,6 +1,6 @@
 use log::{debug, info};
 use petgraph::{
-    algo::{greedy_feedback_arc_set, is_cyclic_directed},
+    algo::{greedy_feedback_arc_set, toposort},
     stable_graph::{EdgeIndex, StableDiGraph},
     visit::EdgeRef,
 };
@@ -12,7 +12,9 @@ use super::{Edge, Vertex};
 /// direction of the edges from that set.
 /// Is not guaranteed to find the minimum fas.
 pub(crate) fn remove_cycles(graph: &mut StableDiGraph<Vertex, Edge>) -> Vec<EdgeIndex> {
-    if !is_cyclic_directed(&*graph) {
+    // unlike is_cyclic_directed, toposort doesn't recurse, so long paths don't
+    // overflow the call stack
+    if toposort(&*graph, None).is_ok() {
         info!(target: "Cycle Removal", "Graph contains no cycle");
         return Vec::new();
     }
@@ -26,8 +28,9 @@ pub(crate) fn remove_cycles(graph: &mut StableDiGraph<Vertex, Edge>) -> Vec<Edge
     // reverse the direction of the edges
     for edge in fas {
         if let Some((tail, head)) = graph.edge_endpoints(edge) {
-            // get the weight
-            let weight = graph[edge];
+            // get the weight and mark it as reversed
+            let mut weight = graph[edge];
+            weight.reversed = !weight.reversed;
             // add new edge in reversed direction
             let reversed_edge = graph.add_edge(head, tail, weight);
             reversed_edges.push(reversed_edge);
@@ -36,7 +39,7 @@ pub(crate) fn remove_cycles(graph: &mut StableDiGraph<Vertex, Edge>) -> Vec<Edge
         }
     }
 
-    assert!(!is_cyclic_directed(&*graph));
+    assert!(toposort(&*graph, None).is_ok());
 
     debug!(target: "Cycle Removal", "Reversed {} edges", reversed_edges.len());
 
@@ -11,9 +11,9 @@ use super::{Edge, Vertex};
 
 #[derive(Debug)]
 struct NeighborhoodInfo {
-    cut_value_sum: i32,
-    tree_edge_weight_sum: i32,
-    non_tree_edge_weight_sum: i32,
+    cut_value_sum: i64,
+    tree_edge_weight_sum: i64,
+    non_tree_edge_weight_sum: i64,
     missing: Option<NodeIndex>,
 }
 
@@ -26,17 +26,21 @@ pub(super) fn init_cutvalues(graph: &mut StableDiGraph<Vertex, Edge>) {
     calculate_cut_values(graph, queue);
 }
 
+/// Recalculates the cut values invalidated by exchanging `removed_edge` with
+/// `swap_edge`. Returns the least common ancestor of the endpoints of
+/// `swap_edge` and the edges whose cut values were outdated.
 pub(super) fn update_cutvalues(
     graph: &mut StableDiGraph<Vertex, Edge>,
     removed_edge: EdgeIndex,
     swap_edge: EdgeIndex,
-) -> NodeIndex {
+) -> (NodeIndex, Vec<EdgeIndex>) {
     info!(target: "cut_values", "Updating outdated cut values");
-    let least_common_ancestor = remove_outdated_cut_values(graph, swap_edge, removed_edge);
+    let (least_common_ancestor, outdated) =
+        remove_outdated_cut_values(graph, swap_edge, removed_edge);
     let queue = VecDeque::from([graph.edge_endpoints(removed_edge).unwrap().0]);
     debug!(target: "cut_values", "Leaves of tree: {queue:?}");
     calculate_cut_values(graph, queue);
-    least_common_ancestor
+    (least_common_ancestor, outdated)
 }
 
 fn leaves(graph: &StableDiGraph<Vertex, Edge>) -> VecDeque<NodeIndex> {
@@ -71,27 +75,45 @@ fn calculate_cut_values(graph: &mut StableDiGraph<Vertex, Edge>, mut queue: VecD
             _ => continue,
         };
 
-        let edge = match graph.find_edge(vertex, missing) {
-            Some(e) => {
-                // switch direction, if vertex is tail component of edge
-                std::mem::swap(&mut incoming, &mut outgoing);
-                e
-            }
-            None => graph.find_edge(missing, vertex).unwrap(),
-        };
+        let edge = tree_edge(graph, vertex, missing, |_, e| e.is_tree_edge);
+        if graph.edge_endpoints(edge).unwrap().0 == vertex {
+            // switch direction, if vertex is tail component of edge
+            std::mem::swap(&mut incoming, &mut outgoing);
+        }
 
-        graph[edge].cut_value = Some(calculate_cut_value(graph[edge].weight, incoming, outgoing));
+        graph[edge].cut_value = Some(calculate_cut_value(
+            graph[edge].weight as i64,
+            incoming,
+            outgoing,
+        ));
         trace!(target: "cut_values", "Cut values for edge: {}, {:?}", edge.index(), graph[edge].cut_value);
         // continue traversing tree in direction of edge whose vertex was missing before
         queue.push_back(missing);
     }
 }
 
+/// Returns the edge connecting `a` and `b` which is part of the tree according
+/// to `is_tree_edge`. Parallel edges which are not part of the tree are
+/// skipped.
+fn tree_edge(
+    graph: &StableDiGraph<Vertex, Edge>,
+    a: NodeIndex,
+    b: NodeIndex,
+    is_tree_edge: impl Fn(EdgeIndex, &Edge) -> bool,
+) -> EdgeIndex {
+    graph
+        .edges_connecting(a, b)
+        .chain(graph.edges_connecting(b, a))
+        .find(|e| is_tree_edge(e.id(), e.weight()))
+        .unwrap()
+        .id()
+}
+
 fn calculate_cut_value(
-    edge_weight: i32,
+    edge_weight: i64,
     incoming: NeighborhoodInfo,
     outgoing: NeighborhoodInfo,
-) -> i32 {
+) -> i64 {
     trace!(target: "cut_values", "Calculating cut value: edge_weight: {edge_weight}, data of incoming edges: {incoming:?}, data of outgoing edges: {outgoing:?}");
     edge_weight + incoming.non_tree_edge_weight_sum - incoming.cut_value_sum
         + incoming.tree_edge_weight_sum
@@ -118,10 +140,10 @@ fn get_neighborhood_info(
         let (tail, head) = (edge.source(), edge.target());
         let edge = *edge.weight();
         if !edge.is_tree_edge {
-            non_tree_edge_weight_sum += edge.weight;
+            non_tree_edge_weight_sum += edge.weight as i64;
         } else if let Some(cut_value) = edge.cut_value {
             cut_value_sum += cut_value;
-            tree_edge_weight_sum += edge.weight;
+            tree_edge_weight_sum += edge.weight as i64;
         } else if missing.is_none() {
             missing = Some(if tail == vertex { head } else { tail });
         } else {
@@ -140,9 +162,10 @@ fn remove_outdated_cut_values(
     graph: &mut StableDiGraph<Vertex, Edge>,
     swap_edge: EdgeIndex,
     removed_edge: EdgeIndex,
-) -> NodeIndex {
+) -> (NodeIndex, Vec<EdgeIndex>) {
     info!(target: "cut_values", "Remove outtdated cut_values, in order to calculate new ones");
     graph[removed_edge].cut_value = None;
+    let mut outdated = vec![removed_edge];
     let (mut w, mut x) = graph.edge_endpoints(swap_edge).unwrap();
     if graph[w].lim > graph[x].lim {
         std::mem::swap(&mut w, &mut x)
@@ -152,6 +175,10 @@ fn remove_outdated_cut_values(
         w.index(), 
         x.index());
 
+    // parents still describe the tree before the exchange
+    let is_tree_edge =
+        |e: EdgeIndex, edge: &Edge| e == removed_edge || edge.is_tree_edge && e != swap_edge;
+
     // follow path back until least common ancestor is found
     // and remove cut_values on the way
     let least_common_ancestor = match graph[w].parent {
@@ -159,8 +186,9 @@ fn remove_outdated_cut_values(
         Some(mut parent) => {
             let mut l = w;
             loop {
-                let edge = graph.find_edge_undirected(l, parent).unwrap().0;
+                let edge = tree_edge(graph, l, parent, is_tree_edge);
                 graph[edge].cut_value = None;
+                outdated.push(edge);
                 l = parent;
                 trace!(target: "cut_values", "current node in path: {}", l.index());
                 if graph[l].low <= graph[w].lim && graph[x].lim <= graph[l].lim
@@ -178,8 +206,9 @@ fn remove_outdated_cut_values(
     let mut l = x;
     while l != least_common_ancestor {
         let parent = graph[l].parent.unwrap();
-        let edge = graph.find_edge_undirected(l, parent).unwrap().0;
+        let edge = tree_edge(graph, l, parent, is_tree_edge);
         graph[edge].cut_value = None;
+        outdated.push(edge);
         l = parent;
     }
 
@@ -189,21 +218,21 @@ fn remove_outdated_cut_values(
         x.index(), 
         least_common_ancestor.index());
 
-    least_common_ancestor
+    (least_common_ancestor, outdated)
 }
 
 #[cfg(test)]
 mod tests {
 
-    use petgraph::stable_graph::NodeIndex;
+    use petgraph::stable_graph::{EdgeIndex, NodeIndex};
 
     use crate::algorithm::p1_layering::{
         cut_values::{init_cutvalues, remove_outdated_cut_values, update_cutvalues},
         low_lim::init_low_lim,
         tests::{
-            GraphBuilder, CUT_VALUES_EXAMPLE_GRAPH_NEG_CUT_VALUE, EXAMPLE_GRAPH,
-            EXAMPLE_GRAPH_FEASIBLE_TREE_NEG_CUT_VALUE, EXAMPLE_GRAPH_FEASIBLE_TREE_POS_CUT_VALUE,
-            LOW_LIM_GRAPH, LOW_LIM_GRAPH_LOW_LIM_VALUES,
+            outdated_cut_values, GraphBuilder, CUT_VALUES_EXAMPLE_GRAPH_NEG_CUT_VALUE,
+            EXAMPLE_GRAPH, EXAMPLE_GRAPH_FEASIBLE_TREE_NEG_CUT_VALUE,
+            EXAMPLE_GRAPH_FEASIBLE_TREE_POS_CUT_VALUE, LOW_LIM_GRAPH, LOW_LIM_GRAPH_LOW_LIM_VALUES,
         },
         Edge,
     };
@@ -340,7 +369,7 @@ mod tests {
         let tail = 6.into();
         let head = 8.into();
         let edge = graph.add_edge(tail, head, Edge::default());
-        let actual_lca = remove_outdated_cut_values(&mut graph, edge, edge);
+        let (actual_lca, _) = remove_outdated_cut_values(&mut graph, edge, edge);
         let expected_path = [(5, 6), (4, 5), (4, 8)]
             .into_iter()
             .map(|(t, h)| graph.find_edge(t.into(), h.into()).unwrap())
@@ -368,7 +397,7 @@ mod tests {
         let tail = 3.into();
         let head = 8.into();
         let edge = graph.add_edge(tail, head, Edge::default());
-        let actual_lca = remove_outdated_cut_values(&mut graph, edge, edge);
+        let (actual_lca, _) = remove_outdated_cut_values(&mut graph, edge, edge);
         let expected_path = [(1, 3), (0, 1), (4, 8), (0, 4)]
             .into_iter()
             .map(|(t, h)| graph.find_edge(t.into(), h.into()).unwrap())
@@ -475,4 +504,18 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn cut_value_of_parallel_edges_is_stored_at_tree_edge() {
+        // only the first of the two parallel edges is part of the tree
+        let (mut graph, ..) = GraphBuilder::new(&[(0, 1), (1, 2), (0, 1)]).build();
+        let parallel = EdgeIndex::from(2);
+        for e in graph.edge_indices().collect::<Vec<_>>() {
+            graph[e].is_tree_edge = e != parallel;
+        }
+        init_low_lim(&mut graph);
+        init_cutvalues(&mut graph);
+        assert_eq!(graph[parallel].cut_value, None);
+        assert!(outdated_cut_values(&graph).is_empty());
+    }
 }
@@ -5,10 +5,19 @@ use petgraph::stable_graph::{NodeIndex, StableDiGraph};
 
 use super::{Edge, Vertex};
 
+#[cfg(test)]
 pub(super) fn init_low_lim(graph: &mut StableDiGraph<Vertex, Edge>) {
-    // start at arbitrary root node
+    // start at arbitrary root node, an empty graph has no tree to label
+    let Some(root) = graph.node_indices().next() else {
+        return;
+    };
+    init_low_lim_from(graph, root);
+}
+
+/// Initializes low, lim and parent values of the vertices, using `root` as
+/// the root of the tree.
+pub(super) fn init_low_lim_from(graph: &mut StableDiGraph<Vertex, Edge>, root: NodeIndex) {
     info!(target: "low_lim", "initialzing low, lim and parent values of vertices");
-    let root = graph.node_indices().next().unwrap();
     debug!(target: "low_lim", "root of tree: {}", root.index());
     let mut max_lim = graph.node_count() as u32;
     trace!(target: "low_lim", "Maximum Lim value: {max_lim}");
@@ -39,31 +48,50 @@ pub(super) fn update_low_lim(
     );
 }
 
+/// Assigns lim values in pre-order, counting down from `max_lim`, and sets
+/// the low value of each vertex to the smallest lim in its subtree. Uses an
+/// explicit stack, so deep trees don't overflow the call stack.
 fn dfs_low_lim(
     graph: &mut StableDiGraph<Vertex, Edge>,
-    next: NodeIndex,
+    root: NodeIndex,
     parent: Option<NodeIndex>,
     max_lim: &mut u32,
     visited: &mut HashSet<NodeIndex>,
 ) {
-    visited.insert(next);
-    debug!(target: "low_lim", "calculating values for vertex: {}", next.index());
-    graph[next].lim = *max_lim;
-    graph[next].parent = parent;
-    let mut walker = graph.neighbors_undirected(next).detach();
-    while let Some((edge, n)) = walker.next(graph) {
-        if !visited.contains(&n) && graph[edge].is_tree_edge {
-            *max_lim -= 1;
-            dfs_low_lim(graph, n, Some(next), max_lim, visited);
+    let mut stack = Vec::new();
+    let mut next = Some((root, parent));
+    loop {
+        if let Some((next, parent)) = next.take() {
+            visited.insert(next);
+            debug!(target: "low_lim", "calculating values for vertex: {}", next.index());
+            graph[next].lim = *max_lim;
+            graph[next].parent = parent;
+            stack.push((next, graph.neighbors_undirected(next).detach()));
+        }
+        let Some((vertex, walker)) = stack.last_mut() else {
+            break;
+        };
+        let vertex = *vertex;
+        match walker.next(graph) {
+            Some((edge, n)) => {
+                if !visited.contains(&n) && graph[edge].is_tree_edge {
+                    *max_lim -= 1;
+                    next = Some((n, Some(vertex)));
+                }
+            }
+            None => {
+                // all descendants are done, so max_lim is the smallest lim
+                graph[vertex].low = *max_lim;
+                trace!(target: "low_lim", 
+                    "Vertex: {}, low: {}, lim: {}, parent: {:?}", 
+                    vertex.index(), 
+                    graph[vertex].low,
+                    graph[vertex].lim, 
+                    graph[vertex].parent);
+                stack.pop();
+            }
         }
     }
-    graph[next].low = *max_lim;
-    trace!(target: "low_lim", 
-        "Vertex: {}, low: {}, lim: {}, parent: {:?}", 
-        next.index(), 
-        graph[next].low,
-        graph[next].lim, 
-        graph[next].parent);
 }
 
 #[cfg(test)]
@@ -127,6 +155,22 @@ mod tests {
         assert_eq!(v8, phase_1_vertex(7, 7, Some(4.into()), true));
     }
 
+    #[test]
+    fn init_low_lim_deep_path() {
+        let n = 50_000;
+        let edges = (1..n).map(|v| (v - 1, v)).collect::<Vec<_>>();
+        let (mut graph, ..) = GraphBuilder::new(&edges).with_tree_edges(&edges).build();
+
+        init_low_lim(&mut graph);
+
+        for v in 0..n {
+            let vertex = graph[NodeIndex::from(v)];
+            assert_eq!(vertex.low, 1);
+            assert_eq!(vertex.lim, n - v);
+            assert_eq!(vertex.parent, v.checked_sub(1).map(NodeIndex::from));
+        }
+    }
+
     #[test]
     fn test_init_low_lim_neg_cut_value() {
         let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH)
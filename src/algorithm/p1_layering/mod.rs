@@ -1,13 +1,14 @@
-// TODOS: Keep non graph edges during rank() procedure in vecdeque to be able to cyclically search through them
 //! Executes the second phase of sugiyamas algorithm, which assigns each vertex
 //! a rank.
-//! Currently three ranking algorithm are implmented:
+//! Currently five ranking algorithms are implemented:
 //!
 //! 1. Original - tries to move each vertex as close to neighbors as possible.
 //! 2. MinimizeEdgeLength - builds a feasible tight tree in order to minimize
 //!     edge lengths. This is the technique describe in the paper by Gansner et al.
 //! 3. Up - Move vertices as far up as possible
 //! 4. Down - Move vertices as far down as possible.
+//! 5. CoffmanGraham - fills ranks via the Coffman-Graham algorithm, so no rank
+//!    is wider than the maximum layer width.
 //!
 mod cut_values;
 mod low_lim;
@@ -15,44 +16,119 @@ pub(super) mod ranking;
 #[cfg(test)]
 pub(crate) mod tests;
 
-use log::info;
-use petgraph::stable_graph::{EdgeIndex, StableDiGraph};
-use petgraph::visit::IntoNodeIdentifiers;
+use std::collections::{BTreeMap, BTreeSet};
 
-use crate::configure::RankingType;
+use log::{debug, info, warn};
+use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableDiGraph};
+use petgraph::visit::{EdgeRef, IntoNodeIdentifiers};
+use petgraph::Direction::{Incoming, Outgoing};
+
+use crate::configure::{Config, LayerSplitting, RankingType};
 
 use self::cut_values::update_cutvalues;
-use self::low_lim::update_low_lim;
-use self::ranking::{feasible_tree, init_rank, move_vertices_down, move_vertices_up, update_ranks};
+use self::low_lim::{init_low_lim_from, update_low_lim};
+use self::ranking::{
+    coffman_graham, feasible_tree_from, init_rank, move_vertices_down, move_vertices_up,
+};
 
-use super::{slack, Edge, Vertex};
+use super::{slack, Edge, Pin, Vertex};
 
+/// Assigns each vertex a rank according to [Config::ranking_type], where each
+/// edge spans at least [Config::minimum_length] ranks. If
+/// [Config::reroot_every] is [Some], the spanning tree of network simplex is
+/// rerooted at a different vertex after that many iterations. Network simplex
+/// stops after [Config::max_simplex_iterations] exchanges, eight times the
+/// number of vertices if [None], keeping the ranking found so far.
+/// [Config::max_layer_width] limits the number of vertices per rank for
+/// [RankingType::CoffmanGraham]. The spanning tree of network simplex is
+/// rooted at `root`, or at the first vertex if [None].
+///
+/// Whenever network simplex has to choose between edges, e.g. which tight edge
+/// to add to the spanning tree or which edge enters it, ties are broken by
+/// [edge_key]. The leaving edge is always the negative edge with the smallest
+/// key. The ranking therefore doesn't depend on the order the edges were added
+/// in.
+///
+/// Returns the `(leaving, entering)` tree edges of each exchange network
+/// simplex performed, in order. It is empty for all other ranking types.
 pub(super) fn rank(
     graph: &mut StableDiGraph<Vertex, Edge>,
-    minimum_length: i32,
-    ranking_type: RankingType,
-) {
+    config: &Config,
+    root: Option<NodeIndex>,
+) -> Vec<(EdgeIndex, EdgeIndex)> {
+    let minimum_length = config.minimum_length as i32;
+    let ranking_type = config.ranking_type;
     info!(target: "ranking", "Start ranking, ranking type: {ranking_type:?}, minimum_length: {minimum_length}");
     init_rank(graph, minimum_length);
     match ranking_type {
         RankingType::Original => original(graph, minimum_length),
-        RankingType::MinimizeEdgeLength => minimize_edge_length(graph, minimum_length),
+        RankingType::MinimizeEdgeLength => {
+            let max_iterations = config
+                .max_simplex_iterations
+                .unwrap_or(8 * graph.node_count());
+            return minimize_edge_length(
+                graph,
+                minimum_length,
+                config.reroot_every,
+                max_iterations,
+                root,
+            );
+        }
         RankingType::Up => move_vertices_up(graph, minimum_length),
         RankingType::Down => move_vertices_down(graph, minimum_length),
+        RankingType::CoffmanGraham => coffman_graham(graph, minimum_length, config.max_layer_width),
     }
+    Vec::new()
 }
 
-fn minimize_edge_length(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
-    feasible_tree(graph, minimum_length);
-    while let Some(removed_edge) = leave_edge(graph) {
+fn minimize_edge_length(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    minimum_length: i32,
+    reroot_every: Option<usize>,
+    max_iterations: usize,
+    root: Option<NodeIndex>,
+) -> Vec<(EdgeIndex, EdgeIndex)> {
+    // an empty graph has no tree to build
+    let Some(mut root) = root.or_else(|| graph.node_indices().next()) else {
+        return Vec::new();
+    };
+    feasible_tree_from(graph, minimum_length, root);
+    let mut exchanges = Vec::new();
+    // ordered by key, so the same edge leaves the tree as in a full scan
+    let mut negative = negative_cut_values(graph);
+    while let Some((.., removed_edge)) = negative.pop_first() {
+        if exchanges.len() == max_iterations {
+            // each exchange keeps the ranking feasible, so stop with the current one
+            warn!(target: "ranking", "Stopping network simplex after {max_iterations} iterations, the ranking may not be optimal");
+            break;
+        }
+        debug!(target: "ranking", "Most negative cut value: -{}", max_abs_cutvalue(graph));
         // swap edges and calculate cut value
         let swap_edge = enter_edge(graph, removed_edge, minimum_length);
-        exchange(graph, removed_edge, swap_edge, minimum_length);
+        for edge in exchange(graph, removed_edge, swap_edge, minimum_length) {
+            if graph[edge].cut_value.is_some_and(|c| c < 0) {
+                negative.insert(edge_key(graph, edge));
+            } else {
+                negative.remove(&edge_key(graph, edge));
+            }
+        }
+        exchanges.push((removed_edge, swap_edge));
+        let iterations = exchanges.len();
+        // cut values don't depend on the root, so only low and lim change
+        if let Some(reroot_every) = reroot_every.filter(|n| *n > 0 && iterations % n == 0) {
+            root = graph
+                .node_indices()
+                .nth(iterations / reroot_every % graph.node_count())
+                .unwrap();
+            debug!(target: "ranking", "Rerooting tree at vertex {}", root.index());
+            init_low_lim_from(graph, root);
+        }
     }
 
-    // don't balance ranks since we want maximum width to
+    // don't balance ranks by default since we want maximum width to
     // give indication about number of parallel processes running
     normalize(graph);
+    exchanges
 }
 
 fn original(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
@@ -60,17 +136,51 @@ fn original(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
     move_vertices_down(graph, minimum_length);
 }
 
+/// Returns the key edges are ordered by whenever ranking chooses between
+/// them: by tail, then by head and finally by index, which only matters for
+/// parallel edges.
+pub(super) fn edge_key(
+    graph: &StableDiGraph<Vertex, Edge>,
+    edge: EdgeIndex,
+) -> (NodeIndex, NodeIndex, EdgeIndex) {
+    let (tail, head) = graph.edge_endpoints(edge).unwrap();
+    (tail, head, edge)
+}
+
+/// Returns the keys of the edges with a negative cut value, ordered by
+/// [edge_key].
+fn negative_cut_values(
+    graph: &StableDiGraph<Vertex, Edge>,
+) -> BTreeSet<(NodeIndex, NodeIndex, EdgeIndex)> {
+    graph
+        .edge_indices()
+        .filter(|e| graph[*e].cut_value.is_some_and(|c| c < 0))
+        .map(|e| edge_key(graph, e))
+        .collect()
+}
+
+#[cfg(test)]
 fn leave_edge(graph: &StableDiGraph<Vertex, Edge>) -> Option<EdgeIndex> {
-    for edge in graph.edge_indices() {
-        if let Some(cut_value) = graph[edge].cut_value {
-            if cut_value < 0 {
-                return Some(edge);
-            }
-        }
-    }
-    None
+    negative_cut_values(graph).first().map(|(.., e)| *e)
 }
 
+/// Returns the absolute value of the most negative cut value of all tree
+/// edges, or 0 if no cut value is negative. As long as it is greater than 0,
+/// the ranking is not optimal, so it approaches 0 as the network simplex
+/// converges.
+pub(super) fn max_abs_cutvalue(graph: &StableDiGraph<Vertex, Edge>) -> i64 {
+    graph
+        .edge_weights()
+        .filter(|e| e.is_tree_edge)
+        .filter_map(|e| e.cut_value)
+        .min()
+        .map_or(0, |cut_value| (-cut_value).max(0))
+}
+
+/// Returns the non-tree edge with minimum slack, which replaces `edge` in the
+/// tight tree. Only edges from the head component to the tail component of
+/// `edge` are considered, which all have an endpoint in the component below
+/// `edge`, so only the edges of that component are searched.
 fn enter_edge(
     graph: &mut StableDiGraph<Vertex, Edge>,
     edge: EdgeIndex,
@@ -88,36 +198,215 @@ fn enter_edge(
     if !is_root_in_head {
         std::mem::swap(&mut u, &mut v);
     }
+    // the component below is the tail component if the root is in the head
+    // component, so the entering edge ends in it, and vice versa
+    let direction = if is_root_in_head { Incoming } else { Outgoing };
 
-    graph
-        .edge_indices()
+    component_below(graph, edge)
+        .into_iter()
+        .flat_map(|v| graph.edges_directed(v, direction).map(|e| e.id()))
         .filter(|e| !graph[*e].is_tree_edge && is_head_to_tail(graph, *e, u, is_root_in_head))
-        .min_by(|e1, e2| slack(graph, *e1, minimum_length).cmp(&slack(graph, *e2, minimum_length)))
+        .min_by_key(|e| (slack(graph, *e, minimum_length), edge_key(graph, *e)))
         .unwrap()
 }
 
+/// Returns the vertices of the tree component below the tree edge `edge`,
+/// i.e. the component which doesn't contain the root once `edge` is removed.
+/// The first vertex is the endpoint of `edge` in that component.
+fn component_below(graph: &StableDiGraph<Vertex, Edge>, edge: EdgeIndex) -> Vec<NodeIndex> {
+    let (tail, head) = graph.edge_endpoints(edge).unwrap();
+    let child = if graph[tail].lim < graph[head].lim {
+        tail
+    } else {
+        head
+    };
+    let mut component = vec![child];
+    let mut next = 0;
+    while let Some(&v) = component.get(next) {
+        next += 1;
+        // the children of a vertex have a smaller lim than the vertex
+        let children = graph
+            .edges_directed(v, Incoming)
+            .chain(graph.edges_directed(v, Outgoing))
+            .filter(|e| e.weight().is_tree_edge)
+            .map(|e| {
+                if e.source() == v {
+                    e.target()
+                } else {
+                    e.source()
+                }
+            })
+            .filter(|n| graph[*n].lim < graph[v].lim)
+            .collect::<Vec<_>>();
+        component.extend(children);
+    }
+    component
+}
+
+/// Exchanges `removed_edge` with `swap_edge` in the tight tree and updates
+/// cut values, low and lim values and ranks. Only the ranks of the component
+/// below `removed_edge` change, they are shifted so `swap_edge` becomes
+/// tight. Returns the edges whose cut value changed.
 fn exchange(
     graph: &mut StableDiGraph<Vertex, Edge>,
     removed_edge: EdgeIndex,
     swap_edge: EdgeIndex,
     minimum_length: i32,
-) {
+) -> Vec<EdgeIndex> {
+    // shift the component below, before low and lim change
+    let component = component_below(graph, removed_edge);
+    let (child, (_, head)) = (
+        graph[component[0]],
+        graph.edge_endpoints(swap_edge).unwrap(),
+    );
+    let slack = slack(graph, swap_edge, minimum_length);
+    let delta = if child.low <= graph[head].lim && graph[head].lim <= child.lim {
+        -slack
+    } else {
+        slack
+    };
+    for v in component {
+        graph[v].rank += delta;
+    }
+
     // swap edges
     graph[removed_edge].is_tree_edge = false;
     graph[swap_edge].is_tree_edge = true;
 
     // update the graph
-    let least_common_ancestor = update_cutvalues(graph, removed_edge, swap_edge);
+    let (least_common_ancestor, updated) = update_cutvalues(graph, removed_edge, swap_edge);
     update_low_lim(graph, least_common_ancestor);
-    update_ranks(graph, minimum_length);
+    updated
 }
 
-fn normalize(graph: &mut StableDiGraph<Vertex, Edge>) {
-    let min_rank = graph
-        .node_identifiers()
-        .map(|v| graph[v].rank)
-        .min()
-        .unwrap();
+/// Moves vertices pinned to the first rank to rank 0 and vertices pinned to
+/// the last rank to the largest rank. Vertices pinned to the first rank need
+/// to be sources and vertices pinned to the last rank sinks, so the ranking
+/// stays feasible.
+pub(super) fn apply_pins(graph: &mut StableDiGraph<Vertex, Edge>) {
+    let Some(max_rank) = graph.node_weights().map(|v| v.rank).max() else {
+        return;
+    };
+    for v in graph.node_weights_mut() {
+        match v.pin {
+            Some(Pin::First) => v.rank = 0,
+            Some(Pin::Last) => v.rank = max_rank,
+            None => (),
+        }
+    }
+}
+
+/// Splits each rank containing more than `max_width` vertices into multiple
+/// adjacent ranks, by moving its vertices down according to `splitting`. All
+/// ranks below are shifted down to make room, so edge lengths never decrease.
+pub(super) fn split_wide_ranks(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    max_width: usize,
+    splitting: LayerSplitting,
+) {
+    info!(target: "ranking", "Splitting ranks wider than {max_width} vertices, splitting: {splitting:?}");
+    let max_width = max_width.max(1);
+    let mut ranks = BTreeMap::<i32, Vec<NodeIndex>>::new();
+    for v in graph.node_indices() {
+        ranks.entry(graph[v].rank).or_default().push(v);
+    }
+
+    let mut shift = 0;
+    for mut vertices in ranks.into_values() {
+        let bands = vertices.len().div_ceil(max_width);
+        if splitting == LayerSplitting::Greedy {
+            // moving a vertex down lengthens its incoming edges, but shortens
+            // its outgoing edges, since all ranks below are shifted as well.
+            let gain = |v: &NodeIndex| {
+                graph.neighbors_directed(*v, Outgoing).count() as i32
+                    - graph.neighbors_directed(*v, Incoming).count() as i32
+            };
+            vertices.sort_by_key(gain);
+        }
+        for (i, v) in vertices.into_iter().enumerate() {
+            let band = match splitting {
+                LayerSplitting::Alternate => i % bands,
+                LayerSplitting::Greedy => i / max_width,
+            };
+            graph[v].rank += shift + band as i32;
+        }
+        shift += bands as i32 - 1;
+    }
+}
+
+/// Returns the largest width for [split_wide_ranks], for which the ratio of
+/// the number of vertices in the widest rank to the number of ranks is at
+/// most `target_aspect` after splitting. Returns [None] if the ranking already
+/// satisfies it, and 1 if no width does.
+pub(super) fn width_for_aspect(
+    graph: &StableDiGraph<Vertex, Edge>,
+    target_aspect: f64,
+) -> Option<usize> {
+    let mut widths = BTreeMap::<i32, usize>::new();
+    for v in graph.node_weights() {
+        *widths.entry(v.rank).or_default() += 1;
+    }
+    let height = (widths.last_key_value()?.0 - widths.first_key_value()?.0) as usize + 1;
+    let widest = widths.values().copied().max()?;
+    let aspect = |width: usize| {
+        let split_height = height
+            + widths
+                .values()
+                .map(|w| w.div_ceil(width) - 1)
+                .sum::<usize>();
+        width as f64 / split_height as f64
+    };
+    if aspect(widest) <= target_aspect {
+        return None;
+    }
+    debug!(target: "ranking", "Splitting ranks to reach aspect ratio {target_aspect}");
+    Some(
+        (1..widest)
+            .rev()
+            .find(|width| aspect(*width) <= target_aspect)
+            .unwrap_or(1),
+    )
+}
+
+/// Moves each vertex with the same total weight of incoming and outgoing
+/// edges to the middle of the ranks it can be placed in, i.e. between the
+/// lowest rank of its predecessors and the highest rank of its successors,
+/// respecting the minimum length of its edges. Since the weights are equal,
+/// this doesn't change the total weighted edge length. Vertices are moved in
+/// topological order, so the ranks of earlier vertices are taken into
+/// account.
+pub(super) fn balance(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
+    info!(target: "ranking", "Balancing ranks");
+    for v in petgraph::algo::toposort(&*graph, None).unwrap() {
+        let weight = |direction| {
+            graph
+                .edges_directed(v, direction)
+                .map(|e| e.weight().weight as i64)
+                .sum::<i64>()
+        };
+        if weight(Incoming) != weight(Outgoing) {
+            continue;
+        }
+        let lowest = graph
+            .edges_directed(v, Incoming)
+            .map(|e| graph[e.source()].rank + e.weight().minimum_length(minimum_length))
+            .max();
+        let highest = graph
+            .edges_directed(v, Outgoing)
+            .map(|e| graph[e.target()].rank - e.weight().minimum_length(minimum_length))
+            .min();
+        if let (Some(lowest), Some(highest)) = (lowest, highest) {
+            let rank = (lowest + highest) / 2;
+            debug!(target: "ranking", "Moving vertex {} from rank {} to {rank}", v.index(), graph[v].rank);
+            graph[v].rank = rank;
+        }
+    }
+}
+
+pub(super) fn normalize(graph: &mut StableDiGraph<Vertex, Edge>) {
+    let Some(min_rank) = graph.node_identifiers().map(|v| graph[v].rank).min() else {
+        return;
+    };
     for v in graph.node_weights_mut() {
         v.rank -= min_rank;
     }
@@ -1,12 +1,15 @@
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet};
 
 use log::{debug, info, trace};
 use petgraph::{
     stable_graph::{EdgeIndex, NodeIndex, StableDiGraph},
-    Direction::{self, Incoming, Outgoing},
+    visit::EdgeRef,
+    Direction::{Incoming, Outgoing},
 };
 
-use super::{cut_values::init_cutvalues, low_lim::init_low_lim, slack, Edge, Vertex};
+use super::{
+    cut_values::init_cutvalues, edge_key, low_lim::init_low_lim_from, slack, Edge, Vertex,
+};
 
 #[allow(dead_code)]
 pub(crate) fn print_ranks(graph: &StableDiGraph<Vertex, Edge>) {
@@ -21,9 +24,23 @@ pub(crate) fn print_ranks(graph: &StableDiGraph<Vertex, Edge>) {
 
 /// Builds a feasible tree, which means a tree in which each edge has a
 /// minimum amount of slack (edge length = minimum length)
+#[cfg(test)]
 pub(super) fn feasible_tree(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
+    // start at arbitrary root node, an empty graph has no tree to build
+    let Some(tree_root) = graph.node_indices().next() else {
+        return;
+    };
+    feasible_tree_from(graph, minimum_length, tree_root);
+}
+
+/// Builds a feasible tree like [feasible_tree], using `tree_root` as the root
+/// of the tree and of its low and lim values.
+pub(super) fn feasible_tree_from(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    minimum_length: i32,
+    tree_root: NodeIndex,
+) {
     info!(target: "ranking", "building feasible tree");
-    let tree_root = graph.node_indices().next().unwrap();
     trace!(target: "ranking", "root of tree is: {}", tree_root.index());
 
     info!(target: "ranking", "Trying to build tight tree.");
@@ -42,7 +59,7 @@ pub(super) fn feasible_tree(graph: &mut StableDiGraph<Vertex, Edge>, minimum_len
     }
 
     init_cutvalues(graph);
-    init_low_lim(graph);
+    init_low_lim_from(graph, tree_root);
 }
 
 pub(super) fn move_vertices_up(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
@@ -50,8 +67,8 @@ pub(super) fn move_vertices_up(graph: &mut StableDiGraph<Vertex, Edge>, minimum_
     info!(target: "ranking", "Moving vertices as far up as possible");
     for v in graph.node_indices().collect::<Vec<_>>() {
         let rank = graph
-            .neighbors_directed(v, Incoming)
-            .map(|n| graph[n].rank + minimum_length)
+            .edges_directed(v, Incoming)
+            .map(|e| graph[e.source()].rank + e.weight().minimum_length(minimum_length))
             .max()
             .unwrap_or(0);
 
@@ -65,8 +82,12 @@ pub(super) fn move_vertices_down(graph: &mut StableDiGraph<Vertex, Edge>, minimu
     if let Some(max_rank) = graph.node_weights().map(|w| w.rank).max() {
         for v in graph.node_indices().collect::<Vec<_>>() {
             let rank = graph
-                .neighbors_directed(v, Outgoing)
-                .filter_map(|n| graph[n].rank.checked_sub(minimum_length))
+                .edges_directed(v, Outgoing)
+                .filter_map(|e| {
+                    graph[e.target()]
+                        .rank
+                        .checked_sub(e.weight().minimum_length(minimum_length))
+                })
                 .min()
                 .unwrap_or(max_rank);
 
@@ -76,12 +97,89 @@ pub(super) fn move_vertices_down(graph: &mut StableDiGraph<Vertex, Edge>, minimu
     }
 }
 
-pub(super) fn update_ranks(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length: i32) {
-    info!(target: "ranking", "Updating node ranks");
-    let node = graph.node_indices().next().unwrap();
-    let mut visited = HashSet::from([node]);
-    graph[node].rank = 0;
-    let mut queue = VecDeque::from([node]);
+/// Assigns ranks via the Coffman-Graham algorithm, which places at most
+/// `max_width` vertices in each rank. Vertices are first labeled in
+/// topological order, preferring vertices whose predecessors have the
+/// smallest labels. Ranks are then filled from the bottom, each time placing
+/// the vertex with the highest label whose successors are all in lower ranks.
+/// Afterwards edges are lengthened to their minimum length, if necessary.
+pub(super) fn coffman_graham(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    minimum_length: i32,
+    max_width: Option<usize>,
+) {
+    info!(target: "ranking", "Ranking via Coffman-Graham, maximum width: {max_width:?}");
+    let max_width = max_width.unwrap_or(usize::MAX).max(1);
+    let mut labels = HashMap::<NodeIndex, usize>::new();
+    while labels.len() < graph.node_count() {
+        let next = graph
+            .node_indices()
+            .filter(|v| !labels.contains_key(v))
+            .filter(|v| {
+                graph
+                    .neighbors_directed(*v, Incoming)
+                    .all(|u| labels.contains_key(&u))
+            })
+            .min_by_key(|v| {
+                let mut predecessors = graph
+                    .neighbors_directed(*v, Incoming)
+                    .map(|u| labels[&u])
+                    .collect::<Vec<_>>();
+                predecessors.sort_unstable_by(|a, b| b.cmp(a));
+                predecessors
+            })
+            .unwrap();
+        labels.insert(next, labels.len());
+    }
+
+    // layers counted from the bottom
+    let mut layers = HashMap::<NodeIndex, usize>::new();
+    let (mut layer, mut width) = (0, 0);
+    while layers.len() < graph.node_count() {
+        let next = graph
+            .node_indices()
+            .filter(|v| !layers.contains_key(v))
+            .filter(|v| {
+                graph
+                    .neighbors_directed(*v, Outgoing)
+                    .all(|w| layers.get(&w).is_some_and(|l| *l < layer))
+            })
+            .max_by_key(|v| labels[v]);
+        match next {
+            Some(v) if width < max_width => {
+                layers.insert(v, layer);
+                width += 1;
+            }
+            _ => {
+                layer += 1;
+                width = 0;
+            }
+        }
+    }
+
+    for v in petgraph::algo::toposort(&*graph, None).unwrap() {
+        let rank = graph
+            .edges_directed(v, Incoming)
+            .map(|e| graph[e.source()].rank + e.weight().minimum_length(minimum_length))
+            .fold((layer - layers[&v]) as i32, i32::max);
+        trace!(target: "ranking", "Vertex: {}, rank: {}", v.index(), rank);
+        graph[v].rank = rank;
+    }
+}
+
+/// Ranks the vertices so all edges of the tree are tight, starting with
+/// `root` on rank 0. Network simplex only shifts the ranks of the component
+/// that moved, this ranks the whole tree to check it against.
+#[cfg(test)]
+pub(super) fn update_ranks(
+    graph: &mut StableDiGraph<Vertex, Edge>,
+    root: NodeIndex,
+    minimum_length: i32,
+) {
+    info!(target: "ranking", "Updating node ranks, starting from root: {}", root.index());
+    let mut visited = HashSet::from([root]);
+    graph[root].rank = 0;
+    let mut queue = std::collections::VecDeque::from([root]);
 
     while let Some(parent) = queue.pop_front() {
         update_neighbor_ranks(
@@ -105,35 +203,52 @@ pub(super) fn update_ranks(graph: &mut StableDiGraph<Vertex, Edge>, minimum_leng
     }
 }
 
-/// Builds a tight tree via depth first search
+/// Builds a tight tree via depth first search, using an explicit stack so
+/// deep trees don't overflow the call stack. The edges of each vertex are
+/// visited ordered by [edge_key].
 /// Returns the number of verticees contained in the tree
 fn tight_tree(
     graph: &mut StableDiGraph<Vertex, Edge>,
-    vertex: NodeIndex,
+    root: NodeIndex,
     visited: &mut HashSet<EdgeIndex>,
     minimum_length: i32,
 ) -> usize {
     // start from topmost nodes.
     // then for each topmost node add nodes to tree until done. Then continue with next node until no more nodes are found.
-    trace!(target: "ranking", "vertex: {}", vertex.index());
-    let mut node_count = 1;
-    if !graph[vertex].is_tree_vertex {
-        graph[vertex].is_tree_vertex = true;
-    }
-
-    let mut neighbors = graph.neighbors_undirected(vertex).detach();
-    while let Some(edge) = neighbors.next_edge(graph) {
+    let mut node_count = 0;
+    let mut stack = Vec::new();
+    let mut next = Some(root);
+    loop {
+        if let Some(vertex) = next.take() {
+            trace!(target: "ranking", "vertex: {}", vertex.index());
+            node_count += 1;
+            graph[vertex].is_tree_vertex = true;
+            let mut edges = graph
+                .edges_directed(vertex, Outgoing)
+                .chain(graph.edges_directed(vertex, Incoming))
+                .map(|e| e.id())
+                .collect::<Vec<_>>();
+            edges.sort_by_key(|e| edge_key(graph, *e));
+            stack.push((vertex, edges.into_iter()));
+        }
+        let Some((vertex, edges)) = stack.last_mut() else {
+            break;
+        };
+        let vertex = *vertex;
+        let Some(edge) = edges.next() else {
+            stack.pop();
+            continue;
+        };
         let (tail, head) = graph.edge_endpoints(edge).unwrap();
         let other = if tail == vertex { head } else { tail };
 
-        if !visited.contains(&edge) {
-            visited.insert(edge);
+        if visited.insert(edge) {
             if graph[edge].is_tree_edge {
-                node_count += tight_tree(graph, other, visited, minimum_length);
+                next = Some(other);
             } else if slack(graph, edge, minimum_length) == 0 && !graph[other].is_tree_vertex {
                 trace!(target: "ranking", "adding edge with minimum slack: {}", edge.index());
                 graph[edge].is_tree_edge = true;
-                node_count += tight_tree(graph, other, visited, minimum_length);
+                next = Some(other);
             }
         }
     }
@@ -149,8 +264,9 @@ pub(crate) fn init_rank(graph: &mut StableDiGraph<Vertex, Edge>, minimum_length:
     info!(target: "ranking", "Initializing ranks via topological sort.");
     for v in petgraph::algo::toposort(&*graph, None).unwrap() {
         let rank = graph
-            .neighbors_directed(v, Incoming)
-            .map(|n| graph[n].rank + minimum_length)
+            .edges_directed(v, Incoming)
+            .map(|e| graph[e.source()].rank + e.weight().minimum_length(minimum_length))
+            .chain(graph[v].initial_rank)
             .max();
 
         if let Some(rank) = rank {
@@ -169,7 +285,7 @@ fn find_non_tight_edge(graph: &StableDiGraph<Vertex, Edge>, minimum_length: i32)
     graph
         .edge_indices()
         .filter(|e| !graph[*e].is_tree_edge && is_incident_edge(graph, e))
-        .min_by(|e1, e2| slack(graph, *e1, minimum_length).cmp(&slack(graph, *e2, minimum_length)))
+        .min_by_key(|e| (slack(graph, *e, minimum_length), edge_key(graph, *e)))
         .unwrap()
 }
 
@@ -182,12 +298,13 @@ fn tighten_edge(graph: &mut StableDiGraph<Vertex, Edge>, delta: i32) {
     }
 }
 
+#[cfg(test)]
 fn update_neighbor_ranks(
     graph: &mut StableDiGraph<Vertex, Edge>,
     parent: NodeIndex,
-    direction: Direction,
+    direction: petgraph::Direction,
     coefficient: i32,
-    queue: &mut VecDeque<NodeIndex>,
+    queue: &mut std::collections::VecDeque<NodeIndex>,
     visited: &mut HashSet<NodeIndex>,
     minimum_length: i32,
 ) {
@@ -196,7 +313,8 @@ fn update_neighbor_ranks(
         if !graph[edge].is_tree_edge || visited.contains(&other) {
             continue;
         }
-        graph[other].rank = graph[parent].rank + minimum_length * coefficient;
+        graph[other].rank =
+            graph[parent].rank + graph[edge].minimum_length(minimum_length) * coefficient;
         trace!(target: "ranking", "updating ranks of {}, new rank is: {}", other.index(), graph[other].rank);
         queue.push_back(other);
         visited.insert(other);
@@ -208,7 +326,10 @@ mod tests {
 
     use std::collections::HashSet;
 
-    use petgraph::Direction::{Incoming, Outgoing};
+    use petgraph::{
+        stable_graph::NodeIndex,
+        Direction::{Incoming, Outgoing},
+    };
 
     use crate::algorithm::p1_layering::{
         ranking::{feasible_tree, tight_tree},
@@ -256,6 +377,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn initial_ranking_starts_from_given_ranks() {
+        let (mut graph, ..) = GraphBuilder::new(&[(0, 1), (2, 1)]).build();
+        graph[NodeIndex::from(2)].start_at_rank(3);
+        // ignored, since the incoming edges require a lower rank
+        graph[NodeIndex::from(1)].start_at_rank(1);
+        init_rank(&mut graph, 1);
+
+        let ranks = graph.node_weights().map(|v| v.rank).collect::<Vec<_>>();
+        assert_eq!(ranks, [0, 4, 3]);
+    }
+
     #[test]
     fn test_dfs_start_from_root() {
         let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH).build();
@@ -302,6 +435,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn feasible_tree_of_long_chain_with_cross_edges() {
+        // a long chain, edges skipping parts of it, and a second source which
+        // is joined to the chain only after the tree was extended
+        let n = 50_000;
+        let mut edges = (1..n).map(|v| (v - 1, v)).collect::<Vec<_>>();
+        edges.extend([(0, 10), (100, 2_000), (n / 2, n - 1), (n, n - 2)]);
+        let (mut graph, ..) = GraphBuilder::new(&edges).build();
+        init_rank(&mut graph, 1);
+        assert_eq!(
+            tight_tree(&mut graph, 0.into(), &mut HashSet::new(), 1),
+            n as usize
+        );
+
+        let (mut graph, ..) = GraphBuilder::new(&edges).build();
+        init_rank(&mut graph, 1);
+        feasible_tree(&mut graph, 1);
+        let tree_edges = graph
+            .edge_indices()
+            .filter(|e| graph[*e].is_tree_edge)
+            .collect::<Vec<_>>();
+        assert_eq!(tree_edges.len(), graph.node_count() - 1);
+        assert!(tree_edges.iter().all(|e| slack(&graph, *e, 1) == 0));
+        // the edges skipping parts of the chain are never tight
+        for (tail, head) in [(0, 10), (100, 2_000), (n / 2, n - 1)] {
+            let edge = graph.find_edge(tail.into(), head.into()).unwrap();
+            assert!(!graph[edge].is_tree_edge);
+        }
+    }
+
     #[test]
     fn test_feasible_tree_is_spanning_tree() {
         // needs to have exactly n - 1 tree edges
@@ -396,7 +559,7 @@ mod tests {
             (6, 2),
             (7, 4),
         ];
-        update_ranks(&mut graph, minimum_length);
+        update_ranks(&mut graph, 0.into(), minimum_length);
 
         for id in graph.node_indices() {
             let rank = graph[id].rank;
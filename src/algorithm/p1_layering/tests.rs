@@ -1,7 +1,14 @@
-use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableDiGraph};
+use std::collections::{HashSet, VecDeque};
+
+use petgraph::{
+    stable_graph::{EdgeIndex, NodeIndex, StableDiGraph},
+    visit::EdgeRef,
+    Direction::{Incoming, Outgoing},
+};
 
 use crate::algorithm::p1_layering::{
     cut_values::init_cutvalues, enter_edge, is_head_to_tail, leave_edge, low_lim::init_low_lim,
+    max_abs_cutvalue,
 };
 
 use super::{Edge, Vertex};
@@ -141,7 +148,7 @@ impl GraphBuilder {
         for (tail, head, cut_value) in cut_values {
             // ignore any edges that do not exist
             if let Some(edge) = self.graph.find_edge((*tail).into(), (*head).into()) {
-                self.graph[edge].cut_value = Some(*cut_value);
+                self.graph[edge].cut_value = Some(*cut_value as i64);
             }
         }
 
@@ -232,6 +239,56 @@ impl GraphBuilder {
     }
 }
 
+/// Returns the tree edges whose cached cut value is missing or differs from
+/// the one calculated from its definition, to check the incremental updates of
+/// the cut values against.
+pub(super) fn outdated_cut_values(graph: &StableDiGraph<Vertex, Edge>) -> Vec<EdgeIndex> {
+    graph
+        .edge_indices()
+        .filter(|e| graph[*e].is_tree_edge)
+        .filter(|e| graph[*e].cut_value != Some(cut_value_from_components(graph, *e)))
+        .collect()
+}
+
+/// Calculates the cut value of `edge` by splitting the tree into the
+/// components of its tail and head. The cut value is the weight of all
+/// edges from the tail to the head component, minus the weight of all edges
+/// from the head to the tail component.
+fn cut_value_from_components(graph: &StableDiGraph<Vertex, Edge>, edge: EdgeIndex) -> i64 {
+    let (tail, _) = graph.edge_endpoints(edge).unwrap();
+    let mut tail_component = HashSet::from([tail]);
+    let mut queue = VecDeque::from([tail]);
+    while let Some(v) = queue.pop_front() {
+        for e in graph
+            .edges_directed(v, Incoming)
+            .chain(graph.edges_directed(v, Outgoing))
+        {
+            let other = if e.source() == v {
+                e.target()
+            } else {
+                e.source()
+            };
+            if e.id() != edge && e.weight().is_tree_edge && tail_component.insert(other) {
+                queue.push_back(other);
+            }
+        }
+    }
+    graph
+        .edge_indices()
+        .map(|e| {
+            let (tail, head) = graph.edge_endpoints(e).unwrap();
+            match (
+                tail_component.contains(&tail),
+                tail_component.contains(&head),
+            ) {
+                (true, false) => graph[e].weight as i64,
+                (false, true) => -(graph[e].weight as i64),
+                _ => 0,
+            }
+        })
+        .sum()
+}
+
 #[test]
 fn leave_edge_has_negative_cut_value() {
     let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH)
@@ -261,6 +318,28 @@ fn leave_edge_has_no_negative_cut_value() {
     assert!(leave_edge.is_none());
 }
 
+#[test]
+fn max_abs_cutvalue_negative_cut_value() {
+    let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH)
+        .with_tree_edges(&EXAMPLE_GRAPH_FEASIBLE_TREE_NEG_CUT_VALUE)
+        .build();
+
+    init_cutvalues(&mut graph);
+
+    assert_eq!(max_abs_cutvalue(&graph), 1);
+}
+
+#[test]
+fn max_abs_cutvalue_no_negative_cut_value() {
+    let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH)
+        .with_tree_edges(&EXAMPLE_GRAPH_FEASIBLE_TREE_POS_CUT_VALUE)
+        .build();
+
+    init_cutvalues(&mut graph);
+
+    assert_eq!(max_abs_cutvalue(&graph), 0);
+}
+
 #[test]
 fn test_is_head_to_tail_true_root_in_tail() {
     // u is always considered to be the tail of the edge to be swapped
@@ -336,12 +415,42 @@ fn enter_edge_find_edge() {
 
 mod integration {
 
-    use crate::configure::{Config, RankingType};
-    use petgraph::stable_graph::StableDiGraph;
+    use std::collections::HashSet;
 
-    use crate::algorithm::p1_layering::{rank, slack, Edge, Vertex};
+    use crate::configure::{Config, LayerSplitting, RankingType};
+    use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableDiGraph};
 
-    use super::{GraphBuilder, EXAMPLE_GRAPH};
+    use crate::algorithm::is_properly_layered;
+    use crate::algorithm::p1_layering::{
+        edge_key, enter_edge, exchange, leave_edge, max_abs_cutvalue, minimize_edge_length,
+        normalize, rank,
+        ranking::{feasible_tree, init_rank, update_ranks},
+        slack, split_wide_ranks, Edge, Vertex,
+    };
+    use crate::algorithm::p2_reduce_crossings::insert_dummy_vertices;
+    use crate::util::random_numbers;
+
+    use super::{outdated_cut_values, GraphBuilder, EXAMPLE_GRAPH};
+
+    /// Ranks `graph` with the default configuration, i.e. by minimizing the
+    /// edge lengths, and returns the exchanges network simplex performed.
+    fn rank_default(graph: &mut StableDiGraph<Vertex, Edge>) -> Vec<(EdgeIndex, EdgeIndex)> {
+        rank(graph, &Config::default(), None)
+    }
+
+    /// Returns the edges of a random tree with 150 vertices and up to 150
+    /// additional random edges, which needs many exchanges to rank.
+    fn random_tree_with_edges(seed: u64) -> Vec<(u32, u32)> {
+        let mut random = random_numbers(seed);
+        let mut edges = (1..150).map(|v| (random(v), v)).collect::<Vec<_>>();
+        for _ in 0..150 {
+            let (a, b) = (random(150), random(150));
+            if a < b {
+                edges.push((a, b));
+            }
+        }
+        edges
+    }
 
     fn is_correct(graph: StableDiGraph<Vertex, Edge>, minimum_length: i32) -> bool {
         // all cut values must be positive,
@@ -368,34 +477,390 @@ mod integration {
     #[test]
     fn run_algorithm_example_graph() {
         let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH).build();
-        rank(&mut graph, 1, RankingType::MinimizeEdgeLength);
+        rank_default(&mut graph);
+        assert!(is_correct(graph, 1));
+    }
+
+    #[test]
+    fn empty_graph_is_ranked_without_panicking() {
+        let mut graph = StableDiGraph::<Vertex, Edge>::new();
+        for ranking_type in [
+            RankingType::Original,
+            RankingType::MinimizeEdgeLength,
+            RankingType::Up,
+            RankingType::Down,
+            RankingType::CoffmanGraham,
+        ] {
+            assert!(rank(
+                &mut graph,
+                &Config::default().ranking_type(ranking_type),
+                None
+            )
+            .is_empty());
+            assert_eq!(graph.node_count(), 0);
+        }
+        // rank returns before network simplex, since an empty graph has no
+        // edges with slack
+        assert!(minimize_edge_length(&mut graph, 1, None, 0, None).is_empty());
+    }
+
+    #[test]
+    fn single_vertex_is_ranked_at_zero() {
+        for ranking_type in [
+            RankingType::Original,
+            RankingType::MinimizeEdgeLength,
+            RankingType::Up,
+            RankingType::Down,
+        ] {
+            let mut graph = StableDiGraph::<Vertex, Edge>::new();
+            let v = graph.add_node(Vertex::default());
+            assert!(rank(
+                &mut graph,
+                &Config::default().ranking_type(ranking_type),
+                None
+            )
+            .is_empty());
+            assert_eq!(graph[v].rank, 0);
+            assert_eq!(graph.edge_count(), 0);
+        }
+    }
+
+    #[test]
+    fn up_ranks_by_longest_path_without_simplex() {
+        let edges = [(0, 1), (1, 2), (2, 3), (4, 3)];
+        let (mut graph, ..) = GraphBuilder::new(&edges).build();
+        assert!(rank(
+            &mut graph,
+            &Config::default().ranking_type(RankingType::Up),
+            None
+        )
+        .is_empty());
+        assert_eq!(graph[NodeIndex::from(4)].rank, 0);
+        assert_eq!(graph[NodeIndex::from(3)].rank, 3);
+
+        // network simplex shortens the edge of the second source instead
+        let (mut graph, ..) = GraphBuilder::new(&edges).build();
+        rank_default(&mut graph);
+        assert_eq!(graph[NodeIndex::from(4)].rank, 2);
+    }
+
+    #[test]
+    fn spanning_tree_is_rooted_at_given_vertex() {
+        // two sources, the first vertex is the first source
+        let edges = [(0, 2), (1, 2), (2, 3), (1, 4), (4, 3), (0, 3)];
+        let (mut expected, ..) = GraphBuilder::new(&edges).build();
+        rank_default(&mut expected);
+
+        let root = NodeIndex::from(1);
+        let (mut graph, ..) = GraphBuilder::new(&edges).build();
+        rank(&mut graph, &Config::default(), Some(root));
+        assert_eq!(graph[root].parent, None);
+        assert_eq!(graph[root].lim, graph.node_count() as u32);
+        for v in graph.node_indices() {
+            assert_eq!(graph[v].rank, expected[v].rank);
+        }
         assert!(is_correct(graph, 1));
     }
 
+    #[test]
+    fn ranks_do_not_depend_on_edge_order() {
+        // a cycle of six vertices with two ranks of the same total edge length
+        let mut edges = vec![(3, 5), (0, 3), (0, 4), (1, 2), (2, 4), (1, 5)];
+        let ranks = |edges: &[(u32, u32)]| {
+            let (mut graph, ..) = GraphBuilder::new(edges).build();
+            rank_default(&mut graph);
+            graph
+                .node_indices()
+                .map(|v| graph[v].rank)
+                .collect::<Vec<_>>()
+        };
+        let expected = ranks(&edges);
+        edges.rotate_left(1);
+        edges.reverse();
+        assert_eq!(ranks(&edges), expected);
+    }
+
+    #[test]
+    fn cut_values_stay_valid_with_parallel_edges() {
+        let mut edges = EXAMPLE_GRAPH.to_vec();
+        edges.extend([(0, 4), (0, 4), (6, 7), (1, 2), (3, 7)]);
+        let (mut graph, ..) = GraphBuilder::new(&edges).build();
+        rank_default(&mut graph);
+        assert!(outdated_cut_values(&graph).is_empty());
+        assert!(is_correct(graph, 1));
+    }
+
+    #[test]
+    fn rerooting_converges_without_negative_cut_values() {
+        let (mut expected, ..) = GraphBuilder::new(&EXAMPLE_GRAPH).build();
+        rank_default(&mut expected);
+        for reroot_every in 1..4 {
+            let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH).build();
+            rank(
+                &mut graph,
+                &Config::default().reroot_every(Some(reroot_every)),
+                None,
+            );
+            assert_eq!(max_abs_cutvalue(&graph), 0);
+            // the total edge length is still minimal
+            let total_length = |graph: &StableDiGraph<Vertex, Edge>| {
+                graph
+                    .edge_indices()
+                    .map(|e| slack(graph, e, 0))
+                    .sum::<i32>()
+            };
+            assert_eq!(total_length(&graph), total_length(&expected));
+            assert!(is_correct(graph, 1));
+        }
+    }
+
+    #[test]
+    fn replaying_exchanges_reproduces_ranking() {
+        // the initial feasible tree of the first graph is optimal, the one of
+        // the second graph is not
+        let graphs = [
+            [
+                (1, 7),
+                (3, 5),
+                (4, 5),
+                (0, 3),
+                (2, 6),
+                (0, 5),
+                (0, 6),
+                (0, 7),
+                (2, 3),
+            ],
+            [
+                (2, 5),
+                (0, 3),
+                (0, 4),
+                (1, 5),
+                (4, 5),
+                (3, 4),
+                (0, 2),
+                (1, 4),
+                (1, 2),
+            ],
+        ];
+        for (i, edges) in graphs.iter().enumerate() {
+            let (mut expected, ..) = GraphBuilder::new(edges).build();
+            let exchanges = rank_default(&mut expected);
+            assert_eq!(exchanges.is_empty(), i == 0);
+
+            let (mut graph, ..) = GraphBuilder::new(edges).build();
+            init_rank(&mut graph, 1);
+            feasible_tree(&mut graph, 1);
+            for (removed_edge, swap_edge) in &exchanges {
+                assert!(leave_edge(&graph).is_some());
+                exchange(&mut graph, *removed_edge, *swap_edge, 1);
+            }
+            normalize(&mut graph);
+            assert_eq!(leave_edge(&graph), None);
+            for v in graph.node_indices() {
+                assert_eq!(graph[v].rank, expected[v].rank);
+            }
+        }
+    }
+
+    #[test]
+    fn leaving_edges_match_full_scan() {
+        // a random tree with additional random edges, needs many exchanges
+        let edges = random_tree_with_edges(3);
+        let (mut graph, ..) = GraphBuilder::new(&edges).build();
+        let exchanges = rank_default(&mut graph);
+
+        assert!(exchanges.len() > 10);
+
+        // reference: replay the exchanges and check each leaving edge against
+        // the cut values recomputed from their definition. The leaving edge is
+        // the negative edge with the smallest key, like a full scan finds it.
+        let (mut expected, ..) = GraphBuilder::new(&edges).build();
+        init_rank(&mut expected, 1);
+        feasible_tree(&mut expected, 1);
+        for (removed_edge, swap_edge) in exchanges {
+            assert!(outdated_cut_values(&expected).is_empty());
+            let negative = expected
+                .edge_indices()
+                .filter(|e| expected[*e].cut_value.is_some_and(|c| c < 0))
+                .map(|e| edge_key(&expected, e))
+                .min();
+            assert_eq!(negative, Some(edge_key(&expected, removed_edge)));
+
+            // the entering edge has the least slack of all edges from the head
+            // to the tail component of the leaving edge
+            let (tail, _) = expected.edge_endpoints(removed_edge).unwrap();
+            let mut tail_component = HashSet::from([tail]);
+            let mut stack = vec![tail];
+            while let Some(v) = stack.pop() {
+                for e in expected.edge_indices() {
+                    let (a, b) = expected.edge_endpoints(e).unwrap();
+                    if e == removed_edge || !expected[e].is_tree_edge || (a != v && b != v) {
+                        continue;
+                    }
+                    let other = if a == v { b } else { a };
+                    if tail_component.insert(other) {
+                        stack.push(other);
+                    }
+                }
+            }
+            let entering = expected
+                .edge_indices()
+                .filter(|e| {
+                    let (a, b) = expected.edge_endpoints(*e).unwrap();
+                    !tail_component.contains(&a) && tail_component.contains(&b)
+                })
+                .min_by_key(|e| (slack(&expected, *e, 1), edge_key(&expected, *e)));
+            assert_eq!(entering, Some(swap_edge));
+            exchange(&mut expected, removed_edge, swap_edge, 1);
+
+            // only the component below the leaving edge was moved, which
+            // results in the same ranks as ranking the whole tree again
+            let mut ranked = expected.clone();
+            update_ranks(&mut ranked, 0.into(), 1);
+            for v in expected.node_indices() {
+                assert_eq!(
+                    expected[v].rank - expected[NodeIndex::new(0)].rank,
+                    ranked[v].rank
+                );
+            }
+        }
+        assert!(outdated_cut_values(&expected).is_empty());
+        assert_eq!(leave_edge(&expected), None);
+    }
+
+    #[test]
+    fn final_ranks_match_first_key_search() {
+        // the maintained set of negative edges results in the same ranking as
+        // scanning all edges for the first negative one
+        let edges = random_tree_with_edges(5);
+        for edges in [&EXAMPLE_GRAPH[..], &edges] {
+            let (mut graph, ..) = GraphBuilder::new(edges).build();
+            rank_default(&mut graph);
+
+            let (mut expected, ..) = GraphBuilder::new(edges).build();
+            init_rank(&mut expected, 1);
+            feasible_tree(&mut expected, 1);
+            while let Some(removed_edge) = leave_edge(&expected) {
+                let swap_edge = enter_edge(&mut expected, removed_edge, 1);
+                exchange(&mut expected, removed_edge, swap_edge, 1);
+            }
+            normalize(&mut expected);
+            for v in graph.node_indices() {
+                assert_eq!(graph[v].rank, expected[v].rank);
+            }
+        }
+    }
+
+    #[test]
+    fn run_algorithm_no_negative_cut_values_remain() {
+        let (mut graph, ..) = GraphBuilder::new(&EXAMPLE_GRAPH).build();
+        rank_default(&mut graph);
+        assert_eq!(max_abs_cutvalue(&graph), 0);
+    }
+
+    #[test]
+    fn properly_layered_grid_graph() {
+        // 4x4 grid, where vertex 4 * i + j has edges to the right and below,
+        // and source 16 points to the last vertex of the grid
+        let mut edges = vec![(16, 15)];
+        for i in 0..4 {
+            for j in 0..4 {
+                let v = 4 * i + j;
+                if j < 3 {
+                    edges.push((v, v + 1));
+                }
+                if i < 3 {
+                    edges.push((v, v + 4));
+                }
+            }
+        }
+        let (mut graph, ..) = GraphBuilder::new(&edges).build();
+        // the initial ranking places 16 on the first rank, far above 15
+        let mut initial = graph.clone();
+        init_rank(&mut initial, 1);
+        assert!(!is_properly_layered(&initial, 1));
+
+        rank_default(&mut graph);
+        // network simplex tightened the edge of 16
+        assert!(graph.edge_weights().any(|e| e.is_tree_edge));
+        assert!(is_properly_layered(&graph, 1));
+        for v in graph.node_indices().filter(|v| v.index() < 16) {
+            assert_eq!(graph[v].rank as usize, v.index() / 4 + v.index() % 4);
+        }
+        assert_eq!(graph[NodeIndex::new(16)].rank, 5);
+
+        let vertex_count = graph.node_count();
+        insert_dummy_vertices(&mut graph, 1, 1.0);
+        assert_eq!(graph.node_count(), vertex_count);
+    }
+
+    #[test]
+    fn split_wide_rank_into_two_bands() {
+        // 20 vertices between a source and a sink
+        let edges = (1..=20).flat_map(|v| [(0, v), (v, 21)]).collect::<Vec<_>>();
+        let (mut graph, ..) = GraphBuilder::new(&edges).build();
+        rank_default(&mut graph);
+        split_wide_ranks(&mut graph, 10, LayerSplitting::Alternate);
+
+        let mut widths = [0; 4];
+        for v in graph.node_weights() {
+            widths[v.rank as usize] += 1;
+        }
+        assert_eq!(widths, [1, 10, 10, 1]);
+        assert!(graph.edge_indices().all(|e| slack(&graph, e, 1) >= 0));
+    }
+
+    #[test]
+    fn split_wide_ranks_greedy() {
+        // 10 vertices between a source and a sink, where vertices 1 and 2 also
+        // have edges to 12 and 13
+        let mut edges = (1..=10).flat_map(|v| [(0, v), (v, 11)]).collect::<Vec<_>>();
+        edges.extend([(1, 12), (1, 13), (2, 12), (2, 13)]);
+        let (mut graph, ..) = GraphBuilder::new(&edges).build();
+        rank_default(&mut graph);
+        split_wide_ranks(&mut graph, 4, LayerSplitting::Greedy);
+
+        let max_rank = graph.node_weights().map(|v| v.rank).max().unwrap();
+        for rank in 0..=max_rank {
+            assert!(graph.node_weights().filter(|v| v.rank == rank).count() <= 4);
+        }
+        // vertices with more outgoing edges are moved down first
+        assert!(graph[NodeIndex::from(1)].rank > 1);
+        assert!(graph[NodeIndex::from(2)].rank > 1);
+        assert!(graph.edge_indices().all(|e| slack(&graph, e, 1) >= 0));
+    }
+
     #[test]
     fn run_algorithm_tree_500_nodes_three_edges_per_node() {
         use graph_generator::GraphLayout;
-        let edges = GraphLayout::new_from_num_nodes(500, 3)
-            .build_edges()
-            .into_iter()
-            .map(|(t, h)| (t as u32, h as u32))
+        let edges = GraphLayout::new_from_num_nodes(500, 3).build_edges();
+        let (mut graph, ..) = GraphBuilder::new(&edges).build();
+        rank_default(&mut graph);
+        assert!(is_correct(graph, 1));
+    }
+
+    #[test]
+    fn benchmark_network_simplex_dense_dag() {
+        // each vertex has edges to 8 random vertices among the next 50
+        let n = 3000;
+        let mut random = random_numbers(11);
+        let edges = (0..n)
+            .flat_map(|v| (0..8).map(move |_| v))
+            .map(|v| (v, v + 1 + random(50)))
+            .filter(|(_, h)| *h < n)
             .collect::<Vec<_>>();
         let (mut graph, ..) = GraphBuilder::new(&edges).build();
-        rank(&mut graph, 1, RankingType::MinimizeEdgeLength);
+        rank_default(&mut graph);
         assert!(is_correct(graph, 1));
     }
 
     #[test]
     fn run_algorithm_random_graph_1000_nodes() {
         use graph_generator::RandomLayout;
-        let edges = RandomLayout::new(1000)
-            .build_edges()
-            .into_iter()
-            .map(|(t, h)| (t as u32, h as u32))
-            .collect::<Vec<_>>();
+        let edges = RandomLayout::new(1000).build_edges();
         println!("built random layout");
         let (mut graph, ..) = GraphBuilder::new(&edges).build();
-        rank(&mut graph, 1, RankingType::MinimizeEdgeLength);
+        rank_default(&mut graph);
         assert!(is_correct(graph, 1));
     }
 
@@ -434,6 +899,6 @@ mod integration {
         let mut cfg = Config::default();
         cfg.ranking_type = RankingType::Up;
         cfg.dummy_vertices = true;
-        crate::algorithm::start(graph, &cfg);
+        crate::algorithm::start(graph, &cfg, &Default::default());
     }
 }
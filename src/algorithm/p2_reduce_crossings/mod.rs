@@ -7,9 +7,11 @@ use std::ops::{Deref, DerefMut};
 use log::{debug, info, trace};
 use petgraph::algo::toposort;
 use petgraph::stable_graph::{NodeIndex, StableDiGraph};
-use petgraph::Direction::{Incoming, Outgoing};
+use petgraph::visit::EdgeRef;
+use petgraph::Direction::{self, Incoming, Outgoing};
 
-use crate::configure::CrossingMinimization;
+use crate::configure::{Config, CrossingMinimization};
+use crate::layout::RouteStyle;
 use crate::util::{iterate, radix_sort, IterDir};
 
 use super::{slack, Edge, Vertex};
@@ -102,7 +104,7 @@ impl Order {
 
     fn crossings(&self, graph: &StableDiGraph<Vertex, Edge>) -> usize {
         let mut cross_count = 0;
-        for rank in 0..self.max_rank() - 1 {
+        for rank in 0..self.max_rank().saturating_sub(1) {
             cross_count += self.bilayer_cross_count(graph, rank);
         }
         cross_count
@@ -192,6 +194,22 @@ impl DerefMut for Order {
     }
 }
 
+/// Returns the number of dummy vertices [insert_dummy_vertices] would insert.
+pub(super) fn count_dummy_vertices(
+    graph: &StableDiGraph<Vertex, Edge>,
+    minimum_length: i32,
+) -> usize {
+    graph
+        .edge_indices()
+        .filter(|e| slack(graph, *e, minimum_length) > 0)
+        .filter(|e| graph[*e].route_style == RouteStyle::Through)
+        .map(|e| {
+            let (tail, head) = graph.edge_endpoints(e).unwrap();
+            (graph[head].rank - graph[tail].rank - 1) as usize
+        })
+        .sum()
+}
+
 pub(super) fn insert_dummy_vertices(
     graph: &mut StableDiGraph<Vertex, Edge>,
     minimum_length: i32,
@@ -201,7 +219,9 @@ pub(super) fn insert_dummy_vertices(
     // and insert dummy vertices
     info!(target: "crossing_reduction", "Inserting dummy vertices for edges spanning more than {minimum_length} ranks");
     for edge in graph.edge_indices().collect::<Vec<_>>() {
-        if slack(graph, edge, minimum_length) > 0 {
+        // edges routed along the side of the layers don't need dummies
+        if slack(graph, edge, minimum_length) > 0 && graph[edge].route_style == RouteStyle::Through
+        {
             let (mut tail, head) = graph.edge_endpoints(edge).unwrap();
             trace!(target: "crossing_reduction", 
                 "Inserting {} dummy vertices between: ({}, {})", 
@@ -209,8 +229,24 @@ pub(super) fn insert_dummy_vertices(
                 tail.index(), 
                 head.index());
 
-            // we don't need to remember edges that where removed
-            graph.remove_edge(edge);
+            // dummy edges inherit whether the original edge was reversed, its
+            // weight, multiplicity, priority and whether it may be crossed
+            let Edge {
+                reversed,
+                weight,
+                multiplicity,
+                uncrossed,
+                priority,
+                ..
+            } = graph.remove_edge(edge).unwrap();
+            let dummy_edge = Edge {
+                reversed,
+                weight,
+                multiplicity,
+                uncrossed,
+                priority,
+                ..Default::default()
+            };
             for rank in (graph[tail].rank + 1)..graph[head].rank {
                 // usize usize::MAX id as reserved value for a dummy vertex
                 let d = Vertex {
@@ -223,14 +259,17 @@ pub(super) fn insert_dummy_vertices(
                 graph[new].root = new;
                 graph[new].sink = new;
                 graph[new].rank = rank;
-                graph.add_edge(tail, new, Edge::default());
+                graph.add_edge(tail, new, dummy_edge);
                 tail = new;
             }
-            graph.add_edge(tail, head, Edge::default()); // add last dummy edge connecting to the head
+            graph.add_edge(tail, head, dummy_edge); // add last dummy edge connecting to the head
         }
     }
 }
 
+/// Removes the dummy vertices of long edges and reconnects their endpoints
+/// with the original edge. Distributors are kept, since they are part of the
+/// structure of the graph.
 pub(super) fn remove_dummy_vertices(
     graph: &mut StableDiGraph<Vertex, Edge>,
     order: &mut [Vec<NodeIndex>],
@@ -243,63 +282,318 @@ pub(super) fn remove_dummy_vertices(
     info!(target: "crossing_reduction", "Removing dummy vertices and inserting original edges.");
     let vertices = toposort(&*graph, None).unwrap();
     for v in vertices {
+        if is_chain_dummy(graph, v) {
+            continue;
+        }
         let mut edges = Vec::new();
-        for mut n in graph.neighbors_directed(v, Outgoing) {
-            if graph[n].is_dummy {
-                while graph[n].is_dummy {
-                    let dummy_neighbors = graph.neighbors_directed(n, Outgoing).collect::<Vec<_>>();
-                    //assert_eq!(dummy_neighbors.len(), 1);
-                    n = dummy_neighbors[0];
-                }
-                edges.push((v, n));
+        for first in graph
+            .edges_directed(v, Outgoing)
+            .filter(|e| is_chain_dummy(graph, e.target()))
+        {
+            // dummies of long edges have exactly one outgoing edge, the last
+            // one carries the attributes of the original edge
+            let (mut last, mut head) = (first.id(), first.target());
+            while is_chain_dummy(graph, head) {
+                let next = graph.edges_directed(head, Outgoing).next().unwrap();
+                (last, head) = (next.id(), next.target());
             }
+            edges.push((v, head, graph[last]));
         }
-        for (tail, head) in edges {
-            graph.add_edge(tail, head, Edge::default());
+        for (tail, head, edge) in edges {
+            graph.add_edge(
+                tail,
+                head,
+                Edge {
+                    reversed: edge.reversed,
+                    weight: edge.weight,
+                    multiplicity: edge.multiplicity,
+                    priority: edge.priority,
+                    ..Default::default()
+                },
+            );
         }
     }
     // remove from order
     for l in order {
-        l.retain(|v| !graph[*v].is_dummy);
+        l.retain(|v| !is_chain_dummy(graph, *v));
+    }
+    graph.retain_nodes(|g, v| !g[v].is_dummy || g[v].is_distributor);
+}
+
+/// Returns true if `v` is a dummy vertex of a long edge.
+fn is_chain_dummy(graph: &StableDiGraph<Vertex, Edge>, v: NodeIndex) -> bool {
+    graph[v].is_dummy && !graph[v].is_distributor
+}
+
+/// Reorders the layers, so that no edge crosses an edge marked as uncrossed.
+/// Layers are swept from top to bottom, from bottom to top and from top to
+/// bottom again, each time reordering a layer with respect to the previous
+/// one, see [separate_layer]. Sweeping upwards allows moving the tails of
+/// uncrossed edges, which are fixed when sweeping downwards. `constraints`
+/// take precedence over separating the edges.
+pub(super) fn separate_uncrossed_edges(
+    graph: &StableDiGraph<Vertex, Edge>,
+    order: &mut [Vec<NodeIndex>],
+    constraints: &OrderConstraints,
+) {
+    if !graph.edge_weights().any(|e| e.uncrossed) {
+        return;
+    }
+    info!(target: "crossing_reduction", "Separating edges which must not be crossed");
+    for rank in 1..order.len() {
+        let (fixed, free) = order.split_at_mut(rank);
+        separate_layer(graph, &fixed[rank - 1], &mut free[0], Incoming, constraints);
+    }
+    for rank in (0..order.len().saturating_sub(1)).rev() {
+        let (free, fixed) = order.split_at_mut(rank + 1);
+        separate_layer(graph, &fixed[0], &mut free[rank], Outgoing, constraints);
+    }
+    for rank in 1..order.len() {
+        let (fixed, free) = order.split_at_mut(rank);
+        separate_layer(graph, &fixed[rank - 1], &mut free[0], Incoming, constraints);
+    }
+}
+
+/// Reorders `free`, so that no edge between `free` and `fixed` crosses an
+/// uncrossed edge between them. `direction` is the direction of the edges
+/// from `free` towards `fixed`. A vertex is moved to the side of each
+/// uncrossed edge its neighbors in `fixed` lie on, keeping its current side
+/// if it has none. If a vertex has neighbors on both sides of an uncrossed
+/// edge, the edge can't be kept uncrossed and the vertex is placed as far left
+/// as its neighbors allow. Afterwards `free` is reordered to satisfy
+/// `constraints`.
+fn separate_layer(
+    graph: &StableDiGraph<Vertex, Edge>,
+    fixed: &[NodeIndex],
+    free: &mut Vec<NodeIndex>,
+    direction: Direction,
+    constraints: &OrderConstraints,
+) {
+    let fixed = fixed
+        .iter()
+        .enumerate()
+        .map(|(pos, v)| (*v, pos))
+        .collect::<HashMap<_, _>>();
+    let positions = free
+        .iter()
+        .enumerate()
+        .map(|(pos, v)| (*v, pos))
+        .collect::<HashMap<_, _>>();
+    // (position in fixed, vertex in free) of each uncrossed edge, ordered
+    // from left to right
+    let mut uncrossed = free
+        .iter()
+        .flat_map(|v| graph.edges_directed(*v, direction).map(move |e| (*v, e)))
+        .filter_map(|(v, e)| {
+            let other = if e.source() == v {
+                e.target()
+            } else {
+                e.source()
+            };
+            (e.weight().uncrossed && fixed.contains_key(&other)).then(|| (fixed[&other], v))
+        })
+        .collect::<Vec<_>>();
+    if uncrossed.is_empty() {
+        return;
+    }
+    uncrossed.sort_by_key(|(pos, v)| (*pos, positions[v]));
+
+    // the endpoint of the k-th uncrossed edge gets key 2k + 1, all other
+    // vertices 2s, where s is the number of uncrossed edges left of them
+    let keys = free
+        .iter()
+        .map(|w| {
+            if let Some(k) = uncrossed.iter().position(|(_, v)| v == w) {
+                return (*w, 2 * k + 1);
+            }
+            let (mut min, mut max) = (0, uncrossed.len());
+            for n in graph.neighbors_directed(*w, direction) {
+                let Some(pos) = fixed.get(&n) else {
+                    continue;
+                };
+                // edges sharing their endpoint in fixed can be on either side
+                min = min.max(uncrossed.iter().filter(|(u, _)| u < pos).count());
+                max = max.min(uncrossed.iter().filter(|(u, _)| u <= pos).count());
+            }
+            let current = uncrossed
+                .iter()
+                .filter(|(_, v)| positions[v] < positions[w])
+                .count();
+            (*w, 2 * current.min(max).max(min))
+        })
+        .collect::<HashMap<_, _>>();
+    free.sort_by_key(|v| keys[v]);
+    apply_constraints(free, constraints);
+}
+
+/// Pairs `(a, b)` of vertices, where `a` has to be placed left of `b`, if both
+/// are in the same rank. The constraints need to be transitively closed and
+/// free of cycles.
+pub(super) type OrderConstraints = HashSet<(NodeIndex, NodeIndex)>;
+
+/// Options for sweeping the layers during crossing reduction.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct SweepOptions {
+    /// The heuristic to reorder each layer by.
+    pub(super) crossing_minimization: CrossingMinimization,
+    /// Interpolates between the two medians of the median heuristic.
+    pub(super) median_weight: f64,
+    /// Whether to swap adjacent vertices after each sweep.
+    pub(super) transpose: bool,
+    /// Whether ties are broken by the order of the sources.
+    pub(super) stable_source_order: bool,
+    /// `Some((base_count, epsilon))`, if the number of sweeps depends on the
+    /// size of the graph.
+    pub(super) adaptive_sweeps: Option<(usize, f64)>,
+    /// The maximum number of sweeps, if it is [Some].
+    pub(super) max_sweeps: Option<usize>,
+}
+
+impl From<&Config> for SweepOptions {
+    fn from(config: &Config) -> Self {
+        Self {
+            crossing_minimization: config.c_minimization,
+            median_weight: config.median_weight,
+            transpose: config.transpose,
+            stable_source_order: config.stable_source_order,
+            adaptive_sweeps: config
+                .adaptive_sweeps
+                .then_some((config.sweep_base_count, config.sweep_epsilon)),
+            max_sweeps: config.max_sweeps,
+        }
     }
-    graph.retain_nodes(|g, v| !g[v].is_dummy);
 }
 
 // TODO: Maybe write store all upper neighbors on vertex directly
 pub(super) fn ordering(
     graph: &mut StableDiGraph<Vertex, Edge>,
-    crossing_minimization: CrossingMinimization,
-    transpose: bool,
+    constraints: &OrderConstraints,
+    options: &SweepOptions,
 ) -> Vec<Vec<NodeIndex>> {
-    let order = init_order(graph);
+    let mut order = init_order(graph, constraints);
+    let tie_break = if options.stable_source_order {
+        source_order(graph)
+    } else {
+        HashMap::new()
+    };
+    if !tie_break.is_empty() {
+        // start with the sources, and the vertices below them, in their order
+        let mut layers = order._inner;
+        for layer in &mut layers {
+            layer.sort_by_key(|v| tie_break[v]);
+            apply_constraints(layer, constraints);
+        }
+        order = Order::new(layers);
+    }
     // move downwards for crossing reduction
-    let cm_method = match crossing_minimization {
-        CrossingMinimization::Barycenter => self::barycenter,
-        CrossingMinimization::Median => self::median,
+    let (order, _) = match options.crossing_minimization {
+        CrossingMinimization::Barycenter => reduce_crossings_bilayer_sweep(
+            graph,
+            order,
+            self::barycenter,
+            constraints,
+            &tie_break,
+            options,
+        ),
+        CrossingMinimization::Median => reduce_crossings_bilayer_sweep(
+            graph,
+            order,
+            |graph, vertex, move_down, positions| {
+                self::median(graph, vertex, move_down, positions, options.median_weight)
+            },
+            constraints,
+            &tie_break,
+            options,
+        ),
     };
-    let order = reduce_crossings_bilayer_sweep(graph, order, cm_method, transpose);
     order._inner
 }
 
-type CMMethod =
-    fn(&StableDiGraph<Vertex, Edge>, NodeIndex, bool, &HashMap<NodeIndex, usize>) -> f64;
+/// Returns the number of crossings between each pair of adjacent `layers`.
+pub(super) fn crossings(graph: &StableDiGraph<Vertex, Edge>, layers: &[Vec<NodeIndex>]) -> usize {
+    Order::new(layers.to_vec()).crossings(graph)
+}
+
+/// Returns the number of crossings between two adjacent layers, where
+/// `endpoints` contains the position of the head of each edge in the lower
+/// layer of `south_len` vertices, ordered by the position of its tail and then
+/// of its head.
+pub(crate) fn bilayer_crossings(endpoints: Vec<usize>, south_len: usize) -> usize {
+    Order::count_crossings(endpoints, south_len)
+}
+
+/// Returns the key which breaks ties between vertices when sources are
+/// ordered by their id: the position of a source among all sources, or the
+/// smallest key of the predecessors of any other vertex. This way the order
+/// of the sources propagates downwards.
+fn source_order(graph: &StableDiGraph<Vertex, Edge>) -> HashMap<NodeIndex, usize> {
+    let mut sources = graph
+        .node_indices()
+        .filter(|v| graph.neighbors_directed(*v, Incoming).next().is_none())
+        .collect::<Vec<_>>();
+    sources.sort_by_key(|v| graph[*v].id);
+    let mut keys = sources
+        .into_iter()
+        .enumerate()
+        .map(|(key, v)| (v, key))
+        .collect::<HashMap<_, _>>();
+    for v in toposort(graph, None).unwrap() {
+        if let Some(key) = graph
+            .neighbors_directed(v, Incoming)
+            .map(|n| keys[&n])
+            .min()
+        {
+            keys.insert(v, key);
+        }
+    }
+    keys
+}
+
+/// Reorders the vertices of a layer, so that all constraints are satisfied.
+/// Vertices keep their current order as much as possible.
+fn apply_constraints(layer: &mut Vec<NodeIndex>, constraints: &OrderConstraints) {
+    if constraints.is_empty() {
+        return;
+    }
+    // topological sort, always choosing the leftmost vertex without unplaced
+    // predecessors
+    let mut remaining = std::mem::take(layer);
+    while !remaining.is_empty() {
+        let next = remaining
+            .iter()
+            .position(|v| !remaining.iter().any(|u| constraints.contains(&(*u, *v))))
+            .expect("Order constraints must not contain cycles");
+        layer.push(remaining.remove(next));
+    }
+}
 
-fn init_order(graph: &StableDiGraph<Vertex, Edge>) -> Order {
+fn init_order(graph: &StableDiGraph<Vertex, Edge>, constraints: &OrderConstraints) -> Order {
     info!(target: "crossing_reduction", 
         "Initializing order of vertices in each rank via dfs.");
 
+    // uses an explicit stack, so long paths don't overflow the call stack
     fn dfs(
         v: NodeIndex,
-        order: &mut Vec<Vec<NodeIndex>>,
+        order: &mut [Vec<NodeIndex>],
         graph: &StableDiGraph<Vertex, Edge>,
         visited: &mut HashSet<NodeIndex>,
     ) {
-        if !visited.contains(&v) {
-            visited.insert(v);
-            order[graph[v].rank as usize].push(v);
-            graph
-                .neighbors_directed(v, Outgoing)
-                .for_each(|n| dfs(n, order, graph, visited))
+        let mut stack = Vec::new();
+        let mut next = Some(v);
+        loop {
+            if let Some(v) = next.take().filter(|v| visited.insert(*v)) {
+                order[graph[v].rank as usize].push(v);
+                stack.push(graph.neighbors_directed(v, Outgoing));
+            }
+            let Some(neighbors) = stack.last_mut() else {
+                break;
+            };
+            match neighbors.next() {
+                Some(n) => next = Some(n),
+                None => {
+                    stack.pop();
+                }
+            }
         }
     }
 
@@ -316,25 +610,61 @@ fn init_order(graph: &StableDiGraph<Vertex, Edge>) -> Order {
         .node_indices()
         .for_each(|v| dfs(v, &mut order, graph, &mut visited));
 
+    for layer in &mut order {
+        apply_constraints(layer, constraints);
+    }
+
     Order::new(order)
 }
 
+/// Sweeps down and up the layers, reordering each layer by `cm_method`, until
+/// 4 sweeps in a row didn't reduce the number of crossings. If `transpose` of
+/// the `options` is set, adjacent vertices are swapped after each sweep.
+///
+/// If `adaptive_sweeps` is `Some((base_count, epsilon))`, at most `base_count`
+/// times the logarithm of the number of vertices sweeps are done instead, and
+/// crossing reduction stops early once a pair of down and up sweeps reduced
+/// the crossings by less than the fraction `epsilon`. No more than
+/// `max_sweeps` sweeps are done in any case, if it is [Some]. Vertices which
+/// `cm_method` can't tell apart are ordered by their key in `tie_break`, if
+/// it contains them, and keep their order otherwise.
+///
+/// Returns the best order found, together with the number of sweeps done.
 fn reduce_crossings_bilayer_sweep(
     graph: &StableDiGraph<Vertex, Edge>,
     mut order: Order,
-    cm_method: CMMethod,
-    transpose: bool,
-) -> Order {
+    cm_method: impl Fn(&StableDiGraph<Vertex, Edge>, NodeIndex, bool, &HashMap<NodeIndex, usize>) -> f64,
+    constraints: &OrderConstraints,
+    tie_break: &HashMap<NodeIndex, usize>,
+    options: &SweepOptions,
+) -> (Order, usize) {
+    let SweepOptions {
+        transpose,
+        adaptive_sweeps,
+        max_sweeps,
+        ..
+    } = *options;
     info!(target: "crossing_reduction", "Reducing crossings via bilayer sweep");
     let mut best_crossings = order.crossings(graph);
     debug!(target: "crossing_reduction", "Initial number of crossings: {best_crossings}");
+    let max_sweeps = adaptive_sweeps
+        .map(|(base_count, _)| {
+            base_count * (graph.node_count() as f64).log2().ceil().max(1.0) as usize
+        })
+        .into_iter()
+        .chain(max_sweeps)
+        .min();
+    let mut crossings_before_pair = best_crossings;
     let mut last_best = 0;
     let mut best = order.clone();
-    for i in 0.. {
-        order = order_layer(graph, i % 2 == 0, &order, cm_method);
+    let mut sweeps = 0;
+    while sweeps < max_sweeps.unwrap_or(usize::MAX) {
+        let move_down = sweeps % 2 == 0;
+        order = order_layer(graph, move_down, &order, &cm_method, constraints, tie_break);
         if transpose {
-            self::transpose(graph, &mut order, i % 2 == 0);
+            self::transpose(graph, &mut order, move_down, constraints);
         }
+        sweeps += 1;
         let crossings = order.crossings(graph);
         trace!(target: "crossing_reduction", "Current number of crossings: {crossings}");
         if crossings < best_crossings {
@@ -345,15 +675,33 @@ fn reduce_crossings_bilayer_sweep(
         } else {
             last_best += 1;
         }
-        if last_best == 4 {
-            info!(target: "crossing_reduction", "Didn't improve after 4 sweeps, returning");
-            return best;
+        match adaptive_sweeps {
+            Some((_, epsilon)) if !move_down => {
+                let improvement = crossings_before_pair - best_crossings;
+                if best_crossings == 0
+                    || (improvement as f64) < epsilon * crossings_before_pair as f64
+                {
+                    info!(target: "crossing_reduction", "Improvement fell below {epsilon} after {sweeps} sweeps, returning");
+                    break;
+                }
+                crossings_before_pair = best_crossings;
+            }
+            None if last_best == 4 => {
+                info!(target: "crossing_reduction", "Didn't improve after 4 sweeps, returning");
+                break;
+            }
+            _ => (),
         }
     }
-    best
+    (best, sweeps)
 }
 
-fn transpose(graph: &StableDiGraph<Vertex, Edge>, order: &mut Order, move_down: bool) {
+fn transpose(
+    graph: &StableDiGraph<Vertex, Edge>,
+    order: &mut Order,
+    move_down: bool,
+    constraints: &OrderConstraints,
+) {
     trace!(target: "crossings_reduction", 
         "Using transpose, try to swap vertices in each layer manually to reduce cross count");
 
@@ -371,6 +719,9 @@ fn transpose(graph: &StableDiGraph<Vertex, Edge>, order: &mut Order, move_down:
             for i in 0..order._inner[r].len() - 1 {
                 let v = order._inner[r][i];
                 let w = order._inner[r][i + 1];
+                if constraints.contains(&(v, w)) {
+                    continue;
+                }
                 let v_w_crossing = order.cross_count_two_vertices(v, w, graph);
                 let w_v_crossing = order.cross_count_two_vertices(w, v, graph);
                 if v_w_crossing > w_v_crossing {
@@ -387,7 +738,9 @@ fn order_layer(
     graph: &StableDiGraph<Vertex, Edge>,
     move_down: bool,
     cur_order: &Order,
-    cm_method: CMMethod,
+    cm_method: impl Fn(&StableDiGraph<Vertex, Edge>, NodeIndex, bool, &HashMap<NodeIndex, usize>) -> f64,
+    constraints: &OrderConstraints,
+    tie_break: &HashMap<NodeIndex, usize>,
 ) -> Order {
     let mut new_order = vec![Vec::new(); cur_order.max_rank()];
     let mut positions = cur_order.positions.clone();
@@ -415,7 +768,12 @@ fn order_layer(
             .map(|n| (*n, cm_method(graph, *n, move_down, &positions)))
             .collect::<HashMap<NodeIndex, f64>>();
 
-        new_order[rank].sort_by(|a, b| ordering.get(a).partial_cmp(&ordering.get(b)).unwrap());
+        new_order[rank].sort_by(|a, b| {
+            ordering[a]
+                .total_cmp(&ordering[b])
+                .then_with(|| tie_break.get(a).cmp(&tie_break.get(b)))
+        });
+        apply_constraints(&mut new_order[rank], constraints);
 
         new_order[rank].iter().enumerate().for_each(|(pos, v)| {
             positions.insert(*v, pos);
@@ -438,32 +796,48 @@ fn barycenter(
     move_down: bool,
     positions: &HashMap<NodeIndex, usize>,
 ) -> f64 {
-    let neighbors: Vec<_> = if move_down {
-        graph.neighbors_directed(vertex, Incoming).collect()
-    } else {
-        graph.neighbors_directed(vertex, Outgoing).collect()
-    };
+    let direction = if move_down { Incoming } else { Outgoing };
+    let neighbors: Vec<_> = graph
+        .edges_directed(vertex, direction)
+        .map(|e| {
+            let n = if move_down { e.source() } else { e.target() };
+            // merged parallel edges pull as much as the separate edges
+            (n, e.weight().weight as i64 * e.weight().multiplicity as i64)
+        })
+        .collect();
 
     if neighbors.is_empty() {
         return *positions.get(&vertex).unwrap() as f64;
     }
 
-    // Only look at direct neighbors
+    // Only look at direct neighbors, heavier edges pull the vertex closer
     let adjacent = neighbors
         .into_iter()
         // .filter(|n| graph[vertex].rank.abs_diff(graph[*n].rank) == 1)
-        .map(|n| *positions.get(&n).unwrap())
-        .collect::<Vec<usize>>();
+        .map(|(n, weight)| (*positions.get(&n).unwrap(), weight))
+        .collect::<Vec<(usize, i64)>>();
 
-    let bary = adjacent.iter().sum::<usize>() as f64 / adjacent.len() as f64;
-    bary
+    let total_weight = adjacent.iter().map(|(_, w)| *w as f64).sum::<f64>();
+    // without any weight, all neighbors pull equally
+    if total_weight == 0.0 {
+        return adjacent.iter().map(|(pos, _)| *pos as f64).sum::<f64>() / adjacent.len() as f64;
+    }
+    adjacent
+        .iter()
+        .map(|(pos, w)| *pos as f64 * *w as f64)
+        .sum::<f64>()
+        / total_weight
 }
 
+/// Returns the weighted median of the positions of the neighbors of `vertex`.
+/// For an even number of neighbors, `weight` biases the interpolation between
+/// the two medians: 0 picks the lower and 1 the upper median.
 fn median(
     graph: &StableDiGraph<Vertex, Edge>,
     vertex: NodeIndex,
     move_down: bool,
     positions: &HashMap<NodeIndex, usize>,
+    weight: f64,
 ) -> f64 {
     let neighbors = if move_down {
         graph.neighbors_directed(vertex, Incoming)
@@ -484,11 +858,23 @@ fn median(
         f64::MAX
     } else if length_p % 2 == 1 {
         adjacent[m] as f64
-    } else if length_p == 2 {
-        (adjacent[0] + adjacent[1]) as f64 / 2.
     } else {
-        let left = adjacent[m - 1] - adjacent[0];
-        let right = adjacent[length_p - 1] - adjacent[m];
-        (adjacent[m - 1] * right + adjacent[m] * left) as f64 / (left + right) as f64
+        let (lower, upper) = (adjacent[m - 1] as f64, adjacent[m] as f64);
+        // the median is biased towards the side where neighbors are packed
+        // more tightly
+        let (left, right) = if length_p == 2 {
+            (1.0, 1.0)
+        } else {
+            (
+                (adjacent[m - 1] - adjacent[0]) as f64,
+                (adjacent[length_p - 1] - adjacent[m]) as f64,
+            )
+        };
+        let (left, right) = (left * weight, right * (1.0 - weight));
+        if left + right == 0.0 {
+            lower + (upper - lower) * weight
+        } else {
+            (lower * right + upper * left) / (left + right)
+        }
     }
 }
@@ -260,7 +260,7 @@ mod insert_dummy_vertices {
         }
         let g = StableDiGraph::from_edges(&edges);
         let c = Config::default();
-        crate::algorithm::start(g, &c);
+        crate::algorithm::start(g, &c, &Default::default());
     }
 }
 
@@ -317,7 +317,13 @@ mod init_order {
 // TODO: Add new tests for Order crosscount
 #[cfg(test)]
 mod order {
-    use crate::algorithm::{p2::order_layer, p2_reduce_crossings::Order, Edge, Vertex};
+    use std::collections::{HashMap, HashSet};
+
+    use crate::algorithm::{
+        p2::{order_layer, separate_uncrossed_edges},
+        p2_reduce_crossings::Order,
+        Edge, Vertex,
+    };
     use petgraph::stable_graph::StableDiGraph;
 
     /// Shorthand for creating a default vertex with a specified rank.
@@ -435,10 +441,296 @@ mod order {
             false,
             &order,
             crate::algorithm::p2_reduce_crossings::barycenter,
+            &Default::default(),
+            &Default::default(),
         );
         assert_eq!(
             expected_order._inner[0],
             vec![n0, n1, n2, n3, n4, n5, n6, n7]
         );
     }
+
+    #[test]
+    fn source_order_breaks_ties() {
+        let mut graph = StableDiGraph::new();
+        let sources = (0..3)
+            .map(|id| {
+                graph.add_node(Vertex {
+                    id,
+                    ..vertex_with_rank(0)
+                })
+            })
+            .collect::<Vec<_>>();
+        let left = graph.add_node(vertex_with_rank(1));
+        let right = graph.add_node(vertex_with_rank(1));
+        for v in &sources {
+            graph.add_edge(*v, left, Edge::default());
+        }
+        graph.add_edge(sources[2], right, Edge::default());
+
+        // the barycenters of the first two sources are equal
+        let order = Order::new(vec![
+            vec![sources[1], sources[0], sources[2]],
+            vec![left, right],
+        ]);
+        let order_sources = |tie_break: &HashMap<_, _>| {
+            order_layer(
+                &graph,
+                false,
+                &order,
+                crate::algorithm::p2_reduce_crossings::barycenter,
+                &Default::default(),
+                tie_break,
+            )
+            ._inner[0]
+                .clone()
+        };
+        assert_eq!(
+            order_sources(&HashMap::new()),
+            [sources[1], sources[0], sources[2]]
+        );
+        let tie_break = crate::algorithm::p2_reduce_crossings::source_order(&graph);
+        assert_eq!(tie_break[&right], 2);
+        assert_eq!(order_sources(&tie_break), sources);
+    }
+
+    #[test]
+    fn separating_uncrossed_edges_keeps_order_constraints() {
+        let mut graph = StableDiGraph::new();
+        let a = graph.add_node(vertex_with_rank(0));
+        let b = graph.add_node(vertex_with_rank(0));
+        let x = graph.add_node(vertex_with_rank(1));
+        let y = graph.add_node(vertex_with_rank(1));
+        graph.add_edge(a, y, Edge::default().with_uncrossed(true));
+        graph.add_edge(b, x, Edge::default());
+
+        // without constraints, y is moved left of x
+        let mut order = vec![vec![a, b], vec![x, y]];
+        separate_uncrossed_edges(&graph, &mut order, &Default::default());
+        assert_eq!(order, [vec![a, b], vec![y, x]]);
+
+        // with x left of y, the sources are swapped instead
+        let mut order = vec![vec![a, b], vec![x, y]];
+        separate_uncrossed_edges(&graph, &mut order, &HashSet::from([(x, y)]));
+        assert_eq!(order, [vec![b, a], vec![x, y]]);
+    }
+
+    #[test]
+    fn barycenter_without_weight_is_the_mean() {
+        let mut graph = StableDiGraph::new();
+        let n0 = graph.add_node(vertex_with_rank(0));
+        let n1 = graph.add_node(vertex_with_rank(0));
+        let s0 = graph.add_node(vertex_with_rank(1));
+        graph.add_edge(n0, s0, Edge::default().with_weight(0));
+        graph.add_edge(n1, s0, Edge::default().with_weight(0));
+        let positions = HashMap::from([(n0, 0), (n1, 3), (s0, 0)]);
+        let bary = crate::algorithm::p2_reduce_crossings::barycenter(&graph, s0, true, &positions);
+        assert_eq!(bary, 1.5);
+    }
+
+    #[test]
+    fn median_weight_picks_lower_or_upper_median() {
+        let mut graph = StableDiGraph::new();
+        let upper = (0..6)
+            .map(|_| graph.add_node(vertex_with_rank(0)))
+            .collect::<Vec<_>>();
+        let two = graph.add_node(vertex_with_rank(1));
+        let four = graph.add_node(vertex_with_rank(1));
+        graph.add_edge(upper[1], two, Edge::default());
+        graph.add_edge(upper[4], two, Edge::default());
+        for i in [0, 2, 3, 5] {
+            graph.add_edge(upper[i], four, Edge::default());
+        }
+        let positions = upper
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (*v, i))
+            .collect::<HashMap<_, _>>();
+
+        let median = |vertex, weight| {
+            crate::algorithm::p2_reduce_crossings::median(&graph, vertex, true, &positions, weight)
+        };
+        assert_eq!(median(two, 0.0), 1.0);
+        assert_eq!(median(two, 0.5), 2.5);
+        assert_eq!(median(two, 1.0), 4.0);
+        assert_eq!(median(four, 0.0), 2.0);
+        assert_eq!(median(four, 1.0), 3.0);
+        // neighbors of both sides are spread equally
+        assert_eq!(median(four, 0.5), 2.5);
+    }
+
+    #[test]
+    fn transpose_removes_crossing_left_by_median() {
+        let mut graph = StableDiGraph::new();
+        let upper = (0..6)
+            .map(|_| graph.add_node(vertex_with_rank(0)))
+            .collect::<Vec<_>>();
+        let v = graph.add_node(vertex_with_rank(1));
+        let w = graph.add_node(vertex_with_rank(1));
+        for i in [0, 4, 5] {
+            graph.add_edge(upper[i], v, Edge::default());
+        }
+        for i in [2, 4, 5] {
+            graph.add_edge(upper[i], w, Edge::default());
+        }
+
+        // both medians are 4, so the median sweep keeps w left of v
+        let order = Order::new(vec![upper.clone(), vec![w, v]]);
+        let mut order = order_layer(
+            &graph,
+            true,
+            &order,
+            |g, v, d, p| crate::algorithm::p2_reduce_crossings::median(g, v, d, p, 0.5),
+            &Default::default(),
+            &Default::default(),
+        );
+        assert_eq!(order._inner[1], [w, v]);
+        assert_eq!(order.crossings(&graph), 4);
+
+        crate::algorithm::p2_reduce_crossings::transpose(
+            &graph,
+            &mut order,
+            true,
+            &Default::default(),
+        );
+        assert_eq!(order._inner, [upper, vec![v, w]]);
+        assert_eq!(order.crossings(&graph), 3);
+    }
+}
+
+#[cfg(test)]
+mod bilayer_sweep {
+    use crate::algorithm::p2_reduce_crossings::{
+        barycenter, init_order, reduce_crossings_bilayer_sweep, SweepOptions,
+    };
+    use crate::configure::CrossingMinimization;
+    use crate::util::random_numbers;
+
+    use super::GraphBuilder;
+
+    /// Shorthand for sweeping with the barycenter heuristic and without transpose.
+    fn sweep_options(
+        adaptive_sweeps: Option<(usize, f64)>,
+        max_sweeps: Option<usize>,
+    ) -> SweepOptions {
+        SweepOptions {
+            crossing_minimization: CrossingMinimization::Barycenter,
+            median_weight: 0.5,
+            transpose: false,
+            stable_source_order: false,
+            adaptive_sweeps,
+            max_sweeps,
+        }
+    }
+
+    #[test]
+    fn adaptive_sweeps_stop_before_cap() {
+        // properly layered graph with 10 layers of 50 vertices each, where each
+        // vertex has an edge to the vertex below it and to 2 pseudo random
+        // vertices of the next layer
+        let (layers, width) = (10, 50);
+        let mut random = random_numbers(42);
+        let mut edges = Vec::new();
+        for layer in 0..layers - 1 {
+            for v in 0..width {
+                edges.push((layer * width + v, (layer + 1) * width + v));
+                for _ in 0..2 {
+                    edges.push((layer * width + v, (layer + 1) * width + random(width)));
+                }
+            }
+        }
+        let ranks = (0..layers * width)
+            .map(|v| (v, v / width))
+            .collect::<Vec<_>>();
+        let (graph, _) = GraphBuilder::new_from_edges_with_ranking(&edges, &ranks).build();
+        let constraints = Default::default();
+        let order = init_order(&graph, &constraints);
+
+        let (fixed, _) = reduce_crossings_bilayer_sweep(
+            &graph,
+            order.clone(),
+            barycenter,
+            &constraints,
+            &Default::default(),
+            &sweep_options(None, None),
+        );
+        let (adaptive, sweeps) = reduce_crossings_bilayer_sweep(
+            &graph,
+            order,
+            barycenter,
+            &constraints,
+            &Default::default(),
+            &sweep_options(Some((10, 0.05)), None),
+        );
+        // log2(500) rounded up is 9
+        assert!(sweeps < 90);
+        let (fixed, adaptive) = (fixed.crossings(&graph), adaptive.crossings(&graph));
+        assert!((adaptive as f64) < fixed as f64 * 1.1);
+    }
+
+    #[test]
+    fn max_sweeps_keeps_best_order_seen() {
+        let edges = [
+            (0, 5),
+            (1, 5),
+            (1, 6),
+            (2, 7),
+            (2, 8),
+            (3, 4),
+            (4, 5),
+            (4, 7),
+            (4, 8),
+            (5, 9),
+            (6, 10),
+            (7, 9),
+            (8, 11),
+            (3, 10),
+        ];
+        let ranks = [
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (3, 0),
+            (4, 1),
+            (5, 1),
+            (6, 1),
+            (7, 1),
+            (8, 1),
+            (9, 2),
+            (10, 2),
+            (11, 2),
+        ];
+        let (graph, _) = GraphBuilder::new_from_edges_with_ranking(&edges, &ranks).build();
+        let constraints = Default::default();
+        let order = init_order(&graph, &constraints);
+        let initial = order.crossings(&graph);
+
+        let (unlimited, _) = reduce_crossings_bilayer_sweep(
+            &graph,
+            order.clone(),
+            barycenter,
+            &constraints,
+            &Default::default(),
+            &sweep_options(None, None),
+        );
+        let mut previous = initial;
+        for max_sweeps in 0..8 {
+            let (best, sweeps) = reduce_crossings_bilayer_sweep(
+                &graph,
+                order.clone(),
+                barycenter,
+                &constraints,
+                &Default::default(),
+                &sweep_options(None, Some(max_sweeps)),
+            );
+            assert!(sweeps <= max_sweeps);
+            // more sweeps never return an order with more crossings
+            let crossings = best.crossings(&graph);
+            assert!(crossings <= previous);
+            assert!(crossings >= unlimited.crossings(&graph));
+            previous = crossings;
+        }
+        assert_eq!(previous, unlimited.crossings(&graph));
+        assert!(unlimited.crossings(&graph) < initial);
+    }
 }
@@ -4,7 +4,7 @@ mod tests;
 use std::collections::HashMap;
 
 use log::info;
-use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::stable_graph::{EdgeIndex, NodeIndex, StableDiGraph};
 use petgraph::visit::EdgeRef;
 use petgraph::Direction::Incoming;
 
@@ -136,6 +136,12 @@ fn get_inner_segment_upper_neighbor(
     }
 }
 
+/// Returns whether `v` or `n` should be aligned with the other one.
+fn is_alignment_hint(graph: &StableDiGraph<Vertex, Edge>, v: NodeIndex, n: NodeIndex) -> bool {
+    let (v, n) = (&graph[v], &graph[n]);
+    !v.is_dummy && !n.is_dummy && (v.align_with == Some(n.id) || n.align_with == Some(v.id))
+}
+
 fn mark_type_1_conflicts(graph: &mut StableDiGraph<Vertex, Edge>, layers: &[Vec<NodeIndex>]) {
     info!(target: "coordinate_calculation", 
         "Marking type one conflicts (edge crossings between dummy vertices and non dummy vertices)");
@@ -197,10 +203,37 @@ fn create_vertical_alignments(
     layers: &mut [Vec<NodeIndex>],
 ) {
     info!(target: "coordinate_calculation", "Creating vertical alignments");
+    // vertices which should be aligned with one of their neighbors are not
+    // aligned with any other neighbor via the medians
+    let reserved = graph
+        .edge_indices()
+        .filter(|e| slack(graph, *e, 1) == 0)
+        .filter_map(|e| graph.edge_endpoints(e))
+        .filter(|(tail, head)| is_alignment_hint(graph, *tail, *head))
+        .flat_map(|(tail, head)| [(tail, head), (head, tail)])
+        .collect::<HashMap<_, _>>();
     for layer in layers {
-        let mut r = -1;
-
-        for v in layer.iter().copied() {
+        // the positions of the upper and lower vertex of each alignment made
+        // in this layer, which may not be crossed by another alignment
+        let mut aligned: Vec<(usize, usize)> = Vec::new();
+
+        // vertices with incoming edges of higher priority, and then heavier
+        // incoming edges, are aligned first, so they win conflicts with
+        // vertices with less important edges. Otherwise, vertices are aligned
+        // from left to right.
+        let mut vertices = layer.clone();
+        vertices.sort_by_key(|v| {
+            std::cmp::Reverse(
+                graph
+                    .edges_directed(*v, Incoming)
+                    .filter(|e| slack(graph, e.id(), 1) == 0)
+                    .map(|e| (e.weight().priority, e.weight().weight))
+                    .max()
+                    .unwrap_or((0, 0)),
+            )
+        });
+
+        for v in vertices {
             let mut edges = graph
                 .edges_directed(v, Incoming)
                 .filter(|e| slack(graph, e.id(), 1) == 0)
@@ -211,24 +244,41 @@ fn create_vertical_alignments(
                 continue;
             }
 
+            // a hint to align with a neighbor takes precedence over the medians
+            let hinted = edges
+                .iter()
+                .find(|(_, n)| is_alignment_hint(graph, v, *n))
+                .copied();
+
+            // only align with the neighbors connected by the edges with the
+            // highest priority and weight, to keep important edges straight
+            let importance = |e: EdgeIndex| (graph[e].priority, graph[e].weight);
+            let max_importance = edges.iter().map(|e| importance(e.0)).max().unwrap();
+            edges.retain(|e| importance(e.0) == max_importance);
+
             edges.sort_by(|e1, e2| graph[e1.1].pos.cmp(&graph[e2.1].pos));
 
             let d = (edges.len() as f64 + 1.) / 2. - 1.; // need to subtract one because indices are zero based
             let lower_upper_median = [d.floor() as usize, d.ceil() as usize];
 
-            for m in lower_upper_median {
-                if graph[v].align == v {
-                    let edge_id = edges[m].0;
-                    let median_neighbor = edges[m].1;
-
-                    if !graph[edge_id].has_type_1_conflict
-                        && r < graph[median_neighbor].pos as isize
-                    {
-                        graph[median_neighbor].align = v;
-                        graph[v].root = graph[median_neighbor].root;
-                        graph[v].align = graph[v].root;
-                        r = graph[median_neighbor].pos as isize;
-                    }
+            let medians = lower_upper_median
+                .map(|m| edges[m])
+                .into_iter()
+                .filter(|(_, n)| match reserved.get(n) {
+                    Some(partner) => *partner == v,
+                    None => true,
+                });
+            for (edge_id, median_neighbor) in hinted.into_iter().chain(medians) {
+                let (upper, lower) = (graph[median_neighbor].pos, graph[v].pos);
+                let crosses_alignment = aligned
+                    .iter()
+                    .any(|(u, l)| *u == upper || (*u < upper) != (*l < lower));
+                if graph[v].align == v && !graph[edge_id].has_type_1_conflict && !crosses_alignment
+                {
+                    graph[median_neighbor].align = v;
+                    graph[v].root = graph[median_neighbor].root;
+                    graph[v].align = graph[v].root;
+                    aligned.push((upper, lower));
                 }
             }
         }
@@ -341,6 +391,10 @@ fn place_blocks(
     }
     x_coordinates
 }
+/// Places the block of `root` and, before that, all blocks left of it. Uses
+/// an explicit stack of `(root, vertex)` pairs, where `vertex` is the vertex of
+/// the block placed next, so long chains of blocks don't overflow the call
+/// stack.
 fn place_block(
     graph: &mut StableDiGraph<Vertex, Edge>,
     layers: &[Vec<NodeIndex>],
@@ -351,11 +405,16 @@ fn place_block(
         return;
     }
     x_coordinates.insert(root, 0.0);
-    let mut w = root;
-    loop {
+    let mut stack = vec![(root, root)];
+    while let Some(&(root, w)) = stack.last() {
         if graph[w].pos > 0 {
             let u = graph[pred(graph[w], layers)].root;
-            place_block(graph, layers, u, x_coordinates);
+            if x_coordinates.get(&u).is_none() {
+                // place the block of the predecessor first, then return to w
+                x_coordinates.insert(u, 0.0);
+                stack.push((u, u));
+                continue;
+            }
             // initialize sink of current node to have the same sink as the root
             if graph[root].sink == root {
                 graph[root].sink = graph[u].sink;
@@ -372,16 +431,19 @@ fn place_block(
                 );
             }
         }
-        w = graph[w].align;
-        if w == root {
-            break;
+        let w = graph[w].align;
+        if w != root {
+            stack.last_mut().unwrap().1 = w;
+            continue;
+        }
+        stack.pop();
+        // align all other vertices in this block to the x-coordinate of the root
+        let mut w = root;
+        while graph[w].align != root {
+            w = graph[w].align;
+            x_coordinates.insert(w, *x_coordinates.get(&root).unwrap());
+            graph[w].sink = graph[root].sink;
         }
-    }
-    // align all other vertices in this block to the x-coordinate of the root
-    while graph[w].align != root {
-        w = graph[w].align;
-        x_coordinates.insert(w, *x_coordinates.get(&root).unwrap());
-        graph[w].sink = graph[root].sink;
     }
 }
 
@@ -5,20 +5,58 @@ use petgraph::stable_graph::StableDiGraph;
 
 // Default values for configuration
 pub const MINIMUM_LENGTH_DEFAULT: u32 = 1;
+pub const REVERSED_MINIMUM_LENGTH_DEFAULT: Option<u32> = None;
 pub const VERTEX_SPACING_DEFAULT: f64 = 10.0;
 pub const DUMMY_VERTICES_DEFAULT: bool = true;
 pub const RANKING_TYPE_DEFAULT: RankingType = RankingType::MinimizeEdgeLength;
+pub const REROOT_EVERY_DEFAULT: Option<usize> = None;
+pub const MAX_SIMPLEX_ITERATIONS_DEFAULT: Option<usize> = None;
 pub const C_MINIMIZATION_DEFAULT: CrossingMinimization = CrossingMinimization::Barycenter;
+pub const MEDIAN_WEIGHT_DEFAULT: f64 = 0.5;
+pub const STABLE_SOURCE_ORDER_DEFAULT: bool = false;
 pub const TRANSPOSE_DEFAULT: bool = true;
+pub const ADAPTIVE_SWEEPS_DEFAULT: bool = false;
+pub const SWEEP_BASE_COUNT_DEFAULT: usize = 2;
+pub const SWEEP_EPSILON_DEFAULT: f64 = 0.01;
+pub const MAX_SWEEPS_DEFAULT: Option<usize> = None;
 pub const DUMMY_SIZE_DEFAULT: f64 = 1.0;
+pub const MAX_DUMMIES_DEFAULT: Option<usize> = None;
+pub const VIRTUAL_SUPER_SOURCE_DEFAULT: bool = false;
+pub const BALANCE_RANKS_DEFAULT: bool = false;
+pub const MAX_LAYER_WIDTH_DEFAULT: Option<usize> = None;
+pub const TARGET_ASPECT_DEFAULT: Option<f64> = None;
+pub const MAX_FAN_OUT_DEFAULT: Option<usize> = None;
+pub const PARALLEL_EDGE_SPACING_DEFAULT: f64 = 0.0;
+pub const SELF_LOOP_SIZE_DEFAULT: f64 = 10.0;
+pub const OPTIMIZE_AREA_DEFAULT: bool = false;
+pub const LAYER_SPLITTING_DEFAULT: LayerSplitting = LayerSplitting::Alternate;
 
 const ENV_MINIMUM_LENGTH: &str = "RUST_GRAPH_MIN_LEN";
+const ENV_REVERSED_MINIMUM_LENGTH: &str = "RUST_GRAPH_REVERSED_MIN_LEN";
 const ENV_VERTEX_SPACING: &str = "RUST_GRAPH_V_SPACING";
 const ENV_DUMMY_VERTICES: &str = "RUST_GRAPH_DUMMIES";
 const ENV_RANKING_TYPE: &str = "RUST_GRAPH_R_TYPE";
+const ENV_REROOT_EVERY: &str = "RUST_GRAPH_REROOT_EVERY";
+const ENV_MAX_SIMPLEX_ITERATIONS: &str = "RUST_GRAPH_MAX_SIMPLEX_ITERATIONS";
 const ENV_CROSSING_MINIMIZATION: &str = "RUST_GRAPH_CROSS_MIN";
+const ENV_MEDIAN_WEIGHT: &str = "RUST_GRAPH_MEDIAN_WEIGHT";
+const ENV_STABLE_SOURCE_ORDER: &str = "RUST_GRAPH_STABLE_SOURCE_ORDER";
 const ENV_TRANSPOSE: &str = "RUST_GRAPH_TRANSPOSE";
+const ENV_ADAPTIVE_SWEEPS: &str = "RUST_GRAPH_ADAPTIVE_SWEEPS";
+const ENV_SWEEP_BASE_COUNT: &str = "RUST_GRAPH_SWEEP_BASE";
+const ENV_SWEEP_EPSILON: &str = "RUST_GRAPH_SWEEP_EPSILON";
+const ENV_MAX_SWEEPS: &str = "RUST_GRAPH_MAX_SWEEPS";
 const ENV_DUMMY_SIZE: &str = "RUST_GRAPH_DUMMY_SIZE";
+const ENV_MAX_DUMMIES: &str = "RUST_GRAPH_MAX_DUMMIES";
+const ENV_VIRTUAL_SUPER_SOURCE: &str = "RUST_GRAPH_SUPER_SOURCE";
+const ENV_BALANCE_RANKS: &str = "RUST_GRAPH_BALANCE_RANKS";
+const ENV_MAX_LAYER_WIDTH: &str = "RUST_GRAPH_MAX_LAYER_WIDTH";
+const ENV_TARGET_ASPECT: &str = "RUST_GRAPH_TARGET_ASPECT";
+const ENV_MAX_FAN_OUT: &str = "RUST_GRAPH_MAX_FAN_OUT";
+const ENV_PARALLEL_EDGE_SPACING: &str = "RUST_GRAPH_PARALLEL_SPACING";
+const ENV_SELF_LOOP_SIZE: &str = "RUST_GRAPH_SELF_LOOP_SIZE";
+const ENV_OPTIMIZE_AREA: &str = "RUST_GRAPH_OPTIMIZE_AREA";
+const ENV_LAYER_SPLITTING: &str = "RUST_GRAPH_LAYER_SPLITTING";
 
 pub trait IntoCoordinates {}
 
@@ -39,11 +77,28 @@ macro_rules! read_env {
     };
 }
 
+macro_rules! setters {
+    ($($field:ident: $ty:ty),* $(,)?) => {
+        $(
+            #[doc = concat!("Sets [`Self::", stringify!($field), "`] and returns the configuration.")]
+            #[must_use]
+            pub fn $field(mut self, $field: $ty) -> Self {
+                self.$field = $field;
+                self
+            }
+        )*
+    };
+}
+
 /// Used to configure parameters of the graph layout.
 #[derive(Clone, Copy, Debug)]
 pub struct Config {
     /// Length between layers.
     pub minimum_length: u32,
+    /// Length between layers for edges which were reversed to remove cycles.
+    /// If [None], [`Self::minimum_length`] is used. This controls how tightly
+    /// feedback loops are drawn.
+    pub reversed_minimum_length: Option<u32>,
     /// The minimum spacing between vertices on the same layer and between
     /// layers.
     pub vertex_spacing: f64,
@@ -52,16 +107,136 @@ pub struct Config {
     /// How much space a dummy should take up, as a multiplier of the
     /// [`Self::vertex_spacing`].
     pub dummy_size: f64,
+    /// The maximum number of dummy vertices that may be inserted. If more
+    /// dummy vertices would be needed, none are inserted and long edges are
+    /// routed directly from tail to head, as if [`Self::dummy_vertices`] was
+    /// false. This protects against running out of memory for edges spanning
+    /// a huge number of layers.
+    pub max_dummies: Option<usize>,
     /// Defines how vertices are placed vertically.
     pub ranking_type: RankingType,
+    /// Reroot the spanning tree used to minimize edge lengths at a different
+    /// vertex after this many iterations. This only changes which swaps are
+    /// explored, not the quality of the ranking, and may help if the initial
+    /// root leads to many unproductive swaps. Only has an effect if
+    /// [`Self::ranking_type`] is [RankingType::MinimizeEdgeLength].
+    pub reroot_every: Option<usize>,
+    /// The maximum number of exchanges done while minimizing edge lengths.
+    /// If it is reached, the ranking found so far is used, which is valid but
+    /// may have longer edges than necessary. Defaults to eight times the
+    /// number of vertices if [None]. Only has an effect if
+    /// [`Self::ranking_type`] is [RankingType::MinimizeEdgeLength].
+    pub max_simplex_iterations: Option<usize>,
+    /// Whether to connect all sources to a temporary virtual vertex during
+    /// ranking, which keeps all sources on the first layer. The edges to the
+    /// virtual vertex outweigh all other edges, including heavy ones. Only has
+    /// an effect if [`Self::ranking_type`] is [RankingType::MinimizeEdgeLength].
+    pub virtual_super_source: bool,
+    /// Whether to center vertices between their neighbors after minimizing
+    /// edge lengths, if they have the same weight of incoming and outgoing
+    /// edges and could be placed in several ranks. This reduces skew, but
+    /// the width of the layers no longer shows how many vertices may run in
+    /// parallel. Only has an effect if [`Self::ranking_type`] is
+    /// [RankingType::MinimizeEdgeLength].
+    pub balance_ranks: bool,
+    /// The maximum number of vertices in a layer. Wider layers are split into
+    /// multiple adjacent layers, as defined by [`Self::layer_splitting`]. This
+    /// controls the aspect ratio of the layout. Dummy vertices, which are
+    /// inserted afterwards, are not taken into account.
+    pub max_layer_width: Option<usize>,
+    /// The targeted ratio of the width to the height of the layout, where the
+    /// width is the number of vertices in the widest layer and the height the
+    /// number of layers. Layers are split like with
+    /// [`Self::max_layer_width`], using the largest width for which the ratio
+    /// is at most this value. If both are set, the smaller width is used.
+    pub target_aspect: Option<f64>,
+    /// How vertices of layers wider than [`Self::max_layer_width`] are
+    /// distributed to the new layers.
+    pub layer_splitting: LayerSplitting,
+    /// The maximum number of outgoing edges of a vertex. The children of
+    /// vertices with more outgoing edges are grouped below dummy vertices,
+    /// which form a tree with at most this many children per vertex. This
+    /// spreads the children over several layers instead of one wide layer.
+    /// The dummy vertices are kept even if [`Self::dummy_vertices`] is false.
+    /// Values below 2 are ignored.
+    pub max_fan_out: Option<usize>,
+    /// The horizontal distance between parallel edges, which would otherwise
+    /// be drawn on top of each other. If 0, parallel edges are not separated.
+    pub parallel_edge_spacing: f64,
+    /// The diameter of self-loops, which are drawn next to their vertex.
+    pub self_loop_size: f64,
     /// Which heuristic to use when minimizing edge crossings.
     pub c_minimization: CrossingMinimization,
+    /// How to interpolate between the two medians of a vertex with an even
+    /// number of neighbors, when using [CrossingMinimization::Median]. 0 picks
+    /// the lower and 1 the upper median, 0.5 weighs both equally.
+    pub median_weight: f64,
+    /// Whether sources in the same layer are ordered from left to right by
+    /// their index, e.g. to match the order of inputs, if crossing reduction
+    /// has no preference. The vertices below them follow the same order. Order
+    /// constraints given by the user take precedence.
+    pub stable_source_order: bool,
     /// Whether to attempt to further reduce crossings by swapping vertices in a
     /// layer. This may increase runtime significantly.
     pub transpose: bool,
+    /// Whether to scale the number of crossing reduction sweeps with the size
+    /// of the graph. If true, at most [`Self::sweep_base_count`] times the
+    /// logarithm of the number of vertices sweeps are done, stopping early if
+    /// the crossings are reduced by less than the fraction
+    /// [`Self::sweep_epsilon`]. Otherwise sweeps continue until the crossings
+    /// didn't decrease for 4 sweeps.
+    pub adaptive_sweeps: bool,
+    /// The number of sweeps per doubling of the number of vertices, if
+    /// [`Self::adaptive_sweeps`] is true.
+    pub sweep_base_count: usize,
+    /// The minimum fraction by which a down and up sweep need to reduce the
+    /// crossings to continue, if [`Self::adaptive_sweeps`] is true.
+    pub sweep_epsilon: f64,
+    /// The maximum number of crossing reduction sweeps, in addition to the
+    /// limits above. The order with the fewest crossings seen in any sweep is
+    /// used, even if later sweeps increase the crossings again.
+    pub max_sweeps: Option<usize>,
+    /// Whether to search for the layout with the smallest bounding box area.
+    /// If true, each component is laid out with every [RankingType], with and
+    /// without splitting layers into layers of roughly the square root of the
+    /// number of vertices. The layout with the smallest area is kept, so
+    /// [`Self::ranking_type`] is only used if no other ranking type results in
+    /// a smaller area. All other settings, e.g. [`Self::vertex_spacing`], are
+    /// kept, and a smaller [`Self::max_layer_width`] is never raised. This
+    /// multiplies the runtime by up to 10.
+    pub optimize_area: bool,
 }
 
 impl Config {
+    setters! {
+        minimum_length: u32,
+        reversed_minimum_length: Option<u32>,
+        vertex_spacing: f64,
+        dummy_vertices: bool,
+        dummy_size: f64,
+        max_dummies: Option<usize>,
+        ranking_type: RankingType,
+        reroot_every: Option<usize>,
+        max_simplex_iterations: Option<usize>,
+        virtual_super_source: bool,
+        balance_ranks: bool,
+        max_layer_width: Option<usize>,
+        target_aspect: Option<f64>,
+        layer_splitting: LayerSplitting,
+        max_fan_out: Option<usize>,
+        parallel_edge_spacing: f64,
+        self_loop_size: f64,
+        c_minimization: CrossingMinimization,
+        median_weight: f64,
+        stable_source_order: bool,
+        transpose: bool,
+        adaptive_sweeps: bool,
+        sweep_base_count: usize,
+        sweep_epsilon: f64,
+        max_sweeps: Option<usize>,
+        optimize_area: bool,
+    }
+
     /// Read in configuration values from environment variables.
     ///
     /// Envs that can be set include:
@@ -69,12 +244,31 @@ impl Config {
     /// | ENV | values | default | description |
     /// | --- | ------ | ------- | ----------- |
     /// | RUST_GRAPH_MIN_LEN    | integer, > 0         | 1          | minimum edge length between layers |
+    /// | RUST_GRAPH_REVERSED_MIN_LEN | integer, > 0   | min len    | minimum edge length between layers for edges reversed to remove cycles |
     /// | RUST_GRAPH_V_SPACING  | integer, > 0         | 10         | minimum spacing between vertices on the same layer |
     /// | RUST_GRAPH_DUMMIES    | y \| n               | y          | if dummy vertices are included in the final layout |
-    /// | RUST_GRAPH_R_TYPE     | original \| minimize \| up \| down | minimize   | defines how vertices are places vertically |
+    /// | RUST_GRAPH_R_TYPE     | original \| minimize \| up (longest) \| down \| coffman | minimize   | defines how vertices are places vertically |
+    /// | RUST_GRAPH_REROOT_EVERY | integer, > 0       | never      | number of iterations after which the spanning tree of the ranking is rerooted |
+    /// | RUST_GRAPH_MAX_SIMPLEX_ITERATIONS | integer, >= 0 | 8 * vertices | maximum number of iterations while minimizing edge lengths |
     /// | RUST_GRAPH_CROSS_MIN  | barycenter \| median | barycenter | which heuristic to use for crossing reduction |
+    /// | RUST_GRAPH_MEDIAN_WEIGHT | float, 0 <= v <= 1 | 0.5       | interpolation between the lower (0) and upper (1) median of vertices with an even number of neighbors |
+    /// | RUST_GRAPH_STABLE_SOURCE_ORDER | y \| n       | n          | if sources in the same layer are ordered by their index |
     /// | RUST_GRAPH_TRANSPOSE  | y \| n               | y          | if transpose function is used to further try to reduce crossings (may increase runtime significally for large graphs) |
+    /// | RUST_GRAPH_ADAPTIVE_SWEEPS | y \| n          | n          | if the number of crossing reduction sweeps scales with the size of the graph |
+    /// | RUST_GRAPH_SWEEP_BASE | integer, > 0        | 2          | number of sweeps per doubling of the number of vertices, if sweeps are adaptive |
+    /// | RUST_GRAPH_SWEEP_EPSILON | float, >= 0      | 0.01       | minimum fraction of crossings a down and up sweep needs to remove to continue, if sweeps are adaptive |
+    /// | RUST_GRAPH_MAX_SWEEPS | integer, >= 0       | unlimited  | maximum number of crossing reduction sweeps |
     /// | RUST_GRAPH_DUMMY_SIZE | float, 1 >= v > 0    | 1.0        |size of dummy vertices in final layout, if dummy vertices are included. this will squish the graph horizontally |
+    /// | RUST_GRAPH_MAX_DUMMIES | integer, >= 0       | unlimited  | maximum number of dummy vertices, long edges are not split into dummy vertices if it is exceeded |
+    /// | RUST_GRAPH_SUPER_SOURCE | y \| n             | n          | if all sources are connected to a virtual vertex during ranking, placing them on the first layer |
+    /// | RUST_GRAPH_BALANCE_RANKS | y \| n            | n          | if vertices are centered between their neighbors after minimizing edge lengths |
+    /// | RUST_GRAPH_MAX_LAYER_WIDTH | integer, > 0     | unlimited  | maximum number of vertices in a layer, wider layers are split into multiple layers |
+    /// | RUST_GRAPH_TARGET_ASPECT | float, > 0         | none       | targeted ratio of the number of vertices in the widest layer to the number of layers, wider layers are split into multiple layers |
+    /// | RUST_GRAPH_PARALLEL_SPACING | float, >= 0     | 0.0        | horizontal distance between the routes of parallel edges, 0 disables separating them |
+    /// | RUST_GRAPH_SELF_LOOP_SIZE | float, > 0        | 10.0       | diameter of self-loops |
+    /// | RUST_GRAPH_MAX_FAN_OUT | integer, > 1        | unlimited  | maximum number of outgoing edges of a vertex, more children are grouped below dummy vertices |
+    /// | RUST_GRAPH_LAYER_SPLITTING | alternate \| greedy | alternate | how vertices of layers wider than the maximum layer width are distributed |
+    /// | RUST_GRAPH_OPTIMIZE_AREA | y \| n            | n          | if multiple layouts are tried to find the one with the smallest area |
     pub fn new_from_env() -> Self {
         let mut config = Self::default();
 
@@ -90,14 +284,38 @@ impl Config {
             ENV_MINIMUM_LENGTH
         );
 
+        read_env!(
+            config.reversed_minimum_length,
+            (|x| x.parse::<u32>().map(Some)),
+            ENV_REVERSED_MINIMUM_LENGTH
+        );
+
         read_env!(
             config.c_minimization,
             (TryFrom::try_from),
             ENV_CROSSING_MINIMIZATION
         );
 
+        read_env!(
+            config.median_weight,
+            (|x| x.parse::<f64>()),
+            ENV_MEDIAN_WEIGHT
+        );
+
         read_env!(config.ranking_type, (TryFrom::try_from), ENV_RANKING_TYPE);
 
+        read_env!(
+            config.reroot_every,
+            (|x| x.parse::<usize>().map(Some)),
+            ENV_REROOT_EVERY
+        );
+
+        read_env!(
+            config.max_simplex_iterations,
+            (|x| x.parse::<usize>().map(Some)),
+            ENV_MAX_SIMPLEX_ITERATIONS
+        );
+
         read_env!(
             config.vertex_spacing,
             (|x| x.parse::<f64>()),
@@ -108,8 +326,86 @@ impl Config {
 
         read_env!(config.dummy_size, (|x| x.parse::<f64>()), ENV_DUMMY_SIZE);
 
+        read_env!(
+            config.stable_source_order,
+            parse_bool,
+            ENV_STABLE_SOURCE_ORDER
+        );
+
         read_env!(config.transpose, parse_bool, ENV_TRANSPOSE);
 
+        read_env!(config.adaptive_sweeps, parse_bool, ENV_ADAPTIVE_SWEEPS);
+
+        read_env!(
+            config.sweep_base_count,
+            (|x| x.parse::<usize>()),
+            ENV_SWEEP_BASE_COUNT
+        );
+
+        read_env!(
+            config.sweep_epsilon,
+            (|x| x.parse::<f64>()),
+            ENV_SWEEP_EPSILON
+        );
+
+        read_env!(
+            config.max_sweeps,
+            (|x| x.parse::<usize>().map(Some)),
+            ENV_MAX_SWEEPS
+        );
+
+        read_env!(
+            config.virtual_super_source,
+            parse_bool,
+            ENV_VIRTUAL_SUPER_SOURCE
+        );
+
+        read_env!(config.balance_ranks, parse_bool, ENV_BALANCE_RANKS);
+
+        read_env!(
+            config.max_layer_width,
+            (|x| x.parse::<usize>().map(Some)),
+            ENV_MAX_LAYER_WIDTH
+        );
+
+        read_env!(
+            config.target_aspect,
+            (|x| x.parse::<f64>().map(Some)),
+            ENV_TARGET_ASPECT
+        );
+
+        read_env!(
+            config.max_fan_out,
+            (|x| x.parse::<usize>().map(Some)),
+            ENV_MAX_FAN_OUT
+        );
+
+        read_env!(
+            config.layer_splitting,
+            (TryFrom::try_from),
+            ENV_LAYER_SPLITTING
+        );
+
+        read_env!(
+            config.parallel_edge_spacing,
+            (|x| x.parse::<f64>()),
+            ENV_PARALLEL_EDGE_SPACING
+        );
+
+        read_env!(
+            config.self_loop_size,
+            (|x| x.parse::<f64>()),
+            ENV_SELF_LOOP_SIZE
+        );
+
+        read_env!(config.optimize_area, parse_bool, ENV_OPTIMIZE_AREA);
+
+        read_env!(
+            config.max_dummies,
+            (|x| x.parse::<usize>().map(Some)),
+            ENV_MAX_DUMMIES
+        );
+
         config
     }
 }
@@ -118,12 +414,31 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             minimum_length: MINIMUM_LENGTH_DEFAULT,
+            reversed_minimum_length: REVERSED_MINIMUM_LENGTH_DEFAULT,
             vertex_spacing: VERTEX_SPACING_DEFAULT,
             dummy_vertices: DUMMY_VERTICES_DEFAULT,
             ranking_type: RANKING_TYPE_DEFAULT,
+            reroot_every: REROOT_EVERY_DEFAULT,
+            max_simplex_iterations: MAX_SIMPLEX_ITERATIONS_DEFAULT,
             c_minimization: C_MINIMIZATION_DEFAULT,
+            median_weight: MEDIAN_WEIGHT_DEFAULT,
+            stable_source_order: STABLE_SOURCE_ORDER_DEFAULT,
             transpose: TRANSPOSE_DEFAULT,
+            adaptive_sweeps: ADAPTIVE_SWEEPS_DEFAULT,
+            sweep_base_count: SWEEP_BASE_COUNT_DEFAULT,
+            sweep_epsilon: SWEEP_EPSILON_DEFAULT,
+            max_sweeps: MAX_SWEEPS_DEFAULT,
             dummy_size: DUMMY_SIZE_DEFAULT,
+            max_dummies: MAX_DUMMIES_DEFAULT,
+            virtual_super_source: VIRTUAL_SUPER_SOURCE_DEFAULT,
+            balance_ranks: BALANCE_RANKS_DEFAULT,
+            max_layer_width: MAX_LAYER_WIDTH_DEFAULT,
+            target_aspect: TARGET_ASPECT_DEFAULT,
+            parallel_edge_spacing: PARALLEL_EDGE_SPACING_DEFAULT,
+            self_loop_size: SELF_LOOP_SIZE_DEFAULT,
+            layer_splitting: LAYER_SPLITTING_DEFAULT,
+            max_fan_out: MAX_FAN_OUT_DEFAULT,
+            optimize_area: OPTIMIZE_AREA_DEFAULT,
         }
     }
 }
@@ -135,10 +450,17 @@ pub enum RankingType {
     Original,
     /// Tries to minimize edge lengths across layers
     MinimizeEdgeLength,
-    /// Move vertices as far up as possible
+    /// Move vertices as far up as possible. This is the longest path
+    /// layering, which skips network simplex and is much cheaper than
+    /// [Self::MinimizeEdgeLength] for large graphs, at the cost of longer
+    /// edges.
     Up,
     /// Move vertices as far down as possible
     Down,
+    /// Fills ranks via the Coffman-Graham algorithm, so that no rank contains
+    /// more than [`Config::max_layer_width`] vertices. Unlike splitting the
+    /// layers afterwards, vertices are placed with the width in mind.
+    CoffmanGraham,
 }
 
 impl TryFrom<String> for RankingType {
@@ -148,8 +470,9 @@ impl TryFrom<String> for RankingType {
         match value.as_str() {
             "original" => Ok(Self::Original),
             "minimize" => Ok(Self::MinimizeEdgeLength),
-            "up" => Ok(Self::Up),
+            "up" | "longest" => Ok(Self::Up),
             "down" => Ok(Self::Down),
+            "coffman" => Ok(Self::CoffmanGraham),
             s => Err(format!("invalid value for ranking type: {s}")),
         }
     }
@@ -162,6 +485,7 @@ impl From<RankingType> for &'static str {
             RankingType::Down => "down",
             RankingType::Original => "original",
             RankingType::MinimizeEdgeLength => "minimize",
+            RankingType::CoffmanGraham => "coffman",
         }
     }
 }
@@ -198,24 +522,96 @@ impl From<CrossingMinimization> for &'static str {
     }
 }
 
+/// Defines how the vertices of a layer exceeding the maximum layer width are
+/// distributed to the layers it is split into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayerSplitting {
+    /// Alternately moves vertices down, so all new layers have about the same
+    /// width.
+    Alternate,
+    /// Keeps as many vertices as possible in the original layer, and moves
+    /// the excess down. Vertices with more outgoing than incoming edges are
+    /// moved first, since moving them shortens edges.
+    Greedy,
+}
+
+impl TryFrom<String> for LayerSplitting {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "alternate" => Ok(Self::Alternate),
+            "greedy" => Ok(Self::Greedy),
+            s => Err(format!("invalid value for layer splitting: {s}")),
+        }
+    }
+}
+
+impl From<LayerSplitting> for &'static str {
+    fn from(value: LayerSplitting) -> Self {
+        match value {
+            LayerSplitting::Alternate => "alternate",
+            LayerSplitting::Greedy => "greedy",
+        }
+    }
+}
+
 #[test]
 fn from_env_all_valid() {
     use std::env;
     env::set_var(ENV_MINIMUM_LENGTH, "5");
+    env::set_var(ENV_REVERSED_MINIMUM_LENGTH, "2");
     env::set_var(ENV_DUMMY_VERTICES, "y");
     env::set_var(ENV_DUMMY_SIZE, "0.1");
     env::set_var(ENV_RANKING_TYPE, "up");
+    env::set_var(ENV_REROOT_EVERY, "7");
+    env::set_var(ENV_MAX_SIMPLEX_ITERATIONS, "50");
     env::set_var(ENV_CROSSING_MINIMIZATION, "median");
+    env::set_var(ENV_MEDIAN_WEIGHT, "0.25");
+    env::set_var(ENV_STABLE_SOURCE_ORDER, "y");
     env::set_var(ENV_TRANSPOSE, "n");
+    env::set_var(ENV_ADAPTIVE_SWEEPS, "y");
+    env::set_var(ENV_SWEEP_BASE_COUNT, "3");
+    env::set_var(ENV_SWEEP_EPSILON, "0.05");
+    env::set_var(ENV_MAX_SWEEPS, "12");
     env::set_var(ENV_VERTEX_SPACING, "20");
+    env::set_var(ENV_MAX_DUMMIES, "100");
+    env::set_var(ENV_VIRTUAL_SUPER_SOURCE, "y");
+    env::set_var(ENV_BALANCE_RANKS, "y");
+    env::set_var(ENV_MAX_LAYER_WIDTH, "10");
+    env::set_var(ENV_TARGET_ASPECT, "0.5");
+    env::set_var(ENV_PARALLEL_EDGE_SPACING, "2.5");
+    env::set_var(ENV_SELF_LOOP_SIZE, "4");
+    env::set_var(ENV_LAYER_SPLITTING, "greedy");
+    env::set_var(ENV_MAX_FAN_OUT, "4");
+    env::set_var(ENV_OPTIMIZE_AREA, "y");
     let cfg = Config::new_from_env();
     assert_eq!(cfg.minimum_length, 5);
+    assert_eq!(cfg.reversed_minimum_length, Some(2));
     assert_eq!(cfg.dummy_vertices, true);
     assert_eq!(cfg.dummy_size, 0.1);
     assert_eq!(cfg.ranking_type, RankingType::Up);
+    assert_eq!(cfg.reroot_every, Some(7));
+    assert_eq!(cfg.max_simplex_iterations, Some(50));
     assert_eq!(cfg.c_minimization, CrossingMinimization::Median);
+    assert_eq!(cfg.median_weight, 0.25);
+    assert!(cfg.stable_source_order);
     assert_eq!(cfg.transpose, false);
+    assert!(cfg.adaptive_sweeps);
+    assert_eq!(cfg.sweep_base_count, 3);
+    assert_eq!(cfg.sweep_epsilon, 0.05);
+    assert_eq!(cfg.max_sweeps, Some(12));
     assert_eq!(cfg.vertex_spacing, 20.0);
+    assert_eq!(cfg.max_dummies, Some(100));
+    assert!(cfg.virtual_super_source);
+    assert!(cfg.balance_ranks);
+    assert_eq!(cfg.max_layer_width, Some(10));
+    assert_eq!(cfg.target_aspect, Some(0.5));
+    assert_eq!(cfg.parallel_edge_spacing, 2.5);
+    assert_eq!(cfg.self_loop_size, 4.0);
+    assert_eq!(cfg.layer_splitting, LayerSplitting::Greedy);
+    assert_eq!(cfg.max_fan_out, Some(4));
+    assert!(cfg.optimize_area);
 }
 
 #[test]
@@ -229,3 +625,21 @@ fn from_env_invalid_value() {
     assert_eq!(default.c_minimization, cfg.c_minimization);
     assert_eq!(default.vertex_spacing, cfg.vertex_spacing);
 }
+
+#[test]
+fn setters_chain_from_default() {
+    let config = Config::default()
+        .minimum_length(2)
+        .ranking_type(RankingType::Up)
+        .c_minimization(CrossingMinimization::Median)
+        .vertex_spacing(20.0)
+        .balance_ranks(true)
+        .max_sweeps(Some(4));
+    assert_eq!(config.minimum_length, 2);
+    assert_eq!(config.ranking_type, RankingType::Up);
+    assert_eq!(config.c_minimization, CrossingMinimization::Median);
+    assert_eq!(config.vertex_spacing, 20.0);
+    assert!(config.balance_ranks);
+    assert_eq!(config.max_sweeps, Some(4));
+    assert_eq!(config.transpose, TRANSPOSE_DEFAULT);
+}
@@ -0,0 +1,60 @@
+//! Contains the errors which may occur when creating a layout.
+
+use std::fmt::Display;
+
+/// An error that prevents a layout from being created or changed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayoutError {
+    /// The order constraints of the vertices contain a cycle, so they can't
+    /// all be satisfied.
+    ConstraintCycle,
+    /// Adding an edge would create a cycle.
+    EdgeCreatesCycle,
+    /// A vertex pinned to the first layer or chosen as root of the ranking has
+    /// incoming edges, a vertex pinned to the last layer has outgoing edges,
+    /// or a pinned vertex is not part of the graph.
+    InvalidPin,
+    /// A vertex is pinned more than once.
+    DuplicatePin,
+    /// An edge references a vertex which does not exist, or a vertex is not
+    /// part of the graph or layout.
+    UnknownVertex,
+    /// An edge which must not be crossed is crossed by another edge in every
+    /// order of the layers that was tried.
+    CannotSatisfyNonCrossing,
+    /// A layer which is not part of the layout was referenced.
+    UnknownLayer,
+    /// The new order of a layer is not a permutation of its vertices.
+    InvalidOrder,
+    /// Moving a vertex to a layer would make one of its edges shorter than
+    /// its minimum length, or reverse it.
+    InfeasibleRank,
+    /// An empty set of vertices was given where at least one vertex is
+    /// required.
+    NoVertices,
+}
+
+impl Display for LayoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ConstraintCycle => f.write_str("order constraints contain a cycle"),
+            Self::EdgeCreatesCycle => f.write_str("edge would create a cycle"),
+            Self::InvalidPin => f.write_str("pinned vertex can't be placed in its layer"),
+            Self::DuplicatePin => f.write_str("vertex is pinned more than once"),
+            Self::UnknownVertex => f.write_str("unknown vertex"),
+            Self::CannotSatisfyNonCrossing => {
+                f.write_str("edge which must not be crossed can't be kept uncrossed")
+            }
+            Self::UnknownLayer => f.write_str("layer does not exist"),
+            Self::InvalidOrder => {
+                f.write_str("new order is not a permutation of the vertices in the layer")
+            }
+            Self::InfeasibleRank => {
+                f.write_str("vertex can't be moved to the layer without shortening its edges")
+            }
+            Self::NoVertices => f.write_str("set of vertices is empty"),
+        }
+    }
+}
+
+impl std::error::Error for LayoutError {}
@@ -0,0 +1,418 @@
+//! Contains [IncrementalLayout], which allows building up a graph one edge at
+//! a time, while keeping a feasible ranking of its vertices.
+
+use std::collections::{HashMap, HashSet};
+
+use log::debug;
+use petgraph::{
+    algo::{has_path_connecting, toposort},
+    graphmap::DiGraphMap,
+    stable_graph::{NodeIndex, StableDiGraph},
+    Direction::{Incoming, Outgoing},
+};
+
+use crate::{
+    algorithm::{self, Edge, Vertex},
+    configure::Config,
+    error::LayoutError,
+    layout::Layout,
+};
+
+/// A graph which is built by adding edges one at a time, e.g. when edges are
+/// streamed in.
+///
+/// After each edge, the ranking of the vertices is kept feasible, i.e. each
+/// edge spans at least [Config::minimum_length] ranks. Edges which would
+/// create a cycle are rejected. The best layout for the current graph can be
+/// calculated at any time via [Self::layout].
+///
+/// Sets of vertices can be collapsed into a single proxy vertex via
+/// [Self::collapse] and restored later via [Self::expand].
+#[derive(Clone, Debug)]
+pub struct IncrementalLayout {
+    config: Config,
+    edges: Vec<(u32, u32)>,
+    graph: DiGraphMap<u32, ()>,
+    ranks: HashMap<u32, usize>,
+    collapsed: HashMap<u32, Collapsed>,
+}
+
+/// The vertices and edges replaced by a proxy vertex.
+#[derive(Clone, Debug)]
+struct Collapsed {
+    vertices: Vec<u32>,
+    edges: Vec<(u32, u32)>,
+}
+
+impl IncrementalLayout {
+    /// Creates an empty graph, which will be laid out with `config`.
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            edges: Vec::new(),
+            graph: DiGraphMap::new(),
+            ranks: HashMap::new(),
+            collapsed: HashMap::new(),
+        }
+    }
+
+    /// Adds the edge from `tail` to `head`, adding the vertices if they don't
+    /// exist yet. Moves `head` and all vertices reachable from it down as far
+    /// as needed to keep the ranking feasible.
+    ///
+    /// Returns [LayoutError::EdgeCreatesCycle] and leaves the graph unchanged
+    /// if `tail` can be reached from `head`.
+    pub fn add_edge(&mut self, tail: u32, head: u32) -> Result<(), LayoutError> {
+        if tail == head
+            || (self.graph.contains_node(tail)
+                && self.graph.contains_node(head)
+                && has_path_connecting(&self.graph, head, tail, None))
+        {
+            return Err(LayoutError::EdgeCreatesCycle);
+        }
+        self.edges.push((tail, head));
+        self.graph.add_edge(tail, head, ());
+        self.ranks.entry(tail).or_insert(0);
+        self.ranks.entry(head).or_insert(0);
+
+        // push down all vertices which are too close to their predecessor
+        let minimum_length = self.config.minimum_length as usize;
+        let mut stack = vec![(tail, head)];
+        while let Some((tail, head)) = stack.pop() {
+            let minimum_rank = self.ranks[&tail] + minimum_length;
+            if self.ranks[&head] < minimum_rank {
+                debug!(target: "incremental", "Moving vertex {head} to rank {minimum_rank}");
+                self.ranks.insert(head, minimum_rank);
+                stack.extend(
+                    self.graph
+                        .neighbors_directed(head, Outgoing)
+                        .map(|n| (head, n)),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the current rank of `vertex`, or [None] if it wasn't added yet.
+    pub fn rank(&self, vertex: u32) -> Option<usize> {
+        self.ranks.get(&vertex).copied()
+    }
+
+    /// Returns the number of edges of the longest path of the graph, computed
+    /// in a single topological sweep independent of the ranking. Each edge
+    /// spans at least one rank, so every layout of the graph has at least
+    /// `min_height() + 1` ranks.
+    pub fn min_height(&self) -> i32 {
+        // edges creating a cycle are rejected, so the graph is always acyclic
+        let mut lengths = HashMap::new();
+        for v in toposort(&self.graph, None).unwrap() {
+            let length = self
+                .graph
+                .neighbors_directed(v, Incoming)
+                .map(|n| lengths[&n] + 1)
+                .max()
+                .unwrap_or(0);
+            lengths.insert(v, length);
+        }
+        lengths.into_values().max().unwrap_or(0)
+    }
+
+    /// Returns all edges added so far, in the order they were added.
+    pub fn edges(&self) -> &[(u32, u32)] {
+        &self.edges
+    }
+
+    /// Replaces `vertices` by a single new proxy vertex and returns it. Edges
+    /// between the collapsed vertices are removed, edges from or to the rest
+    /// of the graph are rerouted to the proxy. Parallel edges created by
+    /// rerouting are merged, all other edges are kept as they are.
+    ///
+    /// Returns [LayoutError::NoVertices] if `vertices` is empty and
+    /// [LayoutError::UnknownVertex] if one of them is not part of the graph.
+    /// The collapsed vertices need to form a contiguous subgraph, i.e. no
+    /// path may leave the set and enter it again. Otherwise rerouting creates
+    /// a cycle, in which case [LayoutError::EdgeCreatesCycle] is returned.
+    /// The graph is left unchanged if an error is returned.
+    pub fn collapse(&mut self, vertices: &[u32]) -> Result<u32, LayoutError> {
+        if vertices.is_empty() {
+            return Err(LayoutError::NoVertices);
+        }
+        if !vertices.iter().all(|v| self.graph.contains_node(*v)) {
+            return Err(LayoutError::UnknownVertex);
+        }
+        let mut collapsed_vertices = HashSet::new();
+        let vertices = vertices
+            .iter()
+            .copied()
+            .filter(|v| collapsed_vertices.insert(*v))
+            .collect::<Vec<_>>();
+        let proxy = self
+            .graph
+            .nodes()
+            .chain(
+                self.collapsed
+                    .values()
+                    .flat_map(|c| c.vertices.iter().copied()),
+            )
+            .max()
+            .map_or(0, |id| id + 1);
+
+        let mut removed_edges = Vec::new();
+        let mut rerouted_edges = HashSet::new();
+        let mut new_edges = Vec::new();
+        for &(tail, head) in &self.edges {
+            let edge = match (
+                collapsed_vertices.contains(&tail),
+                collapsed_vertices.contains(&head),
+            ) {
+                (false, false) => {
+                    new_edges.push((tail, head));
+                    continue;
+                }
+                (true, true) => {
+                    removed_edges.push((tail, head));
+                    continue;
+                }
+                (true, false) => (proxy, head),
+                (false, true) => (tail, proxy),
+            };
+            removed_edges.push((tail, head));
+            if rerouted_edges.insert(edge) {
+                new_edges.push(edge);
+            }
+        }
+
+        let new_vertices = self
+            .graph
+            .nodes()
+            .filter(|v| !collapsed_vertices.contains(v))
+            .chain([proxy])
+            .collect();
+        self.rebuild(new_vertices, new_edges)?;
+        debug!(target: "incremental", "Collapsed vertices {vertices:?} into proxy {proxy}");
+        self.collapsed.insert(
+            proxy,
+            Collapsed {
+                vertices,
+                edges: removed_edges,
+            },
+        );
+        Ok(proxy)
+    }
+
+    /// Restores the vertices and edges which were replaced by `proxy` via
+    /// [Self::collapse] and returns the restored vertices. Edges which were
+    /// added to the proxy after collapsing are removed.
+    ///
+    /// Returns [None] if `proxy` is not a proxy vertex.
+    pub fn expand(&mut self, proxy: u32) -> Option<Vec<u32>> {
+        let collapsed = self.collapsed.remove(&proxy)?;
+        let mut edges = self
+            .edges
+            .iter()
+            .copied()
+            .filter(|&(tail, head)| tail != proxy && head != proxy)
+            .collect::<Vec<_>>();
+        edges.extend(collapsed.edges);
+        let vertices = self
+            .graph
+            .nodes()
+            .filter(|v| *v != proxy)
+            .chain(collapsed.vertices.iter().copied())
+            .collect();
+        // the edges existed before collapsing, so they can't create a cycle
+        self.rebuild(vertices, edges).unwrap();
+        debug!(target: "incremental", "Expanded proxy {proxy} into vertices {:?}", collapsed.vertices);
+        Some(collapsed.vertices)
+    }
+
+    /// Replaces all vertices by `vertices` and all edges by `edges`, and
+    /// recalculates the ranking. The vertices are added explicitly, so ones
+    /// without edges, like the proxy of a whole component, are kept. Leaves
+    /// the graph unchanged if the edges contain a cycle.
+    fn rebuild(&mut self, vertices: Vec<u32>, edges: Vec<(u32, u32)>) -> Result<(), LayoutError> {
+        let mut graph = DiGraphMap::<u32, ()>::new();
+        for v in vertices {
+            graph.add_node(v);
+        }
+        for &(tail, head) in &edges {
+            graph.add_edge(tail, head, ());
+        }
+        let order = toposort(&graph, None).map_err(|_| LayoutError::EdgeCreatesCycle)?;
+        // the longest path ranking, which adding the edges one at a time
+        // results in as well
+        let minimum_length = self.config.minimum_length as usize;
+        let mut ranks = HashMap::new();
+        for v in order {
+            let rank = graph
+                .neighbors_directed(v, Incoming)
+                .map(|n| ranks[&n] + minimum_length)
+                .max()
+                .unwrap_or(0);
+            ranks.insert(v, rank);
+        }
+        self.edges = edges;
+        self.graph = graph;
+        self.ranks = ranks;
+        Ok(())
+    }
+
+    /// Calculates the layout of the current graph. Ranking starts from the
+    /// maintained ranks instead of building an initial ranking from scratch.
+    pub fn layout(&self) -> Vec<Layout<usize>> {
+        algorithm::start(self.ranked_graph(), &self.config, &HashSet::new())
+    }
+
+    /// Returns the graph of the current edges, whose vertices start ranking
+    /// in their maintained rank.
+    fn ranked_graph(&self) -> StableDiGraph<Vertex, Edge> {
+        let mut graph = StableDiGraph::from_edges(&self.edges);
+        // vertices without edges aren't created by their edges
+        let node_bound = self.graph.nodes().max().map_or(0, |v| v as usize + 1);
+        while graph.node_count() < node_bound {
+            graph.add_node(Vertex::default());
+        }
+        graph.retain_nodes(|_, v| self.graph.contains_node(v.index() as u32));
+        for (&vertex, &rank) in &self.ranks {
+            graph[NodeIndex::new(vertex as usize)].start_at_rank(rank as i32);
+        }
+        graph
+    }
+}
+
+#[test]
+fn ranking_stays_feasible_after_each_edge() {
+    // later edges force vertices added earlier to move down
+    let edges = [(0, 1), (2, 3), (1, 2), (0, 3), (3, 4), (1, 4), (5, 0)];
+    let mut incremental = IncrementalLayout::new(Config::default());
+    for (tail, head) in edges {
+        incremental.add_edge(tail, head).unwrap();
+        for (tail, head) in incremental.edges() {
+            let (tail, head) = (incremental.rank(*tail), incremental.rank(*head));
+            assert!(head.unwrap() > tail.unwrap());
+        }
+        // starting from the maintained ranks gives the same layout
+        let layouts = incremental.layout();
+        let expected = crate::layout_from_edges(incremental.edges(), &Config::default());
+        assert_eq!(layouts.len(), expected.len());
+        for (layout, expected) in layouts.iter().zip(&expected) {
+            assert_eq!(layout.layers(), expected.layers());
+        }
+    }
+    assert_eq!(incremental.rank(4), Some(5));
+}
+
+#[test]
+fn min_height_of_chain_and_diamond() {
+    let mut incremental = IncrementalLayout::new(Config::default());
+    assert_eq!(incremental.min_height(), 0);
+    for (tail, head) in [(0, 1), (1, 2), (2, 3), (3, 4)] {
+        incremental.add_edge(tail, head).unwrap();
+    }
+    assert_eq!(incremental.min_height(), 4);
+    assert!(incremental.layout()[0].layers().len() > 4);
+
+    // the shortcut doesn't shorten the longest path
+    let mut incremental = IncrementalLayout::new(Config::default());
+    for (tail, head) in [(0, 1), (0, 2), (1, 3), (2, 3), (0, 3)] {
+        incremental.add_edge(tail, head).unwrap();
+    }
+    assert_eq!(incremental.min_height(), 2);
+    assert!(incremental.layout()[0].layers().len() > 2);
+}
+
+#[test]
+fn edge_creating_cycle_is_rejected() {
+    let mut incremental = IncrementalLayout::new(Config::default());
+    incremental.add_edge(0, 1).unwrap();
+    incremental.add_edge(1, 2).unwrap();
+    assert_eq!(
+        incremental.add_edge(2, 0),
+        Err(LayoutError::EdgeCreatesCycle)
+    );
+    assert_eq!(
+        incremental.add_edge(1, 1),
+        Err(LayoutError::EdgeCreatesCycle)
+    );
+    assert_eq!(incremental.edges(), [(0, 1), (1, 2)]);
+    assert_eq!(incremental.rank(2), Some(2));
+}
+
+#[test]
+fn collapse_and_expand_diamond() {
+    let mut incremental = IncrementalLayout::new(Config::default());
+    for (tail, head) in [(0, 1), (0, 2), (1, 3), (2, 3)] {
+        incremental.add_edge(tail, head).unwrap();
+    }
+
+    let proxy = incremental.collapse(&[1, 2]).unwrap();
+    assert_eq!(proxy, 4);
+    assert_eq!(incremental.edges(), [(0, 4), (4, 3)]);
+    let layouts = incremental.layout();
+    assert_eq!(layouts.len(), 1);
+    assert_eq!(layouts[0].layers(), [vec![0], vec![4], vec![3]]);
+
+    assert_eq!(incremental.expand(proxy), Some(vec![1, 2]));
+    assert_eq!(incremental.expand(proxy), None);
+    assert_eq!(incremental.rank(4), None);
+    assert_eq!(incremental.rank(3), Some(2));
+    let layouts = incremental.layout();
+    let layer_sizes = layouts[0].layers().iter().map(Vec::len).collect::<Vec<_>>();
+    assert_eq!(layer_sizes, [1, 2, 1]);
+}
+
+#[test]
+fn collapsing_non_contiguous_vertices_is_rejected() {
+    let mut incremental = IncrementalLayout::new(Config::default());
+    incremental.add_edge(0, 1).unwrap();
+    incremental.add_edge(1, 2).unwrap();
+    assert_eq!(
+        incremental.collapse(&[0, 2]),
+        Err(LayoutError::EdgeCreatesCycle)
+    );
+    assert_eq!(incremental.edges(), [(0, 1), (1, 2)]);
+}
+
+#[test]
+fn collapse_only_merges_rerouted_edges() {
+    let mut incremental = IncrementalLayout::new(Config::default());
+    for (tail, head) in [(0, 1), (0, 1), (1, 2), (1, 3), (2, 4), (3, 4)] {
+        incremental.add_edge(tail, head).unwrap();
+    }
+    assert_eq!(incremental.collapse(&[]), Err(LayoutError::NoVertices));
+    assert_eq!(
+        incremental.collapse(&[2, 9]),
+        Err(LayoutError::UnknownVertex)
+    );
+    assert_eq!(incremental.edges().len(), 6);
+
+    let proxy = incremental.collapse(&[2, 3, 3]).unwrap();
+    assert_eq!(incremental.edges(), [(0, 1), (0, 1), (1, 5), (5, 4)]);
+    assert_eq!(incremental.rank(4), Some(3));
+    assert_eq!(incremental.expand(proxy), Some(vec![2, 3]));
+    assert_eq!(incremental.edges().len(), 6);
+}
+
+#[test]
+fn collapse_and_expand_whole_component() {
+    let mut incremental = IncrementalLayout::new(Config::default());
+    for (tail, head) in [(0, 1), (2, 3), (3, 4)] {
+        incremental.add_edge(tail, head).unwrap();
+    }
+
+    // the proxy has no edges left, but is still part of the graph
+    let proxy = incremental.collapse(&[2, 3, 4]).unwrap();
+    assert_eq!(proxy, 5);
+    assert_eq!(incremental.edges(), [(0, 1)]);
+    assert_eq!(incremental.rank(proxy), Some(0));
+    let layouts = incremental.layout();
+    assert_eq!(layouts.len(), 2);
+    assert_eq!(layouts[1].layers(), [vec![5]]);
+
+    incremental.add_edge(1, proxy).unwrap();
+    assert_eq!(incremental.rank(proxy), Some(2));
+    assert_eq!(incremental.expand(proxy), Some(vec![2, 3, 4]));
+    assert_eq!(incremental.rank(proxy), None);
+    assert_eq!(incremental.rank(4), Some(2));
+    assert_eq!(incremental.layout().len(), 2);
+}
@@ -0,0 +1,2135 @@
+//! Contains the [Layout] of a single connected component of a graph, which
+//! holds the coordinates of each vertex as well as the route of each edge.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Write};
+use std::hash::Hash;
+
+use petgraph::{
+    algo::toposort,
+    stable_graph::{NodeIndex, StableDiGraph},
+    visit::EdgeRef,
+    Direction::Incoming,
+};
+
+use crate::error::LayoutError;
+#[cfg(feature = "timing")]
+use std::time::Duration;
+
+/// The maximum factor [Layout::fit_aspect_ratio] stretches a layout by.
+pub const MAX_STRETCH: f64 = 10.0;
+
+/// The layout of a weakly connected component of a graph.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "LayoutFields<T>")
+)]
+pub struct Layout<T> {
+    pub(crate) vertices: Vec<(T, (f64, f64))>,
+    /// The width and height of each vertex, in the same order as `vertices`.
+    pub(crate) sizes: Vec<(f64, f64)>,
+    pub(crate) edges: Vec<RoutedEdge<T>>,
+    pub(crate) layers: Vec<Vec<T>>,
+    /// The vertices of each layer including dummy vertices, which are marked
+    /// with `true`.
+    pub(crate) layers_with_dummies: Vec<Vec<(T, bool)>>,
+    /// The x coordinate of each vertex in `layers_with_dummies`.
+    pub(crate) layer_xs: Vec<Vec<f64>>,
+    /// The edges between each pair of adjacent layers, as the positions of
+    /// their tail and head in `layers_with_dummies`. Merged parallel edges are
+    /// contained once.
+    pub(crate) layer_edges: Vec<Vec<(usize, usize)>>,
+    /// The dummy vertices each edge of `edges` passes through, ordered like
+    /// its points, as their layer and their position in
+    /// `layers_with_dummies`.
+    pub(crate) edge_dummies: Vec<Vec<(usize, usize)>>,
+    /// The minimum number of layers each edge of `edges` spans.
+    pub(crate) minimum_lengths: Vec<usize>,
+    /// The space between the vertices of a layer.
+    pub(crate) vertex_spacing: f64,
+    /// The width of dummy vertices, or [None] if the layout doesn't contain
+    /// dummy vertices.
+    pub(crate) dummy_size: Option<f64>,
+    /// Edges of the input graph from or to a vertex which is not part of the
+    /// layout.
+    pub(crate) truncated_edges: Vec<(T, T)>,
+    pub(crate) self_loops: Vec<(T, SelfLoopGeometry)>,
+    pub(crate) width: f64,
+    pub(crate) height: f64,
+    pub(crate) simplex_iterations: usize,
+    pub(crate) max_abs_cutvalue: i32,
+    pub(crate) layer_crossings: usize,
+    #[cfg(feature = "timing")]
+    pub(crate) timings: PhaseTimings,
+    #[cfg(feature = "trace")]
+    pub(crate) simplex_trace: Vec<SimplexExchange<T>>,
+}
+
+/// The `(leaving, entering)` tree edges of an exchange network simplex
+/// performed during ranking, given as `(tail, head)`.
+pub type SimplexExchange<T> = ((T, T), (T, T));
+
+/// An edge of the input graph, together with the points it is routed
+/// through.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoutedEdge<T> {
+    /// The tail of the edge, as specified in the input graph.
+    pub tail: T,
+    /// The head of the edge, as specified in the input graph.
+    pub head: T,
+    /// The points of the edge, ordered from tail to head. The first and last
+    /// point are the coordinates of the tail and head. Points in between are
+    /// the coordinates of the dummy vertices the edge passes through, if
+    /// dummy vertices are included in the layout, or a bend separating the
+    /// edge from parallel edges.
+    pub points: Vec<(f64, f64)>,
+    /// Whether the edge was reversed during cycle removal. The points of a
+    /// reversed edge still pass through each layer in order, but move from
+    /// the bottom to the top of the layout.
+    pub reversed: bool,
+}
+
+/// The wall-clock duration of each phase of the algorithm, when calculating
+/// a [Layout].
+#[cfg(feature = "timing")]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhaseTimings {
+    pub cycle_removal: Duration,
+    pub ranking: Duration,
+    pub crossing_reduction: Duration,
+    pub coordinate_assignment: Duration,
+}
+
+#[cfg(feature = "timing")]
+impl PhaseTimings {
+    /// Returns the sum of the durations of all phases.
+    pub fn total(&self) -> Duration {
+        self.cycle_removal + self.ranking + self.crossing_reduction + self.coordinate_assignment
+    }
+}
+
+/// Metrics of the layering of a [Layout], as returned by [Layout::stats],
+/// e.g. to monitor the quality of layouts over time.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayoutStats {
+    /// The number of layers.
+    pub ranks: usize,
+    /// The number of vertices in the widest layer, including dummy vertices.
+    pub max_layer_width: usize,
+    /// The sum of the number of layers each edge spans.
+    pub total_edge_length: usize,
+    /// The number of crossings, see [Layout::count_crossings].
+    pub crossings: usize,
+    /// The number of dummy vertices inserted for edges spanning multiple
+    /// layers.
+    pub dummy_vertices: usize,
+    /// The number of exchanges performed by network simplex, see
+    /// [Layout::simplex_iterations].
+    pub simplex_iterations: usize,
+}
+
+/// How an edge spanning multiple layers is routed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RouteStyle {
+    /// Route the edge through dummy vertices in each layer it crosses, which
+    /// take part in crossing reduction and coordinate assignment.
+    #[default]
+    Through,
+    /// Route the edge along the left or right margin of each layer it
+    /// crosses, whichever is closer. No dummy vertices are inserted for the
+    /// edge, so it doesn't take up space between the vertices of a layer.
+    Side,
+}
+
+/// How the points of an edge are connected, as returned by
+/// [Layout::edge_paths_with].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EdgeRouting {
+    /// A single straight line from the tail to the head.
+    Straight,
+    /// Straight lines through the dummy vertices of the edge.
+    #[default]
+    Polyline,
+    /// Horizontal and vertical segments only. Between the points of two
+    /// layers, the edge bends halfway between them, so the route steps from
+    /// one dummy vertex to the next.
+    Orthogonal,
+}
+
+/// The position of a single vertex in a [Layout].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeData<T> {
+    /// The identifier of the vertex.
+    pub id: T,
+    /// The layer of the vertex.
+    pub rank: usize,
+    /// The position of the vertex in its layer, from left to right.
+    pub order: usize,
+    /// The x coordinate of the vertex.
+    pub x: f64,
+    /// The y coordinate of the vertex.
+    pub y: f64,
+}
+
+/// The differences between two layouts of an evolving graph, as returned by
+/// [Layout::diff].
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LayoutDiff<T> {
+    /// The vertices contained in both layouts, whose rank or order changed,
+    /// as `(old, new)`.
+    pub moved: Vec<(NodeData<T>, NodeData<T>)>,
+    /// The vertices only contained in the new layout.
+    pub added: Vec<T>,
+    /// The vertices only contained in the previous layout.
+    pub removed: Vec<T>,
+}
+
+/// An axis aligned rectangle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rect {
+    /// The x coordinate of the left side.
+    pub x: f64,
+    /// The y coordinate of the top side.
+    pub y: f64,
+    /// The extent along the x axis.
+    pub width: f64,
+    /// The extent along the y axis.
+    pub height: f64,
+}
+
+/// The geometry of a self-loop, drawn as a half circle at the right side of
+/// its vertex.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelfLoopGeometry {
+    /// The center of the circle, on the right border of the vertex.
+    pub center: (f64, f64),
+    /// The radius of the circle.
+    pub radius: f64,
+    /// The point where the loop leaves the vertex, above the center.
+    pub start: (f64, f64),
+    /// The point where the loop enters the vertex, below the center.
+    pub end: (f64, f64),
+}
+
+/// A primitive needed to draw a [Layout], independent of the format it is
+/// rendered to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCommand<T> {
+    /// A vertex of the graph, occupying `rect`.
+    Node { id: T, rect: Rect },
+    /// An edge of the graph, where `id` is its index in
+    /// [Layout::routed_edges], drawn as lines through the points of
+    /// `polyline`.
+    Edge {
+        id: usize,
+        polyline: Vec<(f64, f64)>,
+    },
+    /// A self-loop of the vertex `id`, drawn as a half circle along
+    /// `geometry`.
+    SelfLoop { id: T, geometry: SelfLoopGeometry },
+    /// Text centered at `pos`.
+    Label { pos: (f64, f64), text: String },
+}
+
+/// Options for rendering a [Layout] via [Layout::to_svg].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SvgOptions {
+    /// If [Some], vertices are drawn as circles with this radius, otherwise
+    /// as rectangles with the size they were given as input.
+    pub node_radius: Option<f64>,
+    /// The space between the outermost vertices and edges and the border of
+    /// the document.
+    pub margin: f64,
+    /// The fill color of vertices.
+    pub node_fill: String,
+    /// The outline color of vertices.
+    pub node_stroke: String,
+    /// The color of edges.
+    pub edge_stroke: String,
+    /// The color of vertex labels.
+    pub label_fill: String,
+    /// If [Some], all coordinates and sizes are rounded to this number of
+    /// decimal places.
+    pub coordinate_precision: Option<u32>,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            node_radius: None,
+            margin: 10.0,
+            node_fill: "white".to_string(),
+            node_stroke: "black".to_string(),
+            edge_stroke: "black".to_string(),
+            label_fill: "black".to_string(),
+            coordinate_precision: None,
+        }
+    }
+}
+
+/// The role of a vertex in the input graph, determined by its edges.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NodeRole {
+    /// The vertex only has outgoing edges.
+    Source,
+    /// The vertex only has incoming edges.
+    Sink,
+    /// The vertex has both incoming and outgoing edges.
+    Internal,
+    /// The vertex has no edges.
+    Isolated,
+}
+
+impl<T> Layout<T> {
+    /// Returns the x and y coordinates of each vertex.
+    pub fn coordinates(&self) -> &[(T, (f64, f64))] {
+        &self.vertices
+    }
+
+    /// Returns the width of the layout, i.e. the number of vertices in the
+    /// widest layer.
+    pub fn width(&self) -> f64 {
+        self.width
+    }
+
+    /// Returns the height of the layout, i.e. the number of layers.
+    pub fn height(&self) -> f64 {
+        self.height
+    }
+
+    /// Returns all edges of the layout, along with the points they are routed
+    /// through.
+    pub fn routed_edges(&self) -> &[RoutedEdge<T>] {
+        &self.edges
+    }
+
+    /// Returns the number of exchanges network simplex performed while
+    /// minimizing edge lengths. If it equals
+    /// [crate::configure::Config::max_simplex_iterations], the ranking may not be
+    /// optimal.
+    pub fn simplex_iterations(&self) -> usize {
+        self.simplex_iterations
+    }
+
+    /// Returns the absolute value of the most negative cut value left after
+    /// network simplex, or 0 if there is none. It is only greater than 0 if
+    /// network simplex was stopped by
+    /// [crate::configure::Config::max_simplex_iterations] before the ranking
+    /// was optimal, and shrinks the more iterations are allowed. Cut values
+    /// beyond [i32::MAX] are returned as [i32::MAX].
+    pub fn max_abs_cutvalue(&self) -> i32 {
+        self.max_abs_cutvalue
+    }
+
+    /// Returns the number of crossings of the final order of the layers,
+    /// counted with the bilayer cross count of Barth et al. between each pair
+    /// of adjacent layers, which takes `O(E log V)`. Edges through dummy
+    /// vertices are counted at each layer they pass. Edges spanning multiple
+    /// layers without dummy vertices, e.g. if
+    /// [crate::configure::Config::max_dummies] was exceeded, are not counted.
+    ///
+    /// Unlike [Self::count_route_intersections], this doesn't depend on the
+    /// routes of the edges.
+    pub fn count_crossings(&self) -> usize {
+        self.layer_crossings
+    }
+
+    /// Counts the crossings of the edges between each pair of adjacent
+    /// layers, like [Self::count_crossings] returns them.
+    fn count_layer_crossings(&self) -> usize {
+        self.layer_edges
+            .iter()
+            .enumerate()
+            .map(|(rank, edges)| {
+                let mut edges = edges.clone();
+                edges.sort_unstable();
+                crate::algorithm::bilayer_crossings(
+                    edges.into_iter().map(|(_, head)| head).collect(),
+                    self.layers_with_dummies[rank + 1].len(),
+                )
+            })
+            .sum()
+    }
+
+    /// Returns the duration of each phase of the algorithm.
+    #[cfg(feature = "timing")]
+    pub fn timings(&self) -> &PhaseTimings {
+        &self.timings
+    }
+
+    /// Returns the `(leaving, entering)` tree edges of each exchange network
+    /// simplex performed during ranking, in order. The edges are given as
+    /// `(tail, head)` after cycle removal, i.e. reversed edges point from
+    /// their head to their tail in the input graph.
+    ///
+    /// Exchanges involving the temporary vertices and edges added while
+    /// ranking are left out, i.e. those of
+    /// [crate::configure::Config::virtual_super_source], of partial ranks and
+    /// of a given ranking root. Replaying the trace only reproduces the final
+    /// ranking if none of these were used. Only
+    /// contains exchanges if the graph was ranked via
+    /// [crate::configure::RankingType::MinimizeEdgeLength].
+    #[cfg(feature = "trace")]
+    pub fn simplex_trace(&self) -> &[SimplexExchange<T>] {
+        &self.simplex_trace
+    }
+
+    /// Returns the vertices of each layer ordered from left to right, including
+    /// the dummy vertices long edges are routed through, which are marked with
+    /// `true`. Identifiers of dummy vertices are only unique among dummy
+    /// vertices.
+    ///
+    /// See [Self::layers] for only the vertices of the input graph.
+    pub fn iter_layers_with_dummies(&self) -> impl Iterator<Item = &[(T, bool)]> {
+        self.layers_with_dummies.iter().map(Vec::as_slice)
+    }
+
+    /// Returns the smallest and largest x and y coordinates of the layout, as
+    /// `((min_x, min_y), (max_x, max_y))`. This includes the coordinates of
+    /// all vertices, as well as the points of all edges.
+    pub fn bounding_box(&self) -> ((f64, f64), (f64, f64)) {
+        self.vertices
+            .iter()
+            .map(|(_, coords)| coords)
+            .chain(self.edges.iter().flat_map(|e| &e.points))
+            .fold(
+                (
+                    (f64::INFINITY, f64::INFINITY),
+                    (f64::NEG_INFINITY, f64::NEG_INFINITY),
+                ),
+                |((min_x, min_y), (max_x, max_y)), (x, y)| {
+                    (
+                        (min_x.min(*x), min_y.min(*y)),
+                        (max_x.max(*x), max_y.max(*y)),
+                    )
+                },
+            )
+    }
+
+    /// Returns the edges of the input graph which lead from or to a vertex that
+    /// was excluded from the layout, as `(tail, head)`. Only layouts of a
+    /// part of a graph, like [crate::layout_ego], contain truncated edges.
+    pub fn truncated_edges(&self) -> &[(T, T)] {
+        &self.truncated_edges
+    }
+
+    /// Returns the self-loops of the layout, together with their geometry.
+    /// Self-loops are ignored when calculating the layout, so they are not
+    /// part of [Self::routed_edges].
+    pub fn self_loops(&self) -> &[(T, SelfLoopGeometry)] {
+        &self.self_loops
+    }
+
+    /// Mirrors the layout horizontally at the center of its
+    /// [bounding box](Self::bounding_box), reversing the order of each layer.
+    /// Edge routes are mirrored as well, so self-loops end up at the left side
+    /// of their vertex. Mirroring twice restores the original layout.
+    pub fn mirror_horizontal(&mut self) {
+        let ((min_x, _), (max_x, _)) = self.bounding_box();
+        let mirror = |(x, _): &mut (f64, f64)| *x = min_x + max_x - *x;
+        self.vertices.iter_mut().for_each(|(_, c)| mirror(c));
+        self.edges
+            .iter_mut()
+            .flat_map(|e| &mut e.points)
+            .for_each(mirror);
+        for (_, geometry) in &mut self.self_loops {
+            mirror(&mut geometry.center);
+            mirror(&mut geometry.start);
+            mirror(&mut geometry.end);
+        }
+        self.layers.iter_mut().for_each(|l| l.reverse());
+        self.layers_with_dummies
+            .iter_mut()
+            .for_each(|l| l.reverse());
+        for xs in &mut self.layer_xs {
+            xs.iter_mut().for_each(|x| *x = min_x + max_x - *x);
+            xs.reverse();
+        }
+        for (layer, pos) in self.edge_dummies.iter_mut().flatten() {
+            *pos = self.layers_with_dummies[*layer].len() - 1 - *pos;
+        }
+        for (rank, edges) in self.layer_edges.iter_mut().enumerate() {
+            let (north, south) = (
+                self.layers_with_dummies[rank].len(),
+                self.layers_with_dummies[rank + 1].len(),
+            );
+            for (tail, head) in edges {
+                (*tail, *head) = (north - 1 - *tail, south - 1 - *head);
+            }
+        }
+    }
+
+    /// Places `layouts` next to each other from left to right, so that at
+    /// least `component_gap` remains between the rightmost vertex or edge of
+    /// one layout and the leftmost of the next. The extent of each layout
+    /// includes the width of its vertices. Layers with the same index are
+    /// merged, and the tops of all layouts stay aligned.
+    ///
+    /// Identifiers of dummy vertices are no longer unique after merging.
+    pub fn merge(layouts: impl IntoIterator<Item = Layout<T>>, component_gap: f64) -> Layout<T> {
+        let mut merged = Layout {
+            vertices: Vec::new(),
+            sizes: Vec::new(),
+            edges: Vec::new(),
+            layers: Vec::new(),
+            layers_with_dummies: Vec::new(),
+            layer_xs: Vec::new(),
+            layer_edges: Vec::new(),
+            edge_dummies: Vec::new(),
+            minimum_lengths: Vec::new(),
+            vertex_spacing: 0.0,
+            dummy_size: None,
+            truncated_edges: Vec::new(),
+            self_loops: Vec::new(),
+            width: 0.0,
+            height: 0.0,
+            simplex_iterations: 0,
+            max_abs_cutvalue: 0,
+            layer_crossings: 0,
+            #[cfg(feature = "timing")]
+            timings: PhaseTimings::default(),
+            #[cfg(feature = "trace")]
+            simplex_trace: Vec::new(),
+        };
+        let mut left = None;
+        for mut layout in layouts {
+            let (min_x, max_x) = layout.horizontal_extent();
+            if let Some(left) = left {
+                layout.translate(left + component_gap - min_x, 0.0);
+            }
+            left = Some(match left {
+                Some(left) => left + component_gap + max_x - min_x,
+                None => max_x,
+            });
+
+            let layer_count = merged.layers.len().max(layout.layers.len());
+            merged.layers.resize_with(layer_count, Vec::new);
+            merged
+                .layers_with_dummies
+                .resize_with(layer_count, Vec::new);
+            merged.layer_xs.resize_with(layer_count, Vec::new);
+            merged
+                .layer_edges
+                .resize_with(layer_count.saturating_sub(1), Vec::new);
+            // the vertices of the layout are appended to each layer
+            let offsets = merged
+                .layers_with_dummies
+                .iter()
+                .map(Vec::len)
+                .collect::<Vec<_>>();
+            for (rank, edges) in layout.layer_edges.into_iter().enumerate() {
+                merged.layer_edges[rank].extend(
+                    edges
+                        .into_iter()
+                        .map(|(tail, head)| (tail + offsets[rank], head + offsets[rank + 1])),
+                );
+            }
+            merged
+                .edge_dummies
+                .extend(layout.edge_dummies.into_iter().map(|dummies| {
+                    dummies
+                        .into_iter()
+                        .map(|(layer, pos)| (layer, pos + offsets[layer]))
+                        .collect()
+                }));
+            for (merged, xs) in merged.layer_xs.iter_mut().zip(layout.layer_xs) {
+                merged.extend(xs);
+            }
+            merged.minimum_lengths.extend(layout.minimum_lengths);
+            merged.vertex_spacing = merged.vertex_spacing.max(layout.vertex_spacing);
+            merged.dummy_size = merged.dummy_size.or(layout.dummy_size);
+            for (merged, layer) in merged.layers.iter_mut().zip(layout.layers) {
+                merged.extend(layer);
+            }
+            for (merged, layer) in merged
+                .layers_with_dummies
+                .iter_mut()
+                .zip(layout.layers_with_dummies)
+            {
+                merged.extend(layer);
+            }
+            merged.vertices.extend(layout.vertices);
+            merged.sizes.extend(layout.sizes);
+            merged.edges.extend(layout.edges);
+            merged.truncated_edges.extend(layout.truncated_edges);
+            merged.self_loops.extend(layout.self_loops);
+            merged.simplex_iterations += layout.simplex_iterations;
+            merged.max_abs_cutvalue = merged.max_abs_cutvalue.max(layout.max_abs_cutvalue);
+            merged.layer_crossings += layout.layer_crossings;
+            #[cfg(feature = "timing")]
+            {
+                let timings = &mut merged.timings;
+                timings.cycle_removal += layout.timings.cycle_removal;
+                timings.ranking += layout.timings.ranking;
+                timings.crossing_reduction += layout.timings.crossing_reduction;
+                timings.coordinate_assignment += layout.timings.coordinate_assignment;
+            }
+            #[cfg(feature = "trace")]
+            merged.simplex_trace.extend(layout.simplex_trace);
+        }
+        merged.width = merged.layers.iter().map(Vec::len).max().unwrap_or(0) as f64;
+        merged.height = merged.layers.len() as f64;
+        merged
+    }
+
+    /// Returns the smallest and largest x coordinate covered by a vertex or
+    /// the route of an edge.
+    fn horizontal_extent(&self) -> (f64, f64) {
+        let vertices = self
+            .vertices
+            .iter()
+            .zip(&self.sizes)
+            .flat_map(|((_, (x, _)), (w, _))| [x - w / 2.0, x + w / 2.0]);
+        let points = self.edges.iter().flat_map(|e| &e.points).map(|(x, _)| *x);
+        vertices
+            .chain(points)
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+                (min.min(x), max.max(x))
+            })
+    }
+
+    /// Moves all vertices and edges of the layout by `(dx, dy)`.
+    pub(crate) fn translate(&mut self, dx: f64, dy: f64) {
+        let translate = |(x, y): &mut (f64, f64)| {
+            *x += dx;
+            *y += dy;
+        };
+        self.vertices.iter_mut().for_each(|(_, c)| translate(c));
+        self.edges
+            .iter_mut()
+            .flat_map(|e| &mut e.points)
+            .for_each(translate);
+        self.layer_xs.iter_mut().flatten().for_each(|x| *x += dx);
+        for (_, geometry) in &mut self.self_loops {
+            translate(&mut geometry.center);
+            translate(&mut geometry.start);
+            translate(&mut geometry.end);
+        }
+    }
+
+    /// Returns the vertices of each layer, ordered from left to right.
+    pub fn layers(&self) -> &[Vec<T>] {
+        &self.layers
+    }
+
+    /// Returns the smallest and largest rank used by the layout, as
+    /// `(min, max)`. Since ranks are normalized, the smallest rank is always 0.
+    pub fn rank_bounds(&self) -> (i32, i32) {
+        (0, self.layers.len() as i32 - 1)
+    }
+
+    /// Maps the vertex identifiers of the layout to a different type.
+    pub(crate) fn map_ids<U>(self, f: impl Fn(T) -> U) -> Layout<U> {
+        Layout {
+            vertices: self
+                .vertices
+                .into_iter()
+                .map(|(id, coords)| (f(id), coords))
+                .collect(),
+            sizes: self.sizes,
+            edges: self
+                .edges
+                .into_iter()
+                .map(|e| RoutedEdge {
+                    tail: f(e.tail),
+                    head: f(e.head),
+                    points: e.points,
+                    reversed: e.reversed,
+                })
+                .collect(),
+            layers: self
+                .layers
+                .into_iter()
+                .map(|l| l.into_iter().map(&f).collect())
+                .collect(),
+            layers_with_dummies: self
+                .layers_with_dummies
+                .into_iter()
+                .map(|l| l.into_iter().map(|(v, d)| (f(v), d)).collect())
+                .collect(),
+            layer_xs: self.layer_xs,
+            layer_edges: self.layer_edges,
+            edge_dummies: self.edge_dummies,
+            minimum_lengths: self.minimum_lengths,
+            vertex_spacing: self.vertex_spacing,
+            dummy_size: self.dummy_size,
+            truncated_edges: self
+                .truncated_edges
+                .into_iter()
+                .map(|(tail, head)| (f(tail), f(head)))
+                .collect(),
+            self_loops: self
+                .self_loops
+                .into_iter()
+                .map(|(v, geometry)| (f(v), geometry))
+                .collect(),
+            width: self.width,
+            height: self.height,
+            simplex_iterations: self.simplex_iterations,
+            max_abs_cutvalue: self.max_abs_cutvalue,
+            layer_crossings: self.layer_crossings,
+            #[cfg(feature = "timing")]
+            timings: self.timings,
+            #[cfg(feature = "trace")]
+            simplex_trace: self
+                .simplex_trace
+                .into_iter()
+                .map(|((a, b), (c, d))| ((f(a), f(b)), (f(c), f(d))))
+                .collect(),
+        }
+    }
+}
+
+impl<T: Copy> Layout<T> {
+    /// Returns the primitives needed to draw the layout: first all edges and
+    /// self-loops, then all vertices and finally a label for each vertex
+    /// `label` returns text for. Vertices are drawn with the size they were given as input.
+    pub fn draw_commands<'a>(
+        &'a self,
+        label: impl Fn(&T) -> Option<String> + 'a,
+    ) -> impl Iterator<Item = DrawCommand<T>> + 'a {
+        let edges = self
+            .edges
+            .iter()
+            .enumerate()
+            .map(|(id, e)| DrawCommand::Edge {
+                id,
+                polyline: e.points.clone(),
+            });
+        let self_loops = self
+            .self_loops
+            .iter()
+            .map(|(id, geometry)| DrawCommand::SelfLoop {
+                id: *id,
+                geometry: *geometry,
+            });
+        let nodes = self
+            .vertices
+            .iter()
+            .zip(&self.sizes)
+            .map(|((id, (x, y)), (width, height))| DrawCommand::Node {
+                id: *id,
+                rect: Rect {
+                    x: x - width / 2.0,
+                    y: y - height / 2.0,
+                    width: *width,
+                    height: *height,
+                },
+            });
+        let labels = self.vertices.iter().filter_map(move |(id, pos)| {
+            label(id).map(|text| DrawCommand::Label { pos: *pos, text })
+        });
+        edges.chain(self_loops).chain(nodes).chain(labels)
+    }
+}
+
+impl<T: Copy + PartialEq> Layout<T> {
+    /// Stretches the layout horizontally or vertically, so the aspect ratio
+    /// (width / height) of its [bounding box](Self::bounding_box) gets as
+    /// close to `target` as possible.
+    ///
+    /// Only the spacing between vertices is increased, either between the
+    /// vertices of a layer or between layers, so vertices never overlap. The
+    /// layout is stretched by at most [MAX_STRETCH]. Self-loops move along
+    /// with their vertex.
+    ///
+    /// Returns the aspect ratio after stretching, or [None] if the layout has
+    /// a width or height of 0, e.g. because it consists of a single layer. In
+    /// that case it is left unchanged.
+    pub fn fit_aspect_ratio(&mut self, target: f64) -> Option<f64> {
+        let ((min_x, min_y), (max_x, max_y)) = self.bounding_box();
+        let (width, height) = (max_x - min_x, max_y - min_y);
+        // also false for an empty layout, whose width and height are NaN
+        if !(width > 0.0 && height > 0.0) {
+            return None;
+        }
+        let stretch = (target * height / width).clamp(1.0 / MAX_STRETCH, MAX_STRETCH);
+        // stretch the axis that needs to get longer
+        let (stretch_x, stretch_y) = if stretch >= 1.0 {
+            (stretch, 1.0)
+        } else {
+            (1.0, 1.0 / stretch)
+        };
+        for (v, (x, y)) in &self.vertices {
+            let shift = (
+                (x - min_x) * (stretch_x - 1.0),
+                (y - min_y) * (stretch_y - 1.0),
+            );
+            shift_self_loops(&mut self.self_loops, *v, shift);
+        }
+        let scale = |(x, y): &mut (f64, f64)| {
+            *x = min_x + (*x - min_x) * stretch_x;
+            *y = min_y + (*y - min_y) * stretch_y;
+        };
+        self.vertices.iter_mut().for_each(|(_, c)| scale(c));
+        self.edges
+            .iter_mut()
+            .flat_map(|e| &mut e.points)
+            .for_each(scale);
+        self.layer_xs
+            .iter_mut()
+            .flatten()
+            .for_each(|x| *x = min_x + (*x - min_x) * stretch_x);
+        Some((width * stretch_x) / (height * stretch_y))
+    }
+
+    /// Returns the role of `vertex`, based on the edges of the input graph.
+    pub fn node_role(&self, vertex: T) -> NodeRole {
+        let has_incoming = self.edges.iter().any(|e| e.head == vertex);
+        let has_outgoing = self.edges.iter().any(|e| e.tail == vertex);
+        match (has_incoming, has_outgoing) {
+            (false, true) => NodeRole::Source,
+            (true, false) => NodeRole::Sink,
+            (true, true) => NodeRole::Internal,
+            (false, false) => NodeRole::Isolated,
+        }
+    }
+
+    /// Changes the order of the vertices in `layer` to `new_order`, for
+    /// example after a user dragged a vertex to a different position.
+    ///
+    /// The ranking and the order of all other layers stay the same. The
+    /// vertices of the layer are moved into the x coordinates of the old
+    /// order, and the routes of all edges incident to them, their self-loops
+    /// as well as [Self::count_crossings] are updated.
+    ///
+    /// Returns [LayoutError::UnknownLayer] if `layer` does not exist and
+    /// [LayoutError::InvalidOrder] if `new_order` is not a permutation of the
+    /// vertices in `layer`.
+    pub fn reorder_layer(&mut self, layer: usize, new_order: &[T]) -> Result<(), LayoutError> {
+        let Some(old_order) = self.layers.get(layer) else {
+            return Err(LayoutError::UnknownLayer);
+        };
+        let is_permutation = new_order.len() == old_order.len()
+            && new_order.iter().all(|v| old_order.contains(v))
+            && new_order
+                .iter()
+                .enumerate()
+                .all(|(i, v)| !new_order[..i].contains(v));
+        if !is_permutation {
+            return Err(LayoutError::InvalidOrder);
+        }
+
+        // the i-th vertex in the new order takes the place of the i-th
+        // vertex in the old order
+        let mut moved = Vec::new();
+        for (old, new) in old_order.iter().zip(new_order) {
+            let coords = self.vertices.iter().find(|(v, _)| v == old).unwrap().1;
+            moved.push((*new, coords));
+        }
+        for (v, coords) in &mut self.vertices {
+            if let Some((_, new_coords)) = moved.iter().find(|(w, _)| w == v) {
+                let shift = (new_coords.0 - coords.0, new_coords.1 - coords.1);
+                *coords = *new_coords;
+                shift_self_loops(&mut self.self_loops, *v, shift);
+            }
+        }
+        for edge in &mut self.edges {
+            for (v, new_coords) in &moved {
+                if edge.tail == *v {
+                    edge.points[0] = *new_coords;
+                }
+                if edge.head == *v {
+                    *edge.points.last_mut().unwrap() = *new_coords;
+                }
+            }
+        }
+        // dummy vertices keep their position, the i-th vertex in the new
+        // order takes the position of the i-th vertex in the old order
+        let slots = self.layers_with_dummies[layer]
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, is_dummy))| !is_dummy)
+            .map(|(pos, _)| pos)
+            .collect::<Vec<_>>();
+        let mut new_positions = (0..self.layers_with_dummies[layer].len()).collect::<Vec<_>>();
+        for (slot, v) in slots.iter().zip(new_order) {
+            let old = old_order.iter().position(|w| w == v).unwrap();
+            new_positions[slots[old]] = *slot;
+            self.layers_with_dummies[layer][*slot].0 = *v;
+        }
+        if let Some(edges) = layer.checked_sub(1).map(|r| &mut self.layer_edges[r]) {
+            edges
+                .iter_mut()
+                .for_each(|(_, head)| *head = new_positions[*head]);
+        }
+        if let Some(edges) = self.layer_edges.get_mut(layer) {
+            edges
+                .iter_mut()
+                .for_each(|(tail, _)| *tail = new_positions[*tail]);
+        }
+        self.layers[layer] = new_order.to_vec();
+        self.layer_crossings = self.count_layer_crossings();
+        Ok(())
+    }
+}
+
+impl<T: Copy + Eq + Hash> Layout<T> {
+    /// Moves `node` to the layer `rank`, e.g. after a user dragged it to a
+    /// different layer, keeping the ranks of all other vertices.
+    ///
+    /// Each edge incident to `node` still needs to span at least its minimum
+    /// length, see [crate::configure::Config::minimum_length], in the
+    /// direction it had after cycle removal. `node` keeps its x coordinate and
+    /// is inserted into the new layer according to it. The dummy vertices of
+    /// its edges are replaced by new ones in each layer the edges cross,
+    /// placed on a straight line between their endpoints, unless the layout
+    /// doesn't contain dummy vertices. The new dummy vertices use `node` as
+    /// their identifier. Vertices overlapping an inserted vertex are moved to
+    /// the right, together with the routes of their edges. The routes of the
+    /// edges incident to `node` are recalculated through their dummy vertices,
+    /// and [Self::count_crossings] is updated.
+    ///
+    /// Returns [LayoutError::UnknownLayer] if `rank` does not exist,
+    /// [LayoutError::UnknownVertex] if `node` is not part of the layout and
+    /// [LayoutError::InfeasibleRank] if the move would make an edge shorter
+    /// than its minimum length.
+    pub fn move_node_to_rank(&mut self, node: T, rank: usize) -> Result<(), LayoutError> {
+        if rank >= self.layers.len() {
+            return Err(LayoutError::UnknownLayer);
+        }
+        let Some(old_rank) = self.layers.iter().position(|l| l.contains(&node)) else {
+            return Err(LayoutError::UnknownVertex);
+        };
+        let is_incident = |e: &RoutedEdge<T>| e.tail == node || e.head == node;
+        let mut ranks = self.ranks();
+        ranks.insert(node, rank as i32);
+        let spans_minimum_length = self
+            .edges
+            .iter()
+            .zip(&self.minimum_lengths)
+            .filter(|(e, _)| is_incident(e))
+            .all(|(e, minimum_length)| {
+                let (upper, lower) = if e.reversed {
+                    (e.head, e.tail)
+                } else {
+                    (e.tail, e.head)
+                };
+                ranks[&lower] - ranks[&upper] >= *minimum_length as i32
+            });
+
+        let old_pos = self.layers_with_dummies[old_rank]
+            .iter()
+            .position(|(v, is_dummy)| !is_dummy && *v == node)
+            .unwrap();
+        let EdgeChains {
+            removed,
+            mut ends,
+            reached,
+        } = self.edge_chains(old_rank, old_pos);
+        // edges without dummy vertices end directly at their other endpoint
+        let positions = self
+            .layers_with_dummies
+            .iter()
+            .enumerate()
+            .flat_map(|(r, l)| l.iter().enumerate().map(move |(p, v)| (v, (r, p))))
+            .filter(|((_, is_dummy), _)| !is_dummy)
+            .map(|((v, _), position)| (*v, position))
+            .collect::<HashMap<_, _>>();
+        for edge in self.edges.iter().filter(|e| is_incident(e)) {
+            let other = if edge.tail == node {
+                edge.head
+            } else {
+                edge.tail
+            };
+            if !reached.contains(&other) && !ends.contains(&positions[&other]) {
+                ends.push(positions[&other]);
+            }
+        }
+        // the vertices the edges continue from stay in their layer
+        let keeps_direction = ends
+            .iter()
+            .all(|(r, _)| *r != rank && (*r < old_rank) == (*r < rank));
+        if !spans_minimum_length || !keeps_direction {
+            return Err(LayoutError::InfeasibleRank);
+        }
+        if rank == old_rank {
+            return Ok(());
+        }
+
+        // the vertices added to each layer are identified by their index
+        // after the previous vertices of the layer
+        let layer_ys = self.layer_ys();
+        let x = self.layer_xs[old_rank][old_pos];
+        let old_lens = self
+            .layers_with_dummies
+            .iter()
+            .map(Vec::len)
+            .collect::<Vec<_>>();
+        let mut added = vec![Vec::new(); self.layers.len()];
+        added[rank].push((node, false, x));
+        let node_key = (rank, old_lens[rank]);
+        let mut segments = Vec::new();
+        let mut chains = Vec::new();
+        for &(end_rank, end_pos) in &ends {
+            let end_x = self.layer_xs[end_rank][end_pos];
+            // the layers crossed by the edge, from `node` outward
+            let crossed = if end_rank < rank {
+                (end_rank + 1..rank).rev().collect::<Vec<_>>()
+            } else {
+                (rank + 1..end_rank).collect()
+            };
+            let mut chain = Vec::new();
+            if self.dummy_size.is_some() {
+                for (i, r) in crossed.iter().enumerate() {
+                    let t = (i + 1) as f64 / (crossed.len() + 1) as f64;
+                    chain.push((*r, old_lens[*r] + added[*r].len()));
+                    added[*r].push((node, true, x + (end_x - x) * t));
+                }
+            }
+            let path = std::iter::once(node_key)
+                .chain(chain.iter().copied())
+                .chain(std::iter::once((end_rank, end_pos)))
+                .collect::<Vec<_>>();
+            for w in path.windows(2) {
+                let (upper, lower) = if w[0].0 < w[1].0 {
+                    (w[0], w[1])
+                } else {
+                    (w[1], w[0])
+                };
+                if upper.0 + 1 == lower.0 {
+                    segments.push((upper, lower));
+                }
+            }
+            chains.push(chain);
+        }
+
+        // insert the added vertices by their x coordinate and move the
+        // vertices right of them out of the way
+        let widths = self
+            .vertices
+            .iter()
+            .zip(&self.sizes)
+            .map(|((v, _), (width, _))| (*v, width + self.vertex_spacing))
+            .collect::<HashMap<_, _>>();
+        let dummy_size = self.dummy_size.unwrap_or(0.0);
+        let width = |v: &T, is_dummy: bool| if is_dummy { dummy_size } else { widths[v] };
+        let mut new_positions = HashMap::new();
+        let mut shifts = HashMap::new();
+        for r in 0..self.layers.len() {
+            let kept = self.layers_with_dummies[r]
+                .iter()
+                .zip(&self.layer_xs[r])
+                .enumerate()
+                .filter(|(p, _)| !removed.contains(&(r, *p)))
+                .map(|(p, ((v, is_dummy), x))| (p, *v, *is_dummy, *x));
+            let inserted = added[r]
+                .iter()
+                .enumerate()
+                .map(|(i, (v, is_dummy, x))| (old_lens[r] + i, *v, *is_dummy, *x));
+            let mut entries = kept.chain(inserted).collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.3.total_cmp(&b.3));
+            if !added[r].is_empty() {
+                for i in 1..entries.len() {
+                    let (_, left, left_is_dummy, left_x) = entries[i - 1];
+                    let (key, v, is_dummy, x) = entries[i];
+                    let min_x = left_x + (width(&left, left_is_dummy) + width(&v, is_dummy)) / 2.0;
+                    if x < min_x {
+                        shifts.insert((r, key), min_x - x);
+                        entries[i].3 = min_x;
+                    }
+                }
+            }
+            for (p, (key, ..)) in entries.iter().enumerate() {
+                new_positions.insert((r, *key), p);
+            }
+            self.layers_with_dummies[r] = entries.iter().map(|(_, v, d, _)| (*v, *d)).collect();
+            self.layer_xs[r] = entries.iter().map(|(.., x)| *x).collect();
+        }
+        let position = |(r, key): (usize, usize)| (r, new_positions[&(r, key)]);
+
+        for (r, edges) in self.layer_edges.iter_mut().enumerate() {
+            edges.retain(|(tail, head)| {
+                !removed.contains(&(r, *tail)) && !removed.contains(&(r + 1, *head))
+            });
+            for (tail, head) in edges.iter_mut() {
+                (*tail, *head) = (position((r, *tail)).1, position((r + 1, *head)).1);
+            }
+        }
+        for (upper, lower) in segments {
+            self.layer_edges[upper.0].push((position(upper).1, position(lower).1));
+        }
+        for (edge, dummies) in self.edges.iter().zip(&mut self.edge_dummies) {
+            dummies.retain(|d| !removed.contains(d));
+            let end = if edge.tail == node {
+                dummies.first().copied().or(Some(positions[&edge.head]))
+            } else if edge.head == node {
+                dummies.last().copied().or(Some(positions[&edge.tail]))
+            } else {
+                None
+            };
+            dummies.iter_mut().for_each(|d| *d = position(*d));
+            let Some(end) = end else {
+                continue;
+            };
+            let chain = chains[ends.iter().position(|e| *e == end).unwrap()]
+                .iter()
+                .map(|d| position(*d));
+            if edge.tail == node {
+                dummies.splice(0..0, chain);
+            } else {
+                dummies.extend(chain.rev());
+            }
+        }
+
+        // update the coordinates of the moved vertices and the routes of
+        // their edges
+        let shifts = shifts
+            .into_iter()
+            .map(|(key, dx)| (position(key), dx))
+            .collect::<HashMap<_, _>>();
+        let new_pos = position(node_key);
+        let new_coords = (self.layer_xs[rank][new_pos.1], layer_ys[rank]);
+        let vertex_shifts = shifts
+            .iter()
+            .map(|((r, p), dx)| (self.layers_with_dummies[*r][*p], (*dx, 0.0)))
+            .filter(|((v, is_dummy), _)| !is_dummy && *v != node)
+            .map(|((v, _), shift)| (v, shift))
+            .collect::<HashMap<_, _>>();
+        for (v, (x, y)) in &mut self.vertices {
+            if *v == node {
+                let shift = (new_coords.0 - *x, new_coords.1 - *y);
+                (*x, *y) = new_coords;
+                shift_self_loops(&mut self.self_loops, *v, shift);
+            } else if let Some(shift) = vertex_shifts.get(v) {
+                *x += shift.0;
+                shift_self_loops(&mut self.self_loops, *v, *shift);
+            }
+        }
+        let coordinates = self.vertices.iter().copied().collect::<HashMap<_, _>>();
+        for (edge, dummies) in self.edges.iter_mut().zip(&self.edge_dummies) {
+            if edge.tail == node || edge.head == node {
+                let dummies = dummies
+                    .iter()
+                    .map(|(r, p)| (self.layer_xs[*r][*p], layer_ys[*r]));
+                edge.points = std::iter::once(coordinates[&edge.tail])
+                    .chain(dummies)
+                    .chain(std::iter::once(coordinates[&edge.head]))
+                    .collect();
+                continue;
+            }
+            for (i, d) in dummies.iter().enumerate() {
+                if let Some(dx) = shifts.get(d) {
+                    edge.points[i + 1].0 += dx;
+                }
+            }
+            if let Some((dx, _)) = vertex_shifts.get(&edge.tail) {
+                edge.points[0].0 += dx;
+            }
+            if let Some((dx, _)) = vertex_shifts.get(&edge.head) {
+                edge.points.last_mut().unwrap().0 += dx;
+            }
+        }
+
+        self.layers = self
+            .layers_with_dummies
+            .iter()
+            .map(|l| {
+                l.iter()
+                    .filter(|(_, is_dummy)| !is_dummy)
+                    .map(|(v, _)| *v)
+                    .collect()
+            })
+            .collect();
+        self.layer_crossings = self.count_layer_crossings();
+        Ok(())
+    }
+
+    /// Follows the edges of the vertex at position `pos` of layer `rank`
+    /// through their dummy vertices, see [EdgeChains].
+    fn edge_chains(&self, rank: usize, pos: usize) -> EdgeChains<T> {
+        // the number of incoming and outgoing edges of each vertex
+        let mut degrees = self
+            .layers_with_dummies
+            .iter()
+            .map(|l| vec![(0, 0); l.len()])
+            .collect::<Vec<_>>();
+        for (r, edges) in self.layer_edges.iter().enumerate() {
+            for (tail, head) in edges {
+                degrees[r][*tail].1 += 1;
+                degrees[r + 1][*head].0 += 1;
+            }
+        }
+        let mut chains = EdgeChains {
+            removed: HashSet::from([(rank, pos)]),
+            ends: Vec::new(),
+            reached: HashSet::new(),
+        };
+        for downward in [false, true] {
+            // the third entry is true once a shared dummy vertex was passed
+            let mut stack = vec![(rank, pos, false)];
+            while let Some((r, p, is_shared)) = stack.pop() {
+                let neighbors = if downward {
+                    self.layer_edges
+                        .get(r)
+                        .into_iter()
+                        .flatten()
+                        .filter(|(tail, _)| *tail == p)
+                        .map(|(_, head)| (r + 1, *head))
+                        .collect::<Vec<_>>()
+                } else if r > 0 {
+                    self.layer_edges[r - 1]
+                        .iter()
+                        .filter(|(_, head)| *head == p)
+                        .map(|(tail, _)| (r - 1, *tail))
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+                for (r, p) in neighbors {
+                    let (v, is_dummy) = self.layers_with_dummies[r][p];
+                    let is_private = is_dummy && degrees[r][p] == (1, 1);
+                    if !is_shared && !is_private {
+                        chains.ends.push((r, p));
+                    }
+                    if !is_dummy {
+                        chains.reached.insert(v);
+                        continue;
+                    }
+                    if !is_shared && is_private {
+                        chains.removed.insert((r, p));
+                    }
+                    stack.push((r, p, is_shared || !is_private));
+                }
+            }
+        }
+        chains
+    }
+
+    /// Returns the rank of each vertex, i.e. the index of its layer in
+    /// [Self::layers]. Ranks start at 0 for the topmost layer.
+    pub fn ranks(&self) -> HashMap<T, i32> {
+        self.layers
+            .iter()
+            .enumerate()
+            .flat_map(|(rank, layer)| layer.iter().map(move |v| (*v, rank as i32)))
+            .collect()
+    }
+
+    /// Returns metrics of the layering, like the number of layers and
+    /// crossings, in a single struct.
+    pub fn stats(&self) -> LayoutStats {
+        let ranks = self.ranks();
+        let rank_difference = |edge: &RoutedEdge<T>| {
+            Some(ranks.get(&edge.tail)?.abs_diff(*ranks.get(&edge.head)?) as usize)
+        };
+        LayoutStats {
+            ranks: self.layers.len(),
+            max_layer_width: self
+                .layers_with_dummies
+                .iter()
+                .map(Vec::len)
+                .max()
+                .unwrap_or(0),
+            total_edge_length: self.edges.iter().filter_map(rank_difference).sum(),
+            crossings: self.layer_crossings,
+            dummy_vertices: self
+                .layers_with_dummies
+                .iter()
+                .flatten()
+                .filter(|(_, is_dummy)| *is_dummy)
+                .count(),
+            simplex_iterations: self.simplex_iterations,
+        }
+    }
+
+    /// Returns the position of each vertex, where x is the coordinate assigned
+    /// by the layout and y is its [rank](Self::ranks) times `layer_spacing`,
+    /// so the topmost layer lies at y = 0 regardless of the vertex heights.
+    pub fn positions(&self, layer_spacing: f64) -> HashMap<T, (f64, f64)> {
+        let ranks = self.ranks();
+        self.vertices
+            .iter()
+            .map(|(v, (x, _))| (*v, (*x, ranks[v] as f64 * layer_spacing)))
+            .collect()
+    }
+
+    /// Returns each edge reversed during cycle removal, given by its index in
+    /// [Self::routed_edges], together with the number of ranks it spans. Long
+    /// feedback edges hint at dependencies between distant parts of the
+    /// graph.
+    pub fn feedback_edges(&self) -> Vec<(usize, i32)> {
+        let ranks = self.ranks();
+        self.edges
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.reversed)
+            .map(|(id, e)| (id, (ranks[&e.tail] - ranks[&e.head]).abs()))
+            .collect()
+    }
+
+    /// Returns the points of each edge of the input graph by its tail and
+    /// head, ordered from tail to head. Edges spanning multiple layers pass
+    /// through the coordinates of their dummy vertices, all other edges only
+    /// consist of their endpoints. Of parallel edges, only the first one in
+    /// [Self::routed_edges] is returned.
+    pub fn edge_paths(&self) -> HashMap<(T, T), Vec<(f64, f64)>> {
+        self.edge_paths_with(EdgeRouting::Polyline)
+    }
+
+    /// Returns the points of each edge like [Self::edge_paths], connected
+    /// according to `routing`.
+    pub fn edge_paths_with(&self, routing: EdgeRouting) -> HashMap<(T, T), Vec<(f64, f64)>> {
+        let mut paths = HashMap::new();
+        for edge in &self.edges {
+            paths
+                .entry((edge.tail, edge.head))
+                .or_insert_with(|| route(&edge.points, routing));
+        }
+        paths
+    }
+
+    /// Returns the number of edges of the input graph between each tail and
+    /// head. Parallel edges are laid out as a single edge and share a route,
+    /// which is offset for each of them if
+    /// [`Config::parallel_edge_spacing`](crate::configure::Config::parallel_edge_spacing)
+    /// is set.
+    pub fn edge_multiplicities(&self) -> HashMap<(T, T), usize> {
+        let mut multiplicities = HashMap::new();
+        for edge in &self.edges {
+            *multiplicities.entry((edge.tail, edge.head)).or_insert(0) += 1;
+        }
+        multiplicities
+    }
+
+    /// Returns the vertical extent of each layer as `(top, bottom)`, e.g. to
+    /// draw alternating background stripes. The bands are contiguous: the
+    /// border between two layers lies in the middle of the gap between the
+    /// tallest vertices of both layers. The first and last band extend by
+    /// half the gap to their neighbor beyond their tallest vertex. Layers
+    /// containing only dummy vertices get a band as well.
+    pub fn layer_bands(&self) -> Vec<(f64, f64)> {
+        let heights = self.vertices.iter().map(|(v, _)| *v).zip(&self.sizes);
+        let heights = heights
+            .map(|(v, (_, h))| (v, *h))
+            .collect::<HashMap<_, _>>();
+        let extents = self
+            .layer_ys()
+            .into_iter()
+            .zip(&self.layers)
+            .map(|(y, layer)| {
+                let half_height = layer.iter().map(|v| heights[v] / 2.0).fold(0.0, f64::max);
+                (y - half_height, y + half_height)
+            })
+            .collect::<Vec<_>>();
+        // the borders between consecutive layers
+        let borders = extents
+            .windows(2)
+            .map(|w| (w[0].1 + w[1].0) / 2.0)
+            .collect::<Vec<_>>();
+        let Some((first, last)) = extents.first().zip(extents.last()) else {
+            return Vec::new();
+        };
+        let top = borders.first().map_or(first.0, |b| first.0 - (b - first.1));
+        let bottom = borders.last().map_or(last.1, |b| last.1 + (last.0 - b));
+        std::iter::once(top)
+            .chain(borders.iter().copied())
+            .zip(borders.iter().copied().chain(std::iter::once(bottom)))
+            .collect()
+    }
+
+    /// Returns the y coordinate of each layer. Layers without vertices of the
+    /// input graph are interpolated between the nearest layers with vertices.
+    fn layer_ys(&self) -> Vec<f64> {
+        let coordinates = self.vertices.iter().copied().collect::<HashMap<_, _>>();
+        let known = self
+            .layers
+            .iter()
+            .map(|l| l.first().map(|v| coordinates[v].1))
+            .collect::<Vec<_>>();
+        (0..known.len())
+            .map(|r| {
+                if let Some(y) = known[r] {
+                    return y;
+                }
+                let above = (0..r).rev().find_map(|a| known[a].map(|y| (a, y)));
+                let below = (r + 1..known.len()).find_map(|b| known[b].map(|y| (b, y)));
+                match (above, below) {
+                    (Some((a, ya)), Some((b, yb))) => {
+                        ya + (yb - ya) * (r - a) as f64 / (b - a) as f64
+                    }
+                    (Some((_, y)), None) | (None, Some((_, y))) => y,
+                    (None, None) => 0.0,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the coordinates of each vertex, mapped into `[0, 1]` relative to
+    /// the [bounding box](Self::bounding_box) of the layout, so they can be
+    /// scaled to any viewport. If all coordinates are the same on an axis, e.g.
+    /// for a single vertex, they are mapped to 0.5.
+    pub fn normalized_coordinates(&self) -> HashMap<T, (f64, f64)> {
+        let ((min_x, min_y), (max_x, max_y)) = self.bounding_box();
+        let normalize = |c: f64, min: f64, max: f64| {
+            if max > min {
+                (c - min) / (max - min)
+            } else {
+                0.5
+            }
+        };
+        self.vertices
+            .iter()
+            .map(|(v, (x, y))| {
+                (
+                    *v,
+                    (normalize(*x, min_x, max_x), normalize(*y, min_y, max_y)),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the position of each vertex, ordered by layer and by position
+    /// in the layer, together with a map from vertex identifier to its index
+    /// in the list.
+    ///
+    /// This is cheaper to iterate than looking up each vertex in a map, which
+    /// makes it more suitable for large graphs.
+    pub fn node_data(&self) -> (Vec<NodeData<T>>, HashMap<T, usize>) {
+        let coordinates = self.vertices.iter().copied().collect::<HashMap<_, _>>();
+        let mut nodes = Vec::with_capacity(self.vertices.len());
+        let mut index = HashMap::with_capacity(self.vertices.len());
+        for (rank, layer) in self.layers.iter().enumerate() {
+            for (order, id) in layer.iter().enumerate() {
+                let (x, y) = coordinates[id];
+                index.insert(*id, nodes.len());
+                nodes.push(NodeData {
+                    id: *id,
+                    rank,
+                    order,
+                    x,
+                    y,
+                });
+            }
+        }
+        (nodes, index)
+    }
+
+    /// Compares the layout to a `previous` layout, e.g. for animating the
+    /// transition between them. Vertices are moved if their rank or their
+    /// position in the layer changed, changes of only the coordinates are not
+    /// taken into account.
+    pub fn diff(&self, previous: &Layout<T>) -> LayoutDiff<T> {
+        let (old_nodes, old_index) = previous.node_data();
+        let (new_nodes, new_index) = self.node_data();
+        let mut moved = Vec::new();
+        let mut added = Vec::new();
+        for new in &new_nodes {
+            match old_index.get(&new.id).map(|i| old_nodes[*i]) {
+                Some(old) if old.rank != new.rank || old.order != new.order => {
+                    moved.push((old, *new))
+                }
+                Some(_) => (),
+                None => added.push(new.id),
+            }
+        }
+        let removed = old_nodes
+            .iter()
+            .filter(|old| !new_index.contains_key(&old.id))
+            .map(|old| old.id)
+            .collect();
+        LayoutDiff {
+            moved,
+            added,
+            removed,
+        }
+    }
+
+    /// Groups the edges by the layers of their tail and head, as
+    /// `(tail_layer, head_layer)`. Edges are given as their index in
+    /// [Self::routed_edges]. Reversed edges keep their original direction, so
+    /// their tail layer is below their head layer.
+    pub fn edges_by_rank_pair(&self) -> HashMap<(usize, usize), Vec<usize>> {
+        let ranks = self
+            .layers
+            .iter()
+            .enumerate()
+            .flat_map(|(rank, layer)| layer.iter().map(move |v| (*v, rank)))
+            .collect::<HashMap<_, _>>();
+        let mut edges = HashMap::<_, Vec<_>>::new();
+        for (id, edge) in self.edges.iter().enumerate() {
+            edges
+                .entry((ranks[&edge.tail], ranks[&edge.head]))
+                .or_default()
+                .push(id);
+        }
+        edges
+    }
+
+    /// Returns true if the route of the edge with index `edge` in
+    /// [Self::routed_edges] intersects the route of any other edge. Routes
+    /// which only touch at a shared vertex don't count as crossing.
+    pub fn is_edge_crossed(&self, edge: usize) -> bool {
+        let route = segments(&self.edges[edge]).collect::<Vec<_>>();
+        self.edges
+            .iter()
+            .enumerate()
+            .filter(|(id, _)| *id != edge)
+            .flat_map(|(_, e)| segments(e))
+            .any(|other| route.iter().any(|segment| intersects(*segment, other)))
+    }
+
+    /// Returns the number of crossings of the routes in
+    /// [Self::routed_edges], counting each pair of intersecting segments once.
+    /// Routes which only touch at a shared vertex don't count as crossing.
+    ///
+    /// All pairs of segments are compared, so this is quadratic in the number
+    /// of segments and meant for diagnostics, e.g. to compare the
+    /// [crate::configure::CrossingMinimization] heuristics.
+    pub fn count_route_intersections(&self) -> usize {
+        let segments = self
+            .edges
+            .iter()
+            .enumerate()
+            .flat_map(|(id, e)| segments(e).map(move |segment| (id, segment)))
+            .collect::<Vec<_>>();
+        segments
+            .iter()
+            .enumerate()
+            .map(|(i, (id, segment))| {
+                segments[i + 1..]
+                    .iter()
+                    .filter(|(other_id, other)| other_id != id && intersects(*segment, *other))
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Returns the number of edges between each pair of layers, where entry
+    /// `[i][j]` counts the edges with their tail in layer `i` and their head
+    /// in layer `j`, as grouped by [Self::edges_by_rank_pair].
+    pub fn density_matrix(&self) -> Vec<Vec<usize>> {
+        let mut matrix = vec![vec![0; self.layers.len()]; self.layers.len()];
+        for ((tail, head), edges) in self.edges_by_rank_pair() {
+            matrix[tail][head] = edges.len();
+        }
+        matrix
+    }
+
+    /// Returns the graph as a mermaid `flowchart TD`, with each vertex labeled
+    /// by its [Display] implementation and each edge of the input graph as
+    /// `-->`, including self-loops. Mermaid calculates its own layout, so only
+    /// the vertices and edges are exported, not the coordinates.
+    pub fn to_mermaid(&self) -> String
+    where
+        T: Display,
+    {
+        let mut mermaid = String::from("flowchart TD\n");
+        let mut ids = HashMap::new();
+        for (id, (v, _)) in self.vertices.iter().enumerate() {
+            ids.insert(*v, id);
+            let label = v.to_string().replace('"', "#quot;");
+            writeln!(mermaid, "    n{id}[\"{label}\"]").unwrap();
+        }
+        for edge in &self.edges {
+            writeln!(mermaid, "    n{} --> n{}", ids[&edge.tail], ids[&edge.head]).unwrap();
+        }
+        for (v, _) in &self.self_loops {
+            writeln!(mermaid, "    n{0} --> n{0}", ids[v]).unwrap();
+        }
+        mermaid
+    }
+
+    /// Returns the layout as a Graphviz `digraph`, with each vertex labeled by
+    /// its [Display] implementation and pinned to its coordinates via
+    /// `pos="x,y!"`, e.g. for rendering it with `neato -n`. Since the y axis
+    /// of Graphviz points up, y coordinates are negated.
+    ///
+    /// If `include_dummies` is true, the points edges are routed through are
+    /// exported as vertices with `shape=point`, and each edge is split into a
+    /// chain through them. Otherwise each edge directly connects its tail and
+    /// head. Self-loops connect their vertex to itself.
+    pub fn to_dot(&self, include_dummies: bool) -> String
+    where
+        T: Display,
+    {
+        self.to_dot_with_precision(include_dummies, None)
+    }
+
+    /// Same as [Self::to_dot], but rounds all coordinates to
+    /// `coordinate_precision` decimal places, if it is [Some].
+    pub fn to_dot_with_precision(
+        &self,
+        include_dummies: bool,
+        coordinate_precision: Option<u32>,
+    ) -> String
+    where
+        T: Display,
+    {
+        let pos = |(x, y): (f64, f64)| {
+            format!(
+                "{},{}!",
+                round_to(x, coordinate_precision),
+                round_to(-y, coordinate_precision)
+            )
+        };
+        let mut dot = String::from("digraph {\n");
+        let mut ids = HashMap::new();
+        for (id, (v, (x, y))) in self.vertices.iter().enumerate() {
+            ids.insert(*v, id);
+            let label = v.to_string().replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(
+                dot,
+                "    n{id} [label=\"{label}\", pos=\"{}\"];",
+                pos((*x, *y))
+            )
+            .unwrap();
+        }
+        for (id, edge) in self.edges.iter().enumerate() {
+            let (tail, head) = (ids[&edge.tail], ids[&edge.head]);
+            let bends = &edge.points[1..edge.points.len() - 1];
+            if !include_dummies || bends.is_empty() {
+                writeln!(dot, "    n{tail} -> n{head};").unwrap();
+                continue;
+            }
+            for (i, (x, y)) in bends.iter().enumerate() {
+                writeln!(
+                    dot,
+                    "    d{id}_{i} [shape=point, pos=\"{}\"];",
+                    pos((*x, *y))
+                )
+                .unwrap();
+            }
+            let chain = std::iter::once(format!("n{tail}"))
+                .chain((0..bends.len()).map(|i| format!("d{id}_{i}")))
+                .chain(std::iter::once(format!("n{head}")))
+                .collect::<Vec<_>>();
+            writeln!(dot, "    {};", chain.join(" -> ")).unwrap();
+        }
+        for (v, _) in &self.self_loops {
+            let id = ids[v];
+            writeln!(dot, "    n{id} -> n{id};").unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Returns the layout as a standalone SVG document, where each edge is a
+    /// `<polyline>` through its routed points, each self-loop a `<path>` along
+    /// its [SelfLoopGeometry] and each vertex a `<circle>` or `<rect>`,
+    /// labeled by its [Display] implementation. Vertices are drawn layer by
+    /// layer, from left to right. The `viewBox` covers all vertices, edges and
+    /// self-loops plus [SvgOptions::margin]. As y grows downward in SVG, the
+    /// first layer is at the top.
+    pub fn to_svg(&self, options: &SvgOptions) -> String
+    where
+        T: Display,
+    {
+        self.write_svg(options, |v| Some(v.to_string()), None)
+    }
+
+    /// Returns the layout as an SVG document, where each vertex is a `<rect>`
+    /// and each edge a `<polyline>`. For interactivity, each `<rect>` carries
+    /// the attributes `data-node-id`, as returned by `node_id`, `data-rank`
+    /// and `data-order`, its position in its layer. Each `<polyline>` carries
+    /// `data-edge-id`, its index in [Self::routed_edges], as well as
+    /// `data-tail` and `data-head`, which the `<path>` of each self-loop
+    /// carries as well.
+    pub fn to_svg_interactive(&self, node_id: impl Fn(&T) -> String) -> String {
+        self.to_svg_interactive_with_precision(node_id, None)
+    }
+
+    /// Same as [Self::to_svg_interactive], but rounds all coordinates and
+    /// sizes to `coordinate_precision` decimal places, if it is [Some]. This
+    /// reduces the size of the document.
+    pub fn to_svg_interactive_with_precision(
+        &self,
+        node_id: impl Fn(&T) -> String,
+        coordinate_precision: Option<u32>,
+    ) -> String {
+        let options = SvgOptions {
+            margin: 0.0,
+            coordinate_precision,
+            ..Default::default()
+        };
+        self.write_svg(&options, |_| None, Some(&node_id))
+    }
+
+    /// Writes the SVG document of [Self::to_svg] and
+    /// [Self::to_svg_interactive]. Vertices are labeled with `label`, and the
+    /// `data-*` attributes are only written if `node_id` is [Some].
+    fn write_svg(
+        &self,
+        options: &SvgOptions,
+        label: impl Fn(&T) -> Option<String>,
+        node_id: Option<&dyn Fn(&T) -> String>,
+    ) -> String {
+        let round = |v: f64| round_to(v, options.coordinate_precision);
+        let node_rect = |(x, y): (f64, f64), (width, height): (f64, f64)| match options.node_radius
+        {
+            Some(radius) => Rect {
+                x: x - radius,
+                y: y - radius,
+                width: 2.0 * radius,
+                height: 2.0 * radius,
+            },
+            None => Rect {
+                x: x - width / 2.0,
+                y: y - height / 2.0,
+                width,
+                height,
+            },
+        };
+        let rects = self
+            .vertices
+            .iter()
+            .zip(&self.sizes)
+            .map(|((_, pos), size)| node_rect(*pos, *size))
+            .collect::<Vec<_>>();
+        let points = self.edges.iter().flat_map(|e| e.points.iter().copied());
+        let loop_points = self
+            .self_loops
+            .iter()
+            .flat_map(|(_, l)| [l.start, l.end, (l.center.0 + l.radius, l.center.1)]);
+        let corners = rects
+            .iter()
+            .flat_map(|r| [(r.x, r.y), (r.x + r.width, r.y + r.height)]);
+        let ((min_x, min_y), (max_x, max_y)) = points.chain(loop_points).chain(corners).fold(
+            (
+                (f64::INFINITY, f64::INFINITY),
+                (f64::NEG_INFINITY, f64::NEG_INFINITY),
+            ),
+            |((min_x, min_y), (max_x, max_y)), (x, y)| {
+                ((min_x.min(x), min_y.min(y)), (max_x.max(x), max_y.max(y)))
+            },
+        );
+        let ((min_x, min_y), (max_x, max_y)) = if min_x.is_finite() {
+            ((min_x, min_y), (max_x, max_y))
+        } else {
+            ((0.0, 0.0), (0.0, 0.0))
+        };
+
+        let margin = options.margin;
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n",
+            round(min_x - margin),
+            round(min_y - margin),
+            round(max_x - min_x + 2.0 * margin),
+            round(max_y - min_y + 2.0 * margin)
+        );
+        for (id, edge) in self.edges.iter().enumerate() {
+            let points = edge
+                .points
+                .iter()
+                .map(|(x, y)| format!("{},{}", round(*x), round(*y)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let data = node_id.map_or(String::new(), |node_id| {
+                format!(
+                    "data-edge-id=\"{id}\" data-tail=\"{}\" data-head=\"{}\" ",
+                    escape_xml(&node_id(&edge.tail)),
+                    escape_xml(&node_id(&edge.head))
+                )
+            });
+            writeln!(
+                svg,
+                "  <polyline {data}points=\"{points}\" fill=\"none\" stroke=\"{}\"/>",
+                escape_xml(&options.edge_stroke)
+            )
+            .unwrap();
+        }
+        for (v, geometry) in &self.self_loops {
+            let data = node_id.map_or(String::new(), |node_id| {
+                let id = escape_xml(&node_id(v));
+                format!("data-tail=\"{id}\" data-head=\"{id}\" ")
+            });
+            let ((x1, y1), (x2, y2), r) = (geometry.start, geometry.end, geometry.radius);
+            writeln!(
+                svg,
+                "  <path {data}d=\"M {},{} A {},{} 0 0 1 {},{}\" fill=\"none\" stroke=\"{}\"/>",
+                round(x1),
+                round(y1),
+                round(r),
+                round(r),
+                round(x2),
+                round(y2),
+                escape_xml(&options.edge_stroke)
+            )
+            .unwrap();
+        }
+        // draw vertices in layer order, so the document doesn't depend on the
+        // order of the vertices in the layout
+        let index = self
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(i, (v, _))| (*v, i))
+            .collect::<HashMap<_, _>>();
+        let (fill, stroke) = (
+            escape_xml(&options.node_fill),
+            escape_xml(&options.node_stroke),
+        );
+        for (rank, layer) in self.layers.iter().enumerate() {
+            for (order, v) in layer.iter().enumerate() {
+                let i = index[v];
+                let ((x, y), rect) = (self.vertices[i].1, &rects[i]);
+                let data = node_id.map_or(String::new(), |node_id| {
+                    format!(
+                        "data-node-id=\"{}\" data-rank=\"{rank}\" data-order=\"{order}\" ",
+                        escape_xml(&node_id(v))
+                    )
+                });
+                match options.node_radius {
+                    Some(radius) => writeln!(
+                        svg,
+                        "  <circle {data}cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{fill}\" stroke=\"{stroke}\"/>",
+                        round(x),
+                        round(y),
+                        round(radius)
+                    ),
+                    None => writeln!(
+                        svg,
+                        "  <rect {data}x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{fill}\" stroke=\"{stroke}\"/>",
+                        round(rect.x),
+                        round(rect.y),
+                        round(rect.width),
+                        round(rect.height)
+                    ),
+                }
+                .unwrap();
+                if let Some(text) = label(v) {
+                    writeln!(
+                        svg,
+                        "  <text x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\">{}</text>",
+                        round(x),
+                        round(y),
+                        escape_xml(&options.label_fill),
+                        escape_xml(&text)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Returns the acyclic graph the vertices were ranked with, i.e. the input
+    /// graph after cycle removal. Edges reversed during cycle removal point
+    /// from their head to their tail, see [Self::reversed_edges]. The weight
+    /// of each edge is its index in [Self::routed_edges]. Self-loops are not
+    /// part of the graph.
+    pub fn acyclic_graph(&self) -> StableDiGraph<T, usize> {
+        let mut graph = StableDiGraph::new();
+        let vertices = self
+            .vertices
+            .iter()
+            .map(|(v, _)| (*v, graph.add_node(*v)))
+            .collect::<HashMap<_, _>>();
+        for (id, edge) in self.edges.iter().enumerate() {
+            let (tail, head) = (vertices[&edge.tail], vertices[&edge.head]);
+            if edge.reversed {
+                graph.add_edge(head, tail, id);
+            } else {
+                graph.add_edge(tail, head, id);
+            }
+        }
+        graph
+    }
+
+    /// Returns the edges which were reversed during cycle removal, as
+    /// `(tail, head)` in the input graph.
+    pub fn reversed_edges(&self) -> Vec<(T, T)> {
+        self.edges
+            .iter()
+            .filter(|e| e.reversed)
+            .map(|e| (e.tail, e.head))
+            .collect()
+    }
+
+    /// Returns all vertices from which `vertex` can be reached, following the
+    /// edges of the input graph. `vertex` itself is only included if it is
+    /// part of a cycle.
+    pub fn ancestors(&self, vertex: T) -> HashSet<T> {
+        self.reachable(vertex, |e| (e.head, e.tail))
+    }
+
+    /// Returns all vertices which can be reached from `vertex`, following the
+    /// edges of the input graph. `vertex` itself is only included if it is
+    /// part of a cycle.
+    pub fn descendants(&self, vertex: T) -> HashSet<T> {
+        self.reachable(vertex, |e| (e.tail, e.head))
+    }
+
+    /// Returns the critical path of the layout, i.e. the path with the maximum
+    /// total vertex weight, where `weight` returns the weight of a vertex (for
+    /// example the duration of a task when laying out a schedule). The path is
+    /// ordered from its first to its last vertex. Edges reversed during cycle
+    /// removal are ignored, so the path follows the edges of the input graph.
+    pub fn critical_path(&self, weight: impl Fn(&T) -> f64) -> Vec<T> {
+        let graph = self.acyclic_graph();
+        // longest path ending in each vertex, together with its predecessor
+        let mut longest = HashMap::<NodeIndex, (f64, Option<NodeIndex>)>::new();
+        let mut end = None;
+        // the ranks order the vertices topologically, except for edges with a
+        // minimum length of 0, so sort the acyclic graph instead
+        for v in toposort(&graph, None).unwrap() {
+            let (length, pred) = graph
+                .edges_directed(v, Incoming)
+                .filter(|e| !self.edges[*e.weight()].reversed)
+                .map(|e| (longest[&e.source()].0, Some(e.source())))
+                .max_by(|a, b| a.0.total_cmp(&b.0))
+                .unwrap_or((0.0, None));
+            let length = length + weight(&graph[v]);
+            longest.insert(v, (length, pred));
+            if !end.is_some_and(|(l, _)| length <= l) {
+                end = Some((length, v));
+            }
+        }
+
+        let mut path = Vec::new();
+        let mut cur = end.map(|(_, v)| v);
+        while let Some(v) = cur {
+            path.push(graph[v]);
+            cur = longest[&v].1;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Returns all vertices reachable from `start`, where `direction` returns
+    /// the start and end point of an edge. The edges are grouped by their
+    /// start point once, so each edge is followed at most once.
+    fn reachable(&self, start: T, direction: impl Fn(&RoutedEdge<T>) -> (T, T)) -> HashSet<T> {
+        let mut neighbors = HashMap::<T, Vec<T>>::new();
+        for (from, to) in self.edges.iter().map(direction) {
+            neighbors.entry(from).or_default().push(to);
+        }
+        let mut visited = HashSet::new();
+        let mut stack = vec![start];
+        while let Some(v) = stack.pop() {
+            for to in neighbors.remove(&v).unwrap_or_default() {
+                if visited.insert(to) {
+                    stack.push(to);
+                }
+            }
+        }
+        visited
+    }
+}
+
+type Segment = ((f64, f64), (f64, f64));
+
+/// The vertices of the layout reached by following the edges of a vertex
+/// through dummy vertices, given as their layer and position in
+/// `layers_with_dummies`.
+struct EdgeChains<T> {
+    /// The vertex itself and the dummy vertices only its edges pass through.
+    removed: HashSet<(usize, usize)>,
+    /// The vertices the edges continue from, i.e. the first vertex of the
+    /// input graph or dummy vertex shared with other edges.
+    ends: Vec<(usize, usize)>,
+    /// The vertices of the input graph the edges lead to.
+    reached: HashSet<T>,
+}
+
+/// Rounds `v` to `precision` decimal places, if it is [Some].
+fn round_to(v: f64, precision: Option<u32>) -> f64 {
+    match precision {
+        Some(precision) => {
+            let factor = 10f64.powi(precision as i32);
+            (v * factor).round() / factor
+        }
+        None => v,
+    }
+}
+
+/// Escapes the characters of `s` which can't appear in XML text or attribute
+/// values.
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Moves the self-loops of `vertex` by `(dx, dy)`.
+fn shift_self_loops<T: PartialEq>(
+    self_loops: &mut [(T, SelfLoopGeometry)],
+    vertex: T,
+    (dx, dy): (f64, f64),
+) {
+    for (_, geometry) in self_loops.iter_mut().filter(|(v, _)| *v == vertex) {
+        for (x, y) in [&mut geometry.center, &mut geometry.start, &mut geometry.end] {
+            *x += dx;
+            *y += dy;
+        }
+    }
+}
+
+/// Connects the points of a route according to `routing`.
+fn route(points: &[(f64, f64)], routing: EdgeRouting) -> Vec<(f64, f64)> {
+    match routing {
+        EdgeRouting::Straight => points
+            .first()
+            .into_iter()
+            .chain(points.last())
+            .copied()
+            .collect(),
+        EdgeRouting::Polyline => points.to_vec(),
+        EdgeRouting::Orthogonal => {
+            let mut route = points.first().into_iter().copied().collect::<Vec<_>>();
+            for w in points.windows(2) {
+                let ((x1, y1), (x2, y2)) = (w[0], w[1]);
+                if x1 != x2 && y1 != y2 {
+                    let y = (y1 + y2) / 2.0;
+                    route.extend([(x1, y), (x2, y)]);
+                }
+                route.push(w[1]);
+            }
+            route
+        }
+    }
+}
+
+/// Returns the straight segments between consecutive points of a route.
+fn segments<T>(edge: &RoutedEdge<T>) -> impl Iterator<Item = Segment> + '_ {
+    edge.points.windows(2).map(|w| (w[0], w[1]))
+}
+
+/// Returns true if both segments intersect in a point which is not an
+/// endpoint of either of them.
+fn intersects((a, b): Segment, (c, d): Segment) -> bool {
+    // twice the signed area of the triangle (a, b, c)
+    let orientation = |a: (f64, f64), b: (f64, f64), c: (f64, f64)| {
+        (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+    };
+    orientation(a, b, c) * orientation(a, b, d) < 0.0
+        && orientation(c, d, a) * orientation(c, d, b) < 0.0
+}
+
+impl Layout<NodeIndex> {
+    /// Returns the weight of each vertex of the layout in `graph`, the graph
+    /// the layout was created from. Dummy vertices are not part of the
+    /// result, since they don't exist in `graph`.
+    ///
+    /// # Panics
+    /// Panics if a vertex of the layout is not contained in `graph`.
+    pub fn node_weights<'a, V, E>(
+        &self,
+        graph: &'a StableDiGraph<V, E>,
+    ) -> HashMap<NodeIndex, &'a V> {
+        self.vertices
+            .iter()
+            .map(|(v, _)| (*v, &graph[*v]))
+            .collect()
+    }
+}
+
+impl<T> From<Layout<T>> for (Vec<(T, (f64, f64))>, f64, f64) {
+    fn from(layout: Layout<T>) -> Self {
+        (layout.vertices, layout.width, layout.height)
+    }
+}
+
+/// The fields of a [Layout] as they are deserialized, before checking that
+/// they are consistent with each other.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct LayoutFields<T> {
+    vertices: Vec<(T, (f64, f64))>,
+    sizes: Vec<(f64, f64)>,
+    edges: Vec<RoutedEdge<T>>,
+    layers: Vec<Vec<T>>,
+    layers_with_dummies: Vec<Vec<(T, bool)>>,
+    layer_xs: Vec<Vec<f64>>,
+    layer_edges: Vec<Vec<(usize, usize)>>,
+    edge_dummies: Vec<Vec<(usize, usize)>>,
+    minimum_lengths: Vec<usize>,
+    vertex_spacing: f64,
+    dummy_size: Option<f64>,
+    truncated_edges: Vec<(T, T)>,
+    self_loops: Vec<(T, SelfLoopGeometry)>,
+    width: f64,
+    height: f64,
+    simplex_iterations: usize,
+    max_abs_cutvalue: i32,
+    layer_crossings: usize,
+    #[cfg(feature = "timing")]
+    timings: PhaseTimings,
+    #[cfg(feature = "trace")]
+    simplex_trace: Vec<SimplexExchange<T>>,
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<LayoutFields<T>> for Layout<T> {
+    type Error = String;
+
+    /// Rejects fields which don't describe the same layout, since the methods
+    /// of [Layout] rely on them matching.
+    fn try_from(fields: LayoutFields<T>) -> Result<Self, Self::Error> {
+        let layer_lens = fields
+            .layers_with_dummies
+            .iter()
+            .map(Vec::len)
+            .collect::<Vec<_>>();
+        if fields.sizes.len() != fields.vertices.len() {
+            return Err("expected one size per vertex".to_string());
+        }
+        if fields.layers.len() != layer_lens.len()
+            || fields
+                .layer_xs
+                .iter()
+                .map(Vec::len)
+                .ne(layer_lens.iter().copied())
+        {
+            return Err("expected one x coordinate per vertex of each layer".to_string());
+        }
+        if fields.layer_edges.len() != layer_lens.len().saturating_sub(1)
+            || fields
+                .layer_edges
+                .iter()
+                .zip(layer_lens.windows(2))
+                .any(|(edges, lens)| edges.iter().any(|(t, h)| *t >= lens[0] || *h >= lens[1]))
+        {
+            return Err("expected edges between the vertices of adjacent layers".to_string());
+        }
+        if fields.edge_dummies.len() != fields.edges.len()
+            || fields.minimum_lengths.len() != fields.edges.len()
+        {
+            return Err("expected dummy vertices and a minimum length per edge".to_string());
+        }
+        if fields
+            .edge_dummies
+            .iter()
+            .flatten()
+            .any(|(layer, pos)| *pos >= layer_lens.get(*layer).copied().unwrap_or(0))
+        {
+            return Err("expected dummy vertices to be contained in their layer".to_string());
+        }
+        Ok(Layout {
+            vertices: fields.vertices,
+            sizes: fields.sizes,
+            edges: fields.edges,
+            layers: fields.layers,
+            layers_with_dummies: fields.layers_with_dummies,
+            layer_xs: fields.layer_xs,
+            layer_edges: fields.layer_edges,
+            edge_dummies: fields.edge_dummies,
+            minimum_lengths: fields.minimum_lengths,
+            vertex_spacing: fields.vertex_spacing,
+            dummy_size: fields.dummy_size,
+            truncated_edges: fields.truncated_edges,
+            self_loops: fields.self_loops,
+            width: fields.width,
+            height: fields.height,
+            simplex_iterations: fields.simplex_iterations,
+            max_abs_cutvalue: fields.max_abs_cutvalue,
+            layer_crossings: fields.layer_crossings,
+            #[cfg(feature = "timing")]
+            timings: fields.timings,
+            #[cfg(feature = "trace")]
+            simplex_trace: fields.simplex_trace,
+        })
+    }
+}
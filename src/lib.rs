@@ -1,17 +1,27 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 
-use algorithm::{Edge, Vertex};
+use algorithm::{Edge, Pin, Vertex};
 
 use configure::Config;
+use error::LayoutError;
+use layout::{Layout, RouteStyle};
 use log::info;
-use petgraph::{graph::NodeIndex, stable_graph::StableDiGraph};
+use petgraph::{
+    graph::{DiGraph, EdgeIndex, NodeIndex},
+    stable_graph::StableDiGraph,
+    Direction::Incoming,
+};
 
 mod algorithm;
 pub mod configure;
+pub mod error;
+pub mod incremental;
+pub mod layout;
 mod util;
 
-type Layout = (Vec<(usize, (f64, f64))>, f64, f64);
 type Layouts<T> = Vec<(Vec<(T, (f64, f64))>, f64, f64)>;
+type VertexAttribute<'a, V, T> = &'a dyn Fn(NodeIndex, &V) -> T;
+type EdgeAttribute<'a, E, T> = &'a dyn Fn(EdgeIndex, &E) -> T;
 
 /// Creates a graph layout from edges, which are given as a `&[(u32, u32)]`.
 ///
@@ -20,9 +30,115 @@ type Layouts<T> = Vec<(Vec<(T, (f64, f64))>, f64, f64)>;
 /// list of the vertex number (as specified in the edges) and its x and y
 /// position respectively.
 pub fn from_edges(edges: &[(u32, u32)], config: &Config) -> Layouts<usize> {
+    into_layouts(layout_from_edges(edges, config))
+}
+
+/// Creates a graph layout from edges, which are given as a `&[(u32, u32)]`.
+///
+/// Returns a [Layout] for each disjoint subgraph, which in addition to the
+/// coordinates of each vertex also contains the route of each edge. The
+/// vertices are the ids used in the edges, which don't need to be contiguous.
+///
+/// ```
+/// use rust_sugiyama::{configure::Config, layout_from_edges};
+///
+/// let edges = [(1, 2), (1, 3), (2, 4), (3, 4)];
+/// let layouts = layout_from_edges(&edges, &Config::default());
+/// assert_eq!(layouts.len(), 1);
+///
+/// let ranks = layouts[0].ranks();
+/// assert_eq!(ranks[&1], 0);
+/// assert_eq!(ranks[&2], ranks[&3]);
+/// assert_eq!(ranks[&4], 2);
+/// ```
+pub fn layout_from_edges(edges: &[(u32, u32)], config: &Config) -> Vec<Layout<usize>> {
     info!(target: "initializing", "Creating new layout from edges, containing {} edges", edges.len());
-    let graph = StableDiGraph::from_edges(edges);
-    algorithm::start(graph, config)
+    let graph = graph_from_edges(edges);
+    algorithm::start(graph, config, &HashSet::new())
+}
+
+/// Creates a graph layout from edges, which are given as a `&[(u32, u32)]`,
+/// where the order of vertices in each layer respects `order_constraints`.
+///
+/// Each constraint `(a, b)` requires vertex `a` to be placed left of vertex
+/// `b`, if both end up in the same layer. Constraints are transitive, i.e.
+/// `(a, b)` and `(b, c)` imply `(a, c)`.
+///
+/// Returns [LayoutError::ConstraintCycle] if the constraints contradict each
+/// other.
+pub fn layout_from_edges_with_order(
+    edges: &[(u32, u32)],
+    order_constraints: &[(u32, u32)],
+    config: &Config,
+) -> Result<Vec<Layout<usize>>, LayoutError> {
+    info!(target: "initializing",
+        "Creating new layout from edges, containing {} edges and {} order constraints",
+        edges.len(),
+        order_constraints.len());
+    let order_constraints = util::transitive_closure(order_constraints)
+        .ok_or(LayoutError::ConstraintCycle)?
+        .into_iter()
+        .map(|(a, b)| (a as usize, b as usize))
+        .collect();
+    let graph = graph_from_edges(edges);
+    Ok(algorithm::start(graph, config, &order_constraints))
+}
+
+/// Creates a graph layout from edges, which are given as a `&[(u32, u32)]`,
+/// where `inputs` are placed in the first and `outputs` in the last layer of
+/// their layout, in the given order from left to right. All other vertices
+/// are placed as usual.
+///
+/// Returns [LayoutError::InvalidPin] if an input has incoming edges, an output
+/// has outgoing edges, or if they are not part of `edges`, and
+/// [LayoutError::DuplicatePin] if a vertex is contained in `inputs` or
+/// `outputs` more than once.
+pub fn layout_from_edges_with_io(
+    edges: &[(u32, u32)],
+    inputs: &[u32],
+    outputs: &[u32],
+    config: &Config,
+) -> Result<Vec<Layout<usize>>, LayoutError> {
+    info!(target: "initializing",
+        "Creating new layout from edges, containing {} edges, {} inputs and {} outputs",
+        edges.len(),
+        inputs.len(),
+        outputs.len());
+    let tails = edges.iter().map(|(t, _)| *t).collect::<HashSet<_>>();
+    let heads = edges.iter().map(|(_, h)| *h).collect::<HashSet<_>>();
+    if !inputs
+        .iter()
+        .all(|v| tails.contains(v) && !heads.contains(v))
+        || !outputs
+            .iter()
+            .all(|v| heads.contains(v) && !tails.contains(v))
+    {
+        return Err(LayoutError::InvalidPin);
+    }
+    let mut pinned = HashSet::new();
+    if !inputs.iter().chain(outputs).all(|v| pinned.insert(*v)) {
+        return Err(LayoutError::DuplicatePin);
+    }
+    let order_constraints = inputs
+        .windows(2)
+        .chain(outputs.windows(2))
+        .map(|w| (w[0], w[1]))
+        .collect::<Vec<_>>();
+    // the constraints form chains of distinct vertices, so they can't contain a cycle
+    let order_constraints = util::transitive_closure(&order_constraints)
+        .unwrap()
+        .into_iter()
+        .map(|(a, b)| (a as usize, b as usize))
+        .collect();
+
+    let mut graph = graph_from_edges(edges);
+    for v in inputs {
+        graph[NodeIndex::from(*v)].pin(Pin::First);
+    }
+    for v in outputs {
+        graph[NodeIndex::from(*v)].pin(Pin::Last);
+    }
+    Ok(algorithm::start(graph, config, &order_constraints))
 }
 
 /// Creates a graph layout from a preexisting [StableDiGraph<V, E>].
@@ -35,28 +151,430 @@ pub fn from_graph<V, E>(
     vertex_size: &impl Fn(NodeIndex, &V) -> (f64, f64),
     config: &Config,
 ) -> Layouts<NodeIndex> {
+    into_layouts(layout_from_graph(graph, vertex_size, config))
+}
+
+/// Creates a graph layout from a preexisting [StableDiGraph<V, E>].
+///
+/// Returns a [Layout] for each disjoint subgraph, which in addition to the
+/// coordinates of each vertex also contains the route of each edge. Each
+/// subgraph is laid out independently, use [Layout::merge] to combine them.
+/// [NodeIndex] values map directly to the input graph.
+pub fn layout_from_graph<V, E>(
+    graph: &StableDiGraph<V, E>,
+    vertex_size: &impl Fn(NodeIndex, &V) -> (f64, f64),
+    config: &Config,
+) -> Vec<Layout<NodeIndex>> {
+    layout_from_mapped_graph(
+        graph,
+        &|id, v| Vertex::new(id.index(), vertex_size(id, v)),
+        &|_, _| Edge::default(),
+        config,
+    )
+}
+
+/// Creates a graph layout from a preexisting [DiGraph<V, E>].
+///
+/// The graph is converted into a [StableDiGraph] first, which preserves the
+/// indices, so the [NodeIndex] values of each [Layout] map directly to the
+/// input graph. Neither vertex nor edge weights need to implement any traits.
+pub fn layout_from_digraph<V, E>(
+    graph: &DiGraph<V, E>,
+    vertex_size: &impl Fn(NodeIndex, &V) -> (f64, f64),
+    config: &Config,
+) -> Vec<Layout<NodeIndex>> {
+    let stable = StableDiGraph::from(graph.map(|_, _| (), |_, _| ()));
+    layout_from_graph(&stable, &|v, _| vertex_size(v, &graph[v]), config)
+}
+
+/// The attributes of the vertices and edges of a [StableDiGraph<V, E>], which
+/// are taken into account by [layout_from_graph_with].
+///
+/// Only the size of the vertices is required. All other attributes are
+/// optional and can be combined freely. Vertices and edges without them are
+/// laid out like in [layout_from_graph].
+///
+/// ```
+/// use petgraph::stable_graph::StableDiGraph;
+/// use rust_sugiyama::{configure::Config, layout_from_graph_with, LayoutInput};
+///
+/// let mut graph = StableDiGraph::<(), u32>::new();
+/// let a = graph.add_node(());
+/// let b = graph.add_node(());
+/// graph.add_edge(a, b, 5);
+///
+/// let size = |_, _: &()| (10.0, 10.0);
+/// let weight = |_, w: &u32| *w;
+/// let input = LayoutInput::new(&size).edge_weight(&weight).root(a);
+/// let layouts = layout_from_graph_with(&graph, &input, &Config::default()).unwrap();
+/// assert_eq!(layouts[0].ranks()[&b], 1);
+/// ```
+pub struct LayoutInput<'a, V, E> {
+    /// Returns the width and height of each vertex.
+    pub vertex_size: VertexAttribute<'a, V, (f64, f64)>,
+    /// Returns how each edge spanning multiple layers is routed. Defaults to
+    /// [RouteStyle::Through].
+    pub route_style: Option<EdgeAttribute<'a, E, RouteStyle>>,
+    /// Returns the weight of each edge. Ranking minimizes the sum of the edge
+    /// lengths multiplied by their weight, so heavy edges, e.g. the important
+    /// ones, are kept short and straight. Defaults to 1. A weight of 0 is
+    /// treated as 1, and weights larger than [i32::MAX] are capped.
+    pub edge_weight: Option<EdgeAttribute<'a, E, u32>>,
+    /// Returns the priority of each edge. During coordinate assignment,
+    /// vertices are aligned along edges with a higher priority first, so
+    /// chains of such edges, e.g. the spine of a graph, are kept vertical.
+    /// Unlike the weight, the priority doesn't influence the ranking. Defaults
+    /// to 0.
+    pub edge_priority: Option<EdgeAttribute<'a, E, u32>>,
+    /// Returns the minimum number of ranks each edge spans, which overrides
+    /// [Config::minimum_length] unless it is [None]. To give groups of edges
+    /// their own minimum length, look up the group of each edge in a map.
+    /// Minimum lengths are capped, so the ranks along a path through all edges
+    /// still fit into an [i32].
+    pub edge_minimum_length: Option<EdgeAttribute<'a, E, Option<u32>>>,
+    /// Returns whether an edge must not be crossed by any other edge, e.g. to
+    /// draw it as a separator. After crossing reduction, the vertices of each
+    /// layer are moved to the side of the uncrossed edges their upper
+    /// neighbors lie on.
+    pub uncrossed: Option<EdgeAttribute<'a, E, bool>>,
+    /// Returns the vertex each vertex should be vertically aligned with, e.g.
+    /// its main successor. The hint is ignored unless both vertices are
+    /// connected by an edge spanning a single layer and the alignment doesn't
+    /// conflict with other alignments.
+    pub align_with: Option<VertexAttribute<'a, V, Option<NodeIndex>>>,
+    /// The ranks some vertices are fixed to, while all other vertices are
+    /// ranked around them. Fixed ranks are relative to each other: if no
+    /// vertex of a component is fixed to rank 0, the component is moved up
+    /// until its first layer is occupied. Fixed ranks are only guaranteed to
+    /// be kept with
+    /// [RankingType::MinimizeEdgeLength](configure::RankingType::MinimizeEdgeLength).
+    pub fixed_ranks: Option<&'a HashMap<NodeIndex, i32>>,
+    /// The vertex the spanning tree of network simplex is rooted at, which is
+    /// placed on the first rank of its component, e.g. to make the ranking
+    /// start at a chosen source. Only has an effect with
+    /// [RankingType::MinimizeEdgeLength](configure::RankingType::MinimizeEdgeLength).
+    pub root: Option<NodeIndex>,
+}
+
+impl<'a, V, E> LayoutInput<'a, V, E> {
+    /// Creates an input where `vertex_size` returns the width and height of
+    /// each vertex and all other attributes are unset.
+    pub fn new(vertex_size: &'a impl Fn(NodeIndex, &V) -> (f64, f64)) -> Self {
+        Self {
+            vertex_size,
+            route_style: None,
+            edge_weight: None,
+            edge_priority: None,
+            edge_minimum_length: None,
+            uncrossed: None,
+            align_with: None,
+            fixed_ranks: None,
+            root: None,
+        }
+    }
+
+    /// Sets [`Self::route_style`] and returns the input.
+    #[must_use]
+    pub fn route_style(mut self, route_style: &'a impl Fn(EdgeIndex, &E) -> RouteStyle) -> Self {
+        self.route_style = Some(route_style);
+        self
+    }
+
+    /// Sets [`Self::edge_weight`] and returns the input.
+    #[must_use]
+    pub fn edge_weight(mut self, edge_weight: &'a impl Fn(EdgeIndex, &E) -> u32) -> Self {
+        self.edge_weight = Some(edge_weight);
+        self
+    }
+
+    /// Sets [`Self::edge_priority`] and returns the input.
+    #[must_use]
+    pub fn edge_priority(mut self, edge_priority: &'a impl Fn(EdgeIndex, &E) -> u32) -> Self {
+        self.edge_priority = Some(edge_priority);
+        self
+    }
+
+    /// Sets [`Self::edge_minimum_length`] and returns the input.
+    #[must_use]
+    pub fn edge_minimum_length(
+        mut self,
+        edge_minimum_length: &'a impl Fn(EdgeIndex, &E) -> Option<u32>,
+    ) -> Self {
+        self.edge_minimum_length = Some(edge_minimum_length);
+        self
+    }
+
+    /// Sets [`Self::uncrossed`] and returns the input.
+    #[must_use]
+    pub fn uncrossed(mut self, uncrossed: &'a impl Fn(EdgeIndex, &E) -> bool) -> Self {
+        self.uncrossed = Some(uncrossed);
+        self
+    }
+
+    /// Sets [`Self::align_with`] and returns the input.
+    #[must_use]
+    pub fn align_with(
+        mut self,
+        align_with: &'a impl Fn(NodeIndex, &V) -> Option<NodeIndex>,
+    ) -> Self {
+        self.align_with = Some(align_with);
+        self
+    }
+
+    /// Sets [`Self::fixed_ranks`] and returns the input.
+    #[must_use]
+    pub fn fixed_ranks(mut self, fixed_ranks: &'a HashMap<NodeIndex, i32>) -> Self {
+        self.fixed_ranks = Some(fixed_ranks);
+        self
+    }
+
+    /// Sets [`Self::root`] and returns the input.
+    #[must_use]
+    pub fn root(mut self, root: NodeIndex) -> Self {
+        self.root = Some(root);
+        self
+    }
+}
+
+/// Creates a graph layout from a preexisting [StableDiGraph<V, E>], taking
+/// the attributes of its vertices and edges contained in `input` into account.
+///
+/// Returns [LayoutError::UnknownVertex] if a vertex with a fixed rank or the
+/// root is not part of the graph. Returns [LayoutError::InvalidPin] if the
+/// root has incoming edges, or if a fixed rank is negative or can't be kept,
+/// e.g. because an edge points from a vertex to one with a lower fixed rank.
+/// Returns [LayoutError::CannotSatisfyNonCrossing] if an uncrossed edge is
+/// still crossed after crossing reduction. Otherwise returns a [Layout] for
+/// each disjoint subgraph. [NodeIndex] values map directly to the input graph.
+pub fn layout_from_graph_with<V, E>(
+    graph: &StableDiGraph<V, E>,
+    input: &LayoutInput<V, E>,
+    config: &Config,
+) -> Result<Vec<Layout<NodeIndex>>, LayoutError> {
+    let no_fixed_ranks = HashMap::new();
+    let fixed_ranks = input.fixed_ranks.unwrap_or(&no_fixed_ranks);
+    if fixed_ranks
+        .keys()
+        .chain(&input.root)
+        .any(|v| !graph.contains_node(*v))
+    {
+        return Err(LayoutError::UnknownVertex);
+    }
+    if fixed_ranks.values().any(|rank| *rank < 0) {
+        return Err(LayoutError::InvalidPin);
+    }
+    if let Some(root) = input.root {
+        if graph.neighbors_directed(root, Incoming).any(|v| v != root) {
+            return Err(LayoutError::InvalidPin);
+        }
+    }
+
+    let is_uncrossed = |id, e: &E| input.uncrossed.is_some_and(|uncrossed| uncrossed(id, e));
+    let layouts = layout_from_mapped_graph(
+        graph,
+        &|id, v| {
+            let mut vertex = Vertex::new(id.index(), (input.vertex_size)(id, v));
+            if let Some(other) = input.align_with.and_then(|align_with| align_with(id, v)) {
+                vertex.align_with(other.index());
+            }
+            if let Some(rank) = fixed_ranks.get(&id) {
+                vertex.fix_rank(*rank);
+            }
+            if input.root == Some(id) {
+                vertex.make_ranking_root();
+            }
+            vertex
+        },
+        &|id, e| {
+            let route_style = input
+                .route_style
+                .map_or(RouteStyle::Through, |route_style| route_style(id, e));
+            let weight = input.edge_weight.map_or(1, |edge_weight| {
+                i32::try_from(edge_weight(id, e)).unwrap_or(i32::MAX).max(1)
+            });
+            let priority = input
+                .edge_priority
+                .map_or(0, |edge_priority| edge_priority(id, e));
+            let mut edge = Edge::with_route_style(route_style)
+                .with_weight(weight)
+                .with_priority(priority)
+                .with_uncrossed(is_uncrossed(id, e));
+            if let Some(minimum_length) = input
+                .edge_minimum_length
+                .and_then(|edge_minimum_length| edge_minimum_length(id, e))
+            {
+                edge = edge
+                    .with_minimum_length(capped_minimum_length(minimum_length, graph.edge_count()));
+            }
+            edge
+        },
+        config,
+    );
+
+    for layout in &layouts {
+        // all fixed vertices of a layout need to be shifted by the same offset
+        let offsets = layout
+            .ranks()
+            .into_iter()
+            .filter_map(|(v, rank)| Some(rank - fixed_ranks.get(&v)?))
+            .collect::<HashSet<_>>();
+        if offsets.len() > 1 || offsets.iter().any(|offset| *offset > 0) {
+            return Err(LayoutError::InvalidPin);
+        }
+    }
+
+    let uncrossed = graph
+        .edge_indices()
+        .filter(|e| is_uncrossed(*e, &graph[*e]))
+        .filter_map(|e| graph.edge_endpoints(e))
+        .collect::<HashSet<_>>();
+    let is_crossed = |layout: &Layout<NodeIndex>| {
+        (0..layout.routed_edges().len()).any(|id| {
+            let edge = &layout.routed_edges()[id];
+            uncrossed.contains(&(edge.tail, edge.head)) && layout.is_edge_crossed(id)
+        })
+    };
+    if layouts.iter().any(is_crossed) {
+        return Err(LayoutError::CannotSatisfyNonCrossing);
+    }
+    Ok(layouts)
+}
+
+/// Creates a graph layout from a preexisting [StableDiGraph<V, E>], where
+/// `vertex` and `edge` create the vertices and edges of the graph used
+/// internally.
+fn layout_from_mapped_graph<V, E>(
+    graph: &StableDiGraph<V, E>,
+    vertex: &impl Fn(NodeIndex, &V) -> Vertex,
+    edge: &impl Fn(EdgeIndex, &E) -> Edge,
+    config: &Config,
+) -> Vec<Layout<NodeIndex>> {
     info!(target: "initializing", 
         "Creating new layout from existing graph, containing {} vertices and {} edges.", 
         graph.node_count(), 
         graph.edge_count());
 
+    let graph = graph.map(vertex, edge);
+
+    algorithm::start(graph, config, &HashSet::new())
+        .into_iter()
+        .map(|l| l.map_ids(|id| NodeIndex::from(id as u32)))
+        .collect()
+}
+
+/// Converts a minimum length given as [u32] into the type used for ranks,
+/// capped so that a path through all `edge_count` edges spans at most
+/// [i32::MAX] ranks.
+fn capped_minimum_length(minimum_length: u32, edge_count: usize) -> i32 {
+    let cap = i32::try_from(edge_count.saturating_add(1)).map_or(1, |n| i32::MAX / n);
+    i32::try_from(minimum_length).unwrap_or(i32::MAX).min(cap)
+}
+
+/// Calculates the position of each vertex of a [StableDiGraph<V, E>] for
+/// drawing it.
+///
+/// `node_spacing` is the minimum horizontal distance between two vertices of a
+/// layer and `layer_spacing` the vertical distance between two ranks. All
+/// components are merged into a single layout, separated by `node_spacing`.
+/// See [Layout::positions].
+pub fn positions_from_graph<V, E>(
+    graph: &StableDiGraph<V, E>,
+    vertex_size: &impl Fn(NodeIndex, &V) -> (f64, f64),
+    node_spacing: f64,
+    layer_spacing: f64,
+    config: &Config,
+) -> HashMap<NodeIndex, (f64, f64)> {
+    let config = Config {
+        vertex_spacing: node_spacing,
+        ..*config
+    };
+    let layouts = layout_from_graph(graph, vertex_size, &config);
+    Layout::merge(layouts, node_spacing).positions(layer_spacing)
+}
+
+/// Estimates the number of layers of the layout of a [StableDiGraph<V, E>],
+/// e.g. to decide whether to lay it out at all.
+///
+/// Only removes cycles and ranks the vertices by their longest path from a
+/// source, which is much cheaper than calculating the layout. Since the
+/// longest path ranking uses as few layers as possible, the estimate is a
+/// lower bound of the number of layers of the tallest component in the final
+/// layout. The final layout may use more layers, e.g. if wide layers are split
+/// via [Config::max_layer_width].
+pub fn estimate_layers<V, E>(graph: &StableDiGraph<V, E>, config: &Config) -> usize {
     let graph = graph.map(
-        |id, v| Vertex::new(id.index(), vertex_size(id, v)),
+        |id, _| Vertex::new(id.index(), (0.0, 0.0)),
         |_, _| Edge::default(),
     );
+    algorithm::estimate_layers(graph, config)
+}
 
-    algorithm::start(graph, config)
-        .into_iter()
-        .map(|(l, w, h)| {
-            (
-                l.into_iter()
-                    .map(|(id, coords)| (NodeIndex::from(id as u32), coords))
-                    .collect(),
-                w,
-                h,
-            )
-        })
-        .collect()
+/// Creates a layout of the neighborhood of `focus` in a [StableDiGraph<V, E>],
+/// containing all vertices at most `radius` edges away from `focus`,
+/// regardless of the direction of the edges.
+///
+/// The coordinates are relative to `focus`, which is placed at `(0, 0)`.
+/// Edges leading from or to vertices outside of the neighborhood are returned
+/// by [Layout::truncated_edges]. [NodeIndex] values map directly to the input
+/// graph.
+///
+/// Returns [LayoutError::UnknownVertex] if `focus` is not part of the graph.
+pub fn layout_ego<V, E>(
+    graph: &StableDiGraph<V, E>,
+    vertex_size: &impl Fn(NodeIndex, &V) -> (f64, f64),
+    focus: NodeIndex,
+    radius: usize,
+    config: &Config,
+) -> Result<Layout<NodeIndex>, LayoutError> {
+    info!(target: "initializing",
+        "Creating new layout of the neighborhood of vertex {} with radius {radius}",
+        focus.index());
+    if !graph.contains_node(focus) {
+        return Err(LayoutError::UnknownVertex);
+    }
+
+    // breadth first search, ignoring the direction of edges
+    let mut distances = HashMap::from([(focus, 0)]);
+    let mut queue = VecDeque::from([focus]);
+    while let Some(v) = queue.pop_front() {
+        let distance = distances[&v];
+        if distance == radius {
+            continue;
+        }
+        for n in graph.neighbors_undirected(v) {
+            distances.entry(n).or_insert_with(|| {
+                queue.push_back(n);
+                distance + 1
+            });
+        }
+    }
+
+    // indices are preserved by filter_map
+    let neighborhood = graph.filter_map(
+        |id, v| {
+            distances
+                .contains_key(&id)
+                .then(|| Vertex::new(id.index(), vertex_size(id, v)))
+        },
+        |_, _| Some(Edge::default()),
+    );
+    let mut layout = algorithm::start(neighborhood, config, &HashSet::new())
+        .remove(0)
+        .map_ids(|id| NodeIndex::from(id as u32));
+
+    layout.truncated_edges = graph
+        .edge_indices()
+        .filter_map(|e| graph.edge_endpoints(e))
+        .filter(|(tail, head)| distances.contains_key(tail) != distances.contains_key(head))
+        .collect();
+    let (x, y) = layout
+        .coordinates()
+        .iter()
+        .find(|(v, _)| *v == focus)
+        .unwrap()
+        .1;
+    layout.translate(-x, -y);
+    Ok(layout)
 }
 
 /// Creates a graph layout from `&[(u32, (f64, f64))]` (vertices as vertex id
@@ -74,34 +592,1447 @@ pub fn from_vertices_and_edges<'a>(
     edges: &'a [(u32, u32)],
     config: &Config,
 ) -> Layouts<usize> {
+    into_layouts(layout_from_vertices_and_edges(vertices, edges, config))
+}
+
+/// Creates a graph layout from `&[(u32, (f64, f64))]` (vertices as vertex id
+/// and vertex size) and `&[(u32, u32)]` (edges).
+///
+/// Returns a [Layout] for each disjoint subgraph, which in addition to the
+/// coordinates of each vertex also contains the route of each edge.
+///
+/// # Panics
+///
+/// Panics if `edges` contain vertices which are not contained in `vertices`
+pub fn layout_from_vertices_and_edges<'a>(
+    vertices: &'a [(u32, (f64, f64))],
+    edges: &'a [(u32, u32)],
+    config: &Config,
+) -> Vec<Layout<usize>> {
     info!(target: "initializing", 
         "Creating new layout from existing graph, containing {} vertices and {} edges.", 
         vertices.len(), 
         edges.len());
 
-    let mut graph = StableDiGraph::new();
-    let mut id_map = HashMap::new();
-    for &(v, size) in vertices {
-        let id = graph.add_node(Vertex::new(v as usize, size));
-        id_map.insert(v, id);
+    let mut graph = StableDiGraph::new();
+    let mut id_map = HashMap::new();
+    for &(v, size) in vertices {
+        let id = graph.add_node(Vertex::new(v as usize, size));
+        id_map.insert(v, id);
+    }
+
+    for (tail, head) in edges {
+        graph.add_edge(
+            *id_map.get(tail).unwrap(),
+            *id_map.get(head).unwrap(),
+            Edge::default(),
+        );
+    }
+
+    algorithm::start(graph, config, &HashSet::new())
+}
+
+/// Creates a graph layout from a list of vertex payloads and a list of edges,
+/// where each edge references vertices by their position in `nodes`.
+///
+/// The layouts are returned as a list of disjoint subgraphs containing the
+/// subgraph layout, the width, and the height. The layout of a subgraph is a
+/// list of the vertex payload and its x and y position respectively.
+///
+/// Returns [LayoutError::UnknownVertex] if an edge references a position
+/// outside of `nodes`.
+pub fn from_nodes_and_edges<T: Clone>(
+    nodes: Vec<T>,
+    edges: &[(usize, usize)],
+    config: &Config,
+) -> Result<Layouts<T>, LayoutError> {
+    layout_from_nodes_and_edges(nodes, edges, config).map(into_layouts)
+}
+
+/// Creates a graph layout from a list of vertex payloads and a list of edges,
+/// where each edge references vertices by their position in `nodes`.
+///
+/// Returns a [Layout] for each disjoint subgraph, which in addition to the
+/// coordinates of each vertex also contains the route of each edge. Vertices
+/// are identified by their payload.
+///
+/// Returns [LayoutError::UnknownVertex] if an edge references a position
+/// outside of `nodes`.
+pub fn layout_from_nodes_and_edges<T: Clone>(
+    nodes: Vec<T>,
+    edges: &[(usize, usize)],
+    config: &Config,
+) -> Result<Vec<Layout<T>>, LayoutError> {
+    info!(target: "initializing",
+        "Creating new layout from {} nodes and {} edges",
+        nodes.len(),
+        edges.len());
+    if edges
+        .iter()
+        .any(|(tail, head)| *tail >= nodes.len() || *head >= nodes.len())
+    {
+        return Err(LayoutError::UnknownVertex);
+    }
+
+    let mut graph = StableDiGraph::with_capacity(nodes.len(), edges.len());
+    for _ in &nodes {
+        graph.add_node(Vertex::default());
+    }
+    for (tail, head) in edges {
+        graph.add_edge(
+            NodeIndex::new(*tail),
+            NodeIndex::new(*head),
+            Edge::default(),
+        );
+    }
+
+    Ok(algorithm::start(graph, config, &HashSet::new())
+        .into_iter()
+        .map(|l| l.map_ids(|id| nodes[id].clone()))
+        .collect())
+}
+
+/// Creates the graph of a list of edges. Ids which are not used by any edge
+/// are removed again, so they don't end up as isolated vertices.
+fn graph_from_edges(edges: &[(u32, u32)]) -> StableDiGraph<Vertex, Edge> {
+    let mut graph = StableDiGraph::from_edges(edges);
+    graph.retain_nodes(|g, v| g.neighbors_undirected(v).next().is_some());
+    graph
+}
+
+fn into_layouts<T>(layouts: Vec<Layout<T>>) -> Layouts<T> {
+    layouts.into_iter().map(Into::into).collect()
+}
+
+/// Returns the number of weakly connected components of the graph, which is
+/// the number of [Layout]s returned when laying it out.
+pub fn component_count<V, E>(graph: &StableDiGraph<V, E>) -> usize {
+    util::components(graph).len()
+}
+
+/// Returns the vertices of each weakly connected component of the graph,
+/// sorted by their [NodeIndex].
+pub fn components<V, E>(graph: &StableDiGraph<V, E>) -> Vec<Vec<NodeIndex>> {
+    util::components(graph)
+}
+
+#[test]
+fn non_contiguous_ids_are_not_vertices() {
+    let layouts = layout_from_edges(&[(3, 10), (10, 7)], &Config::default());
+    assert_eq!(layouts.len(), 1);
+    let mut ids = layouts[0]
+        .coordinates()
+        .iter()
+        .map(|(v, _)| *v)
+        .collect::<Vec<_>>();
+    ids.sort();
+    assert_eq!(ids, [3, 7, 10]);
+}
+
+#[test]
+fn heavy_edge_is_kept_tight() {
+    // 4 can be placed in rank 1 or 2, depending on which of its edges is heavier
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (0, 4), (4, 3)]);
+    let rank_of_4 = |heavy| {
+        let size = |_, _: &()| (10.0, 10.0);
+        let weight = |e, _: &()| if e == heavy { 5 } else { 1 };
+        let input = LayoutInput::new(&size).edge_weight(&weight);
+        let layouts = layout_from_graph_with(&graph, &input, &Config::default()).unwrap();
+        layouts[0].ranks()[&4.into()]
+    };
+    assert_eq!(rank_of_4(EdgeIndex::new(3)), 1);
+    assert_eq!(rank_of_4(EdgeIndex::new(4)), 2);
+}
+
+#[test]
+fn edge_weights_of_zero_and_beyond_i32() {
+    let graph = StableDiGraph::<(), u32>::from_edges([
+        (0, 2, 0),
+        (1, 2, 0),
+        (0, 3, 0),
+        (0, 1, u32::MAX),
+        (1, 3, u32::MAX),
+    ]);
+    let size = |_, _: &()| (10.0, 10.0);
+    let weight = |_, weight: &u32| *weight;
+    let input = LayoutInput::new(&size).edge_weight(&weight);
+    let layouts = layout_from_graph_with(&graph, &input, &Config::default()).unwrap();
+    let ranks = layouts[0].ranks();
+    assert_eq!(ranks[&1.into()], 1);
+    assert_eq!(ranks[&3.into()], 2);
+}
+
+#[test]
+fn simplex_stops_after_max_iterations() {
+    // the initial feasible tree of the first graph is optimal, the one of
+    // the second graph is not
+    let graphs = [
+        [
+            (1, 7),
+            (3, 5),
+            (4, 5),
+            (0, 3),
+            (2, 6),
+            (0, 5),
+            (0, 6),
+            (0, 7),
+            (2, 3),
+        ],
+        [
+            (2, 5),
+            (0, 3),
+            (0, 4),
+            (1, 5),
+            (4, 5),
+            (3, 4),
+            (0, 2),
+            (1, 4),
+            (1, 2),
+        ],
+    ];
+    for (i, edges) in graphs.iter().enumerate() {
+        let layouts = layout_from_edges(edges, &Config::default());
+        assert_eq!(layouts[0].simplex_iterations() > 0, i == 1);
+        assert_eq!(layouts[0].max_abs_cutvalue(), 0);
+
+        let config = Config {
+            max_simplex_iterations: Some(0),
+            ..Default::default()
+        };
+        let layouts = layout_from_edges(edges, &config);
+        assert_eq!(layouts[0].simplex_iterations(), 0);
+        assert_eq!(layouts[0].max_abs_cutvalue() > 0, i == 1);
+        // the ranking is still valid
+        let ranks = layouts[0].ranks();
+        assert!(edges
+            .iter()
+            .all(|(t, h)| ranks[&(*t as usize)] < ranks[&(*h as usize)]));
+    }
+}
+
+#[test]
+fn route_intersections_of_complete_bipartite_graph() {
+    // K2,2 can't be drawn without a crossing, a chain has none
+    let layouts = layout_from_edges(&[(0, 2), (0, 3), (1, 2), (1, 3)], &Config::default());
+    assert_eq!(layouts[0].count_route_intersections(), 1);
+    let layouts = layout_from_edges(&[(0, 1), (1, 2)], &Config::default());
+    assert_eq!(layouts[0].count_route_intersections(), 0);
+
+    for c_minimization in [
+        configure::CrossingMinimization::Barycenter,
+        configure::CrossingMinimization::Median,
+    ] {
+        let config = Config {
+            c_minimization,
+            ..Default::default()
+        };
+        let layouts = layout_from_edges(&[(0, 3), (1, 4), (2, 5), (0, 5)], &config);
+        assert_eq!(layouts[0].count_route_intersections(), 0);
+    }
+}
+
+#[test]
+fn barycenter_and_median_diverge() {
+    let crossings = |edges: &[(u32, u32)], c_minimization| {
+        let config = Config {
+            c_minimization,
+            ..Default::default()
+        };
+        let first = layout_from_edges(edges, &config)[0].count_route_intersections();
+        // both heuristics are deterministic
+        assert_eq!(
+            layout_from_edges(edges, &config)[0].count_route_intersections(),
+            first
+        );
+        first
+    };
+    let barycenter = configure::CrossingMinimization::Barycenter;
+    let median = configure::CrossingMinimization::Median;
+
+    let edges = [
+        (0, 5),
+        (1, 5),
+        (1, 6),
+        (2, 7),
+        (2, 8),
+        (4, 5),
+        (4, 7),
+        (4, 8),
+    ];
+    assert_eq!(crossings(&edges, barycenter), 1);
+    assert_eq!(crossings(&edges, median), 2);
+
+    let edges = [(0, 7), (0, 8), (1, 5), (1, 7), (2, 8), (3, 7)];
+    assert_eq!(crossings(&edges, barycenter), 1);
+    assert_eq!(crossings(&edges, median), 0);
+}
+
+#[test]
+fn to_dot_pins_vertices_and_optionally_dummies() {
+    let edges = [(0, 1), (1, 2), (2, 3), (0, 3)];
+    let layouts = layout_from_edges(&edges, &Config::default());
+    let (x, y) = layouts[0]
+        .coordinates()
+        .iter()
+        .find(|(v, _)| *v == 3)
+        .unwrap()
+        .1;
+
+    let dot = layouts[0].to_dot(false);
+    assert!(dot.starts_with("digraph {"));
+    assert!(dot.contains(&format!("[label=\"3\", pos=\"{x},{}!\"]", -y)));
+    assert_eq!(dot.lines().filter(|l| l.contains("->")).count(), 4);
+    assert!(!dot.contains("shape=point"));
+
+    // the long edge passes through two dummy vertices
+    let dot = layouts[0].to_dot(true);
+    assert_eq!(dot.lines().filter(|l| l.contains("shape=point")).count(), 2);
+    assert_eq!(dot.lines().filter(|l| l.contains("->")).count(), 4);
+}
+
+#[test]
+fn to_dot_escapes_labels_and_keeps_self_loops() {
+    let layouts =
+        layout_from_nodes_and_edges(vec!["a\\", "b\"c"], &[(0, 1), (1, 1)], &Config::default())
+            .unwrap();
+    let dot = layouts[0].to_dot(false);
+    assert!(dot.contains(r#"[label="a\\", "#));
+    assert!(dot.contains(r#"[label="b\"c", "#));
+    let self_loop = |l: &str| {
+        l.trim()
+            .trim_end_matches(';')
+            .split_once(" -> ")
+            .is_some_and(|(tail, head)| tail == head)
+    };
+    assert_eq!(dot.lines().filter(|l| self_loop(l)).count(), 1);
+}
+
+#[test]
+fn to_svg_of_triangle() {
+    let layouts = layout_from_edges(&[(0, 1), (1, 2), (0, 2)], &Config::default());
+    let options = layout::SvgOptions {
+        node_radius: Some(5.0),
+        ..Default::default()
+    };
+    // the first layer is at the top
+    let expected = r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="-12.25 -15 35 50">
+  <polyline points="2.75,0 0,10 2.75,20" fill="none" stroke="black"/>
+  <polyline points="2.75,0 7.75,10" fill="none" stroke="black"/>
+  <polyline points="7.75,10 2.75,20" fill="none" stroke="black"/>
+  <circle cx="2.75" cy="0" r="5" fill="white" stroke="black"/>
+  <text x="2.75" y="0" text-anchor="middle" dominant-baseline="central" fill="black">0</text>
+  <circle cx="7.75" cy="10" r="5" fill="white" stroke="black"/>
+  <text x="7.75" y="10" text-anchor="middle" dominant-baseline="central" fill="black">1</text>
+  <circle cx="2.75" cy="20" r="5" fill="white" stroke="black"/>
+  <text x="2.75" y="20" text-anchor="middle" dominant-baseline="central" fill="black">2</text>
+</svg>
+"#;
+    assert_eq!(layouts[0].to_svg(&options), expected);
+}
+
+#[test]
+fn to_svg_draws_self_loops() {
+    let layouts = layout_from_edges(&[(0, 1), (1, 1)], &Config::default());
+    let geometry = layouts[0].self_loops()[0].1;
+    let (start, end) = (geometry.start, geometry.end);
+    let path = format!(
+        "d=\"M {},{} A {r},{r} 0 0 1 {},{}\"",
+        start.0,
+        start.1,
+        end.0,
+        end.1,
+        r = geometry.radius
+    );
+
+    let svg = layouts[0].to_svg(&layout::SvgOptions::default());
+    assert_eq!(svg.matches("<path ").count(), 1);
+    assert!(svg.contains(&path));
+
+    let svg = layouts[0].to_svg_interactive(|v| v.to_string());
+    assert!(svg.contains(&format!("<path data-tail=\"1\" data-head=\"1\" {path}")));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn layout_round_trips_through_json() {
+    let layouts = layout_from_edges(&[(0, 1), (1, 2), (0, 2), (2, 0)], &Config::default());
+    let json = serde_json::to_string(&layouts[0]).unwrap();
+    let layout: Layout<usize> = serde_json::from_str(&json).unwrap();
+    assert_eq!(layout, layouts[0]);
+
+    // indices of petgraph are serialized as plain integers
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    let layouts = layout_from_graph(&graph, &|_, _| (1.0, 1.0), &Config::default());
+    let json = serde_json::to_string(&layouts[0]).unwrap();
+    let layout: Layout<NodeIndex> = serde_json::from_str(&json).unwrap();
+    assert_eq!(layout.coordinates(), layouts[0].coordinates());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn inconsistent_layout_is_not_deserialized() {
+    let layouts = layout_from_edges(&[(0, 1), (1, 2), (0, 2)], &Config::default());
+    let mut json = serde_json::to_value(&layouts[0]).unwrap();
+    json["sizes"].as_array_mut().unwrap().pop();
+    assert!(serde_json::from_value::<Layout<usize>>(json).is_err());
+
+    let mut json = serde_json::to_value(&layouts[0]).unwrap();
+    json["layer_edges"][0]
+        .as_array_mut()
+        .unwrap()
+        .push(serde_json::json!([0, 5]));
+    assert!(serde_json::from_value::<Layout<usize>>(json).is_err());
+
+    let mut json = serde_json::to_value(&layouts[0]).unwrap();
+    json["minimum_lengths"].as_array_mut().unwrap().clear();
+    assert!(serde_json::from_value::<Layout<usize>>(json).is_err());
+}
+
+#[test]
+fn node_weights_of_graph_layout() {
+    let mut graph = StableDiGraph::new();
+    let a = graph.add_node("a");
+    let b = graph.add_node("b");
+    let c = graph.add_node("c");
+    graph.add_edge(a, b, ());
+    graph.add_edge(b, c, ());
+    graph.add_edge(a, c, ());
+    let layouts = layout_from_graph(&graph, &|_, _| (10.0, 10.0), &Config::default());
+    let weights = layouts[0].node_weights(&graph);
+    // the dummy vertex of the long edge is not included
+    assert_eq!(weights.len(), 3);
+    assert_eq!(*weights[&a], "a");
+    assert_eq!(*weights[&c], "c");
+}
+
+#[test]
+fn count_crossings_of_complete_bipartite_layers() {
+    // every order of two complete bipartite layers with 2 vertices has 1 crossing
+    let edges = [
+        (0, 2),
+        (0, 3),
+        (1, 2),
+        (1, 3),
+        (2, 4),
+        (2, 5),
+        (3, 4),
+        (3, 5),
+    ];
+    let layouts = layout_from_edges(&edges, &Config::default());
+    assert_eq!(layouts[0].count_crossings(), 2);
+
+    // with 3 vertices, each of the 3 pairs of tails crosses at each of the 3
+    // pairs of heads
+    let edges = (0..3)
+        .flat_map(|t| (3..6).map(move |h| (t, h)))
+        .collect::<Vec<_>>();
+    let layouts = layout_from_edges(&edges, &Config::default());
+    assert_eq!(layouts[0].count_crossings(), 9);
+}
+
+#[test]
+fn count_crossings_of_edges_through_dummies() {
+    // each edge passes a dummy vertex, so the crossing of the complete
+    // bipartite graph is between the dummy vertices and the heads
+    let mut graph = StableDiGraph::<(), ()>::new();
+    let v = (0..4).map(|_| graph.add_node(())).collect::<Vec<_>>();
+    graph.extend_with_edges([(v[0], v[2]), (v[0], v[3]), (v[1], v[2]), (v[1], v[3])]);
+    let fixed_ranks = HashMap::from([(v[0], 0), (v[1], 0), (v[2], 2), (v[3], 2)]);
+    let size = |_, _: &()| (10.0, 10.0);
+    let input = LayoutInput::new(&size).fixed_ranks(&fixed_ranks);
+    let layouts = layout_from_graph_with(&graph, &input, &Config::default()).unwrap();
+    assert_eq!(layouts[0].count_crossings(), 1);
+    assert_eq!(layouts[0].count_route_intersections(), 1);
+
+    // after swapping the heads, the edges of the dummy vertices of the
+    // first tail cross the ones of the second tail except for one pair
+    let mut layout = layouts.into_iter().next().unwrap();
+    let heads = layout.layers()[2].clone();
+    layout.reorder_layer(2, &[heads[1], heads[0]]).unwrap();
+    assert_eq!(layout.count_crossings(), 3);
+    layout.mirror_horizontal();
+    assert_eq!(layout.count_crossings(), 3);
+    layout.reorder_layer(2, &[heads[1], heads[0]]).unwrap();
+    assert_eq!(layout.count_crossings(), 1);
+}
+
+#[test]
+fn stats_of_triangle_with_long_edge() {
+    let layouts = layout_from_edges(&[(0, 1), (1, 2), (0, 2)], &Config::default());
+    let stats = layouts[0].stats();
+    assert_eq!(
+        stats,
+        layout::LayoutStats {
+            ranks: 3,
+            max_layer_width: 2,
+            total_edge_length: 4,
+            crossings: 0,
+            dummy_vertices: 1,
+            simplex_iterations: layouts[0].simplex_iterations(),
+        }
+    );
+}
+
+#[test]
+fn layers_with_dummies_are_ordered_without_crossings() {
+    // the long edge 0 -> 4 passes the middle layer next to 2 and 3
+    let edges = [(0, 2), (1, 3), (2, 5), (3, 5), (0, 4), (5, 4)];
+    let layouts = layout_from_edges(&edges, &Config::default());
+    let layers = layouts[0].iter_layers_with_dummies().collect::<Vec<_>>();
+    assert_eq!(layers.len(), 4);
+    let position = |layer: usize, v: usize| {
+        layers[layer]
+            .iter()
+            .position(|(id, is_dummy)| *id == v && !is_dummy)
+            .unwrap()
+    };
+    // the order of the sources is kept in the second layer
+    assert_eq!(
+        position(0, 0) < position(0, 1),
+        position(1, 2) < position(1, 3)
+    );
+    // one dummy vertex in each layer the long edge passes
+    let dummies = |layer: &[(usize, bool)]| layer.iter().filter(|(_, d)| *d).count();
+    assert_eq!(
+        layers.iter().map(|l| dummies(l)).collect::<Vec<_>>(),
+        [0, 1, 1, 0]
+    );
+    assert_eq!(layouts[0].count_crossings(), 0);
+    for (layer, with_dummies) in layouts[0].layers().iter().zip(&layers) {
+        let without_dummies = with_dummies
+            .iter()
+            .filter(|(_, d)| !d)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        assert_eq!(layer, &without_dummies);
+    }
+}
+
+#[test]
+fn run_algo_empty_graph() {
+    let edges = [];
+    let g = from_edges(&edges, &Config::default());
+    assert!(g.is_empty());
+}
+
+#[test]
+fn routed_edges_of_reversed_edge_are_monotonic() {
+    let edges = [(0, 1), (1, 2), (2, 3), (3, 0)];
+    let layouts = layout_from_edges(&edges, &Config::default());
+    assert_eq!(layouts.len(), 1);
+    let routes = layouts[0].routed_edges();
+    assert_eq!(routes.len(), edges.len());
+
+    // each edge keeps the direction given in the input
+    for route in routes {
+        assert!(edges.contains(&(route.tail as u32, route.head as u32)));
+    }
+
+    let reversed = routes.iter().filter(|r| r.reversed).collect::<Vec<_>>();
+    assert_eq!(reversed.len(), 1);
+    // the back edge spans multiple layers and moves through them in order
+    let points = &reversed[0].points;
+    assert!(points.len() > 2);
+    assert!(points.windows(2).all(|p| p[0].1 > p[1].1));
+
+    for route in routes.iter().filter(|r| !r.reversed) {
+        assert!(route.points.windows(2).all(|p| p[0].1 < p[1].1));
+    }
+}
+
+#[test]
+fn exceeding_max_dummies_routes_long_edges_directly() {
+    // a path of 10,000 edges, with a shortcut spanning all of its ranks
+    let mut edges = (0..10_000).map(|v| (v, v + 1)).collect::<Vec<_>>();
+    edges.push((0, 10_000));
+    let config = Config {
+        max_dummies: Some(100),
+        ..Default::default()
+    };
+    let layouts = layout_from_edges(&edges, &config);
+    assert_eq!(layouts.len(), 1);
+    assert_eq!(layouts[0].coordinates().len(), 10_001);
+    let shortcut = layouts[0]
+        .routed_edges()
+        .iter()
+        .find(|e| e.tail == 0 && e.head == 10_000)
+        .unwrap();
+    assert_eq!(shortcut.points.len(), 2);
+}
+
+#[test]
+fn ranking_root_is_placed_on_first_rank() {
+    // vertex 3 is a source, but is placed next to its successor by default
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (3, 2)]);
+    let size = |_, _: &()| (10.0, 10.0);
+    let layouts = layout_from_graph(&graph, &size, &Config::default());
+    assert_eq!(layouts[0].ranks()[&3.into()], 1);
+
+    let rooted_at = |root: u32| {
+        let input = LayoutInput::new(&size).root(root.into());
+        layout_from_graph_with(&graph, &input, &Config::default())
+    };
+    let layouts = rooted_at(3).unwrap();
+    let ranks = layouts[0].ranks();
+    assert_eq!(ranks[&3.into()], 0);
+    assert_eq!(ranks[&0.into()], 0);
+    assert_eq!(ranks[&2.into()], 2);
+
+    assert_eq!(rooted_at(2), Err(LayoutError::InvalidPin));
+    assert_eq!(rooted_at(4), Err(LayoutError::UnknownVertex));
+}
+
+#[test]
+fn virtual_super_source_places_roots_on_first_layer() {
+    let edges = [(0, 2), (1, 2), (2, 3), (3, 4), (5, 4)];
+    let roots = [0, 1, 5];
+    let config = Config {
+        virtual_super_source: true,
+        ..Default::default()
+    };
+    let layouts = layout_from_edges(&edges, &config);
+    assert_eq!(layouts.len(), 1);
+    let coordinates = layouts[0].coordinates();
+    // the super source is not part of the layout
+    assert_eq!(coordinates.len(), 6);
+    let min_y = coordinates
+        .iter()
+        .map(|(_, (_, y))| *y)
+        .min_by(f64::total_cmp)
+        .unwrap();
+    for (v, (_, y)) in coordinates {
+        assert_eq!(*y == min_y, roots.contains(v));
+    }
+}
+
+#[test]
+fn virtual_super_source_outweighs_heavy_edges() {
+    // the heavy edge pulls 6 down to 5, unless the super source keeps it up
+    let graph =
+        StableDiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (6, 5)]);
+    let config = Config {
+        virtual_super_source: true,
+        ..Default::default()
+    };
+    let size = |_, _: &()| (10.0, 10.0);
+    let weight = |e: EdgeIndex, _: &()| if e.index() == 5 { 1000 } else { 1 };
+    let input = LayoutInput::new(&size).edge_weight(&weight);
+    let layouts = layout_from_graph_with(&graph, &input, &config).unwrap();
+    let ranks = layouts[0].ranks();
+    assert_eq!(ranks[&NodeIndex::new(0)], 0);
+    assert_eq!(ranks[&NodeIndex::new(6)], 0);
+}
+
+#[test]
+fn reorder_layer_updates_crossings() {
+    let edges = [(0, 2), (0, 3), (1, 3)];
+    let mut layouts = layout_from_edges(&edges, &Config::default());
+    let layout = &mut layouts[0];
+    layout.reorder_layer(0, &[0, 1]).unwrap();
+    layout.reorder_layer(1, &[2, 3]).unwrap();
+    assert_eq!(layout.count_crossings(), 0);
+    layout.reorder_layer(1, &[3, 2]).unwrap();
+    assert_eq!(layout.count_crossings(), 1);
+    layout.reorder_layer(0, &[1, 0]).unwrap();
+    assert_eq!(layout.count_crossings(), 0);
+}
+
+#[test]
+fn reorder_layer_moves_self_loops() {
+    let edges = [(0, 1), (0, 2), (2, 2)];
+    let mut layouts = layout_from_edges(&edges, &Config::default());
+    let layout = &mut layouts[0];
+    let offset = |layout: &Layout<usize>| {
+        let (x, y) = layout
+            .coordinates()
+            .iter()
+            .find(|(v, _)| *v == 2)
+            .unwrap()
+            .1;
+        let center = layout.self_loops()[0].1.center;
+        (center.0 - x, center.1 - y)
+    };
+    let before = offset(layout);
+    let (x, _) = layout
+        .coordinates()
+        .iter()
+        .find(|(v, _)| *v == 2)
+        .unwrap()
+        .1;
+
+    let reversed = layout.layers()[1].iter().rev().copied().collect::<Vec<_>>();
+    layout.reorder_layer(1, &reversed).unwrap();
+    assert_ne!(
+        layout
+            .coordinates()
+            .iter()
+            .find(|(v, _)| *v == 2)
+            .unwrap()
+            .1
+             .0,
+        x
+    );
+    assert_eq!(offset(layout), before);
+}
+
+#[test]
+fn reorder_middle_layer_updates_routes() {
+    let edges = [(0, 1), (0, 2), (1, 3), (2, 3)];
+    let mut layouts = layout_from_edges(&edges, &Config::default());
+    let layout = &mut layouts[0];
+    let coordinates_of = |layout: &Layout<usize>, v: usize| {
+        layout
+            .coordinates()
+            .iter()
+            .find(|(w, _)| *w == v)
+            .unwrap()
+            .1
+    };
+    let middle = layout.layers()[1].clone();
+    assert_eq!(middle.len(), 2);
+    let (left, right) = (middle[0], middle[1]);
+    let (left_coords, right_coords) = (coordinates_of(layout, left), coordinates_of(layout, right));
+
+    assert_eq!(
+        layout.reorder_layer(1, &[left]),
+        Err(LayoutError::InvalidOrder)
+    );
+    assert_eq!(
+        layout.reorder_layer(1, &[left, left]),
+        Err(LayoutError::InvalidOrder)
+    );
+    assert_eq!(
+        layout.reorder_layer(1, &[left, 0]),
+        Err(LayoutError::InvalidOrder)
+    );
+    assert_eq!(layout.reorder_layer(3, &[]), Err(LayoutError::UnknownLayer));
+
+    layout.reorder_layer(1, &[right, left]).unwrap();
+    assert_eq!(layout.layers()[1], [right, left]);
+    assert_eq!(coordinates_of(layout, right), left_coords);
+    assert_eq!(coordinates_of(layout, left), right_coords);
+    for edge in layout.routed_edges() {
+        assert_eq!(edge.points[0], coordinates_of(layout, edge.tail));
+        assert_eq!(
+            *edge.points.last().unwrap(),
+            coordinates_of(layout, edge.head)
+        );
+    }
+}
+
+#[test]
+fn ancestors_and_descendants_diamond() {
+    let edges = [(0, 1), (0, 2), (1, 3), (2, 3)];
+    let layouts = layout_from_edges(&edges, &Config::default());
+    let layout = &layouts[0];
+    assert_eq!(layout.descendants(0), [1, 2, 3].into());
+    assert_eq!(layout.descendants(1), [3].into());
+    assert!(layout.descendants(3).is_empty());
+    assert_eq!(layout.ancestors(3), [0, 1, 2].into());
+    assert!(layout.ancestors(0).is_empty());
+}
+
+#[test]
+fn critical_path_weighted_diamond() {
+    let edges = [(0, 1), (0, 2), (1, 3), (2, 3)];
+    let weights = [1.0, 2.0, 5.0, 1.0];
+    let layouts = layout_from_edges(&edges, &Config::default());
+    assert_eq!(layouts[0].critical_path(|v| weights[*v]), vec![0, 2, 3]);
+}
+
+#[test]
+fn critical_path_ignores_reversed_edges() {
+    let layouts = layout_from_edges(&[(0, 1), (1, 2), (2, 0)], &Config::default());
+    let path = layouts[0].critical_path(|_| 1.0);
+    assert_eq!(path.len(), 3);
+    let reversed = layouts[0].reversed_edges();
+    assert!(path.windows(2).all(|p| !reversed.contains(&(p[0], p[1]))));
+}
+
+#[test]
+fn critical_path_follows_edges_within_a_layer() {
+    let mut graph = StableDiGraph::<f64, u32>::new();
+    let a = graph.add_node(1.0);
+    let b = graph.add_node(1.0);
+    let c = graph.add_node(1.0);
+    graph.add_edge(a, b, 1);
+    graph.add_edge(b, c, 0);
+    let size = |_, _: &f64| (10.0, 10.0);
+    let minimum_length = |_, length: &u32| Some(*length);
+    let input = LayoutInput::new(&size).edge_minimum_length(&minimum_length);
+    let layouts = layout_from_graph_with(&graph, &input, &Config::default()).unwrap();
+    let ranks = layouts[0].ranks();
+    assert_eq!(ranks[&b], ranks[&c]);
+    assert_eq!(layouts[0].critical_path(|v| graph[*v]), vec![a, b, c]);
+}
+
+#[test]
+fn normalized_coordinates_map_extremes_to_bounds() {
+    let edges = [(0, 1), (0, 2), (0, 3), (1, 4), (2, 4), (3, 4)];
+    let layouts = layout_from_edges(&edges, &Config::default());
+    let normalized = layouts[0].normalized_coordinates();
+    assert_eq!(normalized.len(), 5);
+    for axis in [|c: (f64, f64)| c.0, |c: (f64, f64)| c.1] {
+        let values = normalized.values().map(|c| axis(*c)).collect::<Vec<_>>();
+        assert!(values.iter().all(|v| (0.0..=1.0).contains(v)));
+        assert!(values.contains(&0.0));
+        assert!(values.contains(&1.0));
+    }
+}
+
+#[test]
+fn normalized_coordinates_single_vertex() {
+    let layouts = layout_from_vertices_and_edges(&[(0, (10.0, 10.0))], &[], &Config::default());
+    assert_eq!(layouts[0].normalized_coordinates()[&0], (0.5, 0.5));
+}
+
+#[test]
+fn order_constraints_are_respected() {
+    // without constraints, the middle layer is ordered as [4, 3, 2, 1]
+    let edges = (1..=4).flat_map(|v| [(0, v), (v, 5)]).collect::<Vec<_>>();
+    let constraints = [(1, 2), (2, 3), (3, 4)];
+    for transpose in [false, true] {
+        let config = Config {
+            transpose,
+            ..Default::default()
+        };
+        let layouts = layout_from_edges_with_order(&edges, &constraints, &config).unwrap();
+        assert_eq!(layouts[0].layers()[1], [1, 2, 3, 4]);
+    }
+}
+
+#[test]
+fn order_constraints_with_cycle() {
+    let edges = [(0, 1), (0, 2)];
+    let constraints = [(1, 2), (2, 1)];
+    let layouts = layout_from_edges_with_order(&edges, &constraints, &Config::default());
+    assert_eq!(layouts, Err(LayoutError::ConstraintCycle));
+}
+
+#[test]
+fn node_data_matches_coordinates() {
+    let edges = [(0, 1), (0, 2), (0, 3), (1, 4), (2, 4), (3, 4), (0, 4)];
+    let layouts = layout_from_edges(&edges, &Config::default());
+    let layout = &layouts[0];
+    let (nodes, index) = layout.node_data();
+    assert_eq!(nodes.len(), layout.coordinates().len());
+    assert_eq!(index.len(), nodes.len());
+    for (id, (x, y)) in layout.coordinates() {
+        let node = nodes[index[id]];
+        assert_eq!(node.id, *id);
+        assert_eq!((node.x, node.y), (*x, *y));
+        assert_eq!(layout.layers()[node.rank][node.order], *id);
+    }
+}
+
+#[test]
+fn draw_commands_contain_all_vertices_and_edges() {
+    use layout::DrawCommand;
+
+    let vertices = [(0, (10.0, 20.0)), (1, (10.0, 20.0)), (2, (30.0, 20.0))];
+    let edges = [(0, 1), (0, 2), (1, 2), (1, 1)];
+    let layouts = layout_from_vertices_and_edges(&vertices, &edges, &Config::default());
+    let commands = layouts[0]
+        .draw_commands(|id| (*id == 0).then(|| "root".to_string()))
+        .collect::<Vec<_>>();
+
+    let nodes = commands
+        .iter()
+        .filter_map(|c| match c {
+            DrawCommand::Node { id, rect } => Some((*id, *rect)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(nodes.len(), vertices.len());
+    for (id, rect) in nodes {
+        assert_eq!((rect.width, rect.height), vertices[id].1);
+    }
+    let edge_count = commands
+        .iter()
+        .filter(|c| matches!(c, DrawCommand::Edge { .. }))
+        .count();
+    assert_eq!(edge_count, edges.len() - 1);
+    let self_loops = commands
+        .iter()
+        .filter_map(|c| match c {
+            DrawCommand::SelfLoop { id, geometry } => Some((*id, *geometry)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(self_loops, layouts[0].self_loops());
+    let labels = commands
+        .iter()
+        .filter(|c| matches!(c, DrawCommand::Label { .. }))
+        .count();
+    assert_eq!(labels, 1);
+}
+
+#[test]
+fn fit_wide_aspect_ratio() {
+    let edges = [(0, 1), (0, 2), (1, 3), (2, 3), (3, 4)];
+    let mut layouts = layout_from_edges(&edges, &Config::default());
+    let layout = &mut layouts[0];
+    let ((min_x, min_y), (max_x, max_y)) = layout.bounding_box();
+    assert!(max_x - min_x < max_y - min_y);
+
+    let ratio = layout.fit_aspect_ratio(2.0).unwrap();
+    let ((min_x, min_y), (max_x, max_y)) = layout.bounding_box();
+    assert!(max_x - min_x > max_y - min_y);
+    assert!((ratio - 2.0).abs() < 1e-9);
+    assert!((ratio - (max_x - min_x) / (max_y - min_y)).abs() < 1e-9);
+}
+
+#[test]
+fn fit_aspect_ratio_moves_self_loops_and_skips_flat_layouts() {
+    let edges = [(0, 1), (0, 2), (2, 2)];
+    let mut layouts = layout_from_edges(&edges, &Config::default());
+    let layout = &mut layouts[0];
+    let offset = |layout: &Layout<usize>| {
+        let (_, geometry) = layout.self_loops()[0];
+        let (_, (x, y)) = layout.coordinates().iter().find(|(v, _)| *v == 2).unwrap();
+        (geometry.center.0 - x, geometry.center.1 - y)
+    };
+    let before = offset(layout);
+    // stretching vertically moves vertex 2 down, since it is in the second layer
+    assert!(layout.fit_aspect_ratio(0.1).is_some());
+    assert_eq!(offset(layout), before);
+
+    // a single column has no width to stretch
+    let mut layouts = layout_from_edges(&[(0, 1)], &Config::default());
+    let coordinates = layouts[0].coordinates().to_vec();
+    assert_eq!(layouts[0].fit_aspect_ratio(2.0), None);
+    assert_eq!(layouts[0].coordinates(), coordinates);
+}
+
+#[test]
+fn edges_grouped_by_rank_pair() {
+    let edges = [(0, 1), (1, 2), (0, 2), (0, 3)];
+    let layouts = layout_from_edges(&edges, &Config::default());
+    let layout = &layouts[0];
+    let edge_id = |tail, head| {
+        layout
+            .routed_edges()
+            .iter()
+            .position(|e| e.tail == tail && e.head == head)
+            .unwrap()
+    };
+    let mut by_rank_pair = layout.edges_by_rank_pair();
+    by_rank_pair.values_mut().for_each(|edges| edges.sort());
+    let mut from_first_layer = vec![edge_id(0, 1), edge_id(0, 3)];
+    from_first_layer.sort();
+
+    assert_eq!(by_rank_pair.len(), 3);
+    assert_eq!(by_rank_pair[&(0, 1)], from_first_layer);
+    assert_eq!(by_rank_pair[&(1, 2)], [edge_id(1, 2)]);
+    assert_eq!(by_rank_pair[&(0, 2)], [edge_id(0, 2)]);
+}
+
+#[test]
+fn parallel_edges_have_distinct_routes() {
+    let edges = [(0, 1), (0, 1), (0, 1)];
+    let config = Config {
+        parallel_edge_spacing: 5.0,
+        ..Default::default()
+    };
+    let layouts = layout_from_edges(&edges, &config);
+    let routes = layouts[0].routed_edges();
+    assert_eq!(routes.len(), 3);
+    for (i, a) in routes.iter().enumerate() {
+        for b in &routes[i + 1..] {
+            assert_ne!(a.points, b.points);
+        }
+    }
+}
+
+#[test]
+fn parallel_edges_are_layered_like_a_single_edge() {
+    let config = Config {
+        parallel_edge_spacing: 5.0,
+        ..Default::default()
+    };
+    // 4 can be placed in rank 1 or 2, the copies of (4, 3) don't pull it down
+    let single = layout_from_edges(&[(0, 1), (1, 2), (2, 3), (0, 4), (4, 3)], &config);
+    let parallel = layout_from_edges(
+        &[(0, 1), (1, 2), (2, 3), (0, 4), (4, 3), (4, 3), (4, 3)],
+        &config,
+    );
+    assert_eq!(single[0].ranks(), parallel[0].ranks());
+    assert_eq!(parallel[0].ranks()[&4], 1);
+
+    let single = layout_from_edges(&[(0, 1), (1, 2), (0, 2)], &config);
+    let parallel = layout_from_edges(&[(0, 1), (0, 1), (0, 1), (1, 2), (0, 2), (0, 2)], &config);
+    assert_eq!(single[0].layers(), parallel[0].layers());
+    let dummies = |layout: &Layout<usize>| {
+        layout
+            .iter_layers_with_dummies()
+            .map(|l| l.len())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(dummies(&single[0]), dummies(&parallel[0]));
+
+    let multiplicities = parallel[0].edge_multiplicities();
+    assert_eq!(multiplicities[&(0, 1)], 3);
+    assert_eq!(multiplicities[&(1, 2)], 1);
+    assert_eq!(multiplicities[&(0, 2)], 2);
+    let long = parallel[0]
+        .routed_edges()
+        .iter()
+        .filter(|e| (e.tail, e.head) == (0, 2))
+        .collect::<Vec<_>>();
+    assert_eq!(long[0].points.len(), 3);
+    assert_ne!(long[0].points, long[1].points);
+}
+
+#[test]
+fn node_roles_of_chain() {
+    use layout::NodeRole;
+
+    let layouts = layout_from_edges(&[(0, 1), (1, 2)], &Config::default());
+    assert_eq!(layouts[0].node_role(0), NodeRole::Source);
+    assert_eq!(layouts[0].node_role(1), NodeRole::Internal);
+    assert_eq!(layouts[0].node_role(2), NodeRole::Sink);
+
+    let layouts = layout_from_vertices_and_edges(&[(0, (1.0, 1.0))], &[], &Config::default());
+    assert_eq!(layouts[0].node_role(0), NodeRole::Isolated);
+}
+
+#[test]
+fn side_routed_edge_passes_layer_margins() {
+    let mut graph = StableDiGraph::<(), RouteStyle>::new();
+    let v = (0..6).map(|_| graph.add_node(())).collect::<Vec<_>>();
+    graph.add_edge(v[0], v[1], RouteStyle::Through);
+    graph.add_edge(v[1], v[2], RouteStyle::Through);
+    graph.add_edge(v[2], v[3], RouteStyle::Through);
+    graph.add_edge(v[0], v[4], RouteStyle::Through);
+    graph.add_edge(v[4], v[5], RouteStyle::Through);
+    graph.add_edge(v[5], v[3], RouteStyle::Through);
+    graph.add_edge(v[0], v[3], RouteStyle::Side);
+    let size = |_, _: &()| (10.0, 10.0);
+    let route_style = |_, style: &RouteStyle| *style;
+    let input = LayoutInput::new(&size).route_style(&route_style);
+    let layouts = layout_from_graph_with(&graph, &input, &Config::default()).unwrap();
+    let layout = &layouts[0];
+    // no dummies are inserted for the edge routed along the side
+    assert!(layout
+        .iter_layers_with_dummies()
+        .all(|l| l.iter().all(|(_, is_dummy)| !is_dummy)));
+
+    let route = layout
+        .routed_edges()
+        .iter()
+        .find(|e| (e.tail, e.head) == (v[0], v[3]))
+        .unwrap();
+    assert_eq!(route.points.len(), 4);
+    let coordinates = layout
+        .coordinates()
+        .iter()
+        .copied()
+        .collect::<HashMap<_, _>>();
+    for (layer, (x, y)) in layout.layers()[1..3].iter().zip(&route.points[1..3]) {
+        assert!(layer.iter().all(|v| coordinates[v].1 == *y));
+        // the waypoint is outside of all vertices, including spacing
+        let left = layer
+            .iter()
+            .map(|v| coordinates[v].0 - 10.0)
+            .reduce(f64::min)
+            .unwrap();
+        let right = layer
+            .iter()
+            .map(|v| coordinates[v].0 + 10.0)
+            .reduce(f64::max)
+            .unwrap();
+        assert!(*x == left || *x == right);
+    }
+}
+
+#[test]
+fn mirror_horizontal_twice_restores_layout() {
+    let edges = [(0, 1), (0, 2), (0, 3), (1, 4), (2, 4), (0, 4), (3, 3)];
+    let layout = layout_from_edges(&edges, &Config::default()).remove(0);
+    let mut mirrored = layout.clone();
+    mirrored.mirror_horizontal();
+    assert_eq!(mirrored.bounding_box(), layout.bounding_box());
+    for (layer, mirrored_layer) in layout.layers().iter().zip(mirrored.layers()) {
+        let mut reversed = layer.clone();
+        reversed.reverse();
+        assert_eq!(&reversed, mirrored_layer);
+    }
+    mirrored.mirror_horizontal();
+    assert_eq!(mirrored, layout);
+}
+
+#[test]
+fn self_loop_is_placed_next_to_vertex() {
+    let layouts = layout_from_edges(&[(0, 1), (1, 1), (1, 2)], &Config::default());
+    assert_eq!(layouts.len(), 1);
+    assert_eq!(layouts[0].routed_edges().len(), 2);
+    let self_loops = layouts[0].self_loops();
+    assert_eq!(self_loops.len(), 1);
+    let (vertex, geometry) = self_loops[0];
+    assert_eq!(vertex, 1);
+    let (_, (x, y)) = layouts[0]
+        .coordinates()
+        .iter()
+        .find(|(v, _)| *v == 1)
+        .unwrap();
+    assert_eq!(geometry.center, (*x, *y));
+    assert_eq!(geometry.radius, 5.0);
+    assert_eq!(geometry.start, (*x, y - 5.0));
+    assert_eq!(geometry.end, (*x, y + 5.0));
+}
+
+#[test]
+fn single_vertex_with_self_loop() {
+    let layouts = layout_from_edges(&[(0, 0)], &Config::default());
+    assert_eq!(layouts.len(), 1);
+    assert_eq!(layouts[0].layers(), [vec![0]]);
+    assert!(layouts[0].routed_edges().is_empty());
+    assert_eq!(layouts[0].self_loops().len(), 1);
+    assert_eq!(layouts[0].self_loops()[0].0, 0);
+}
+
+#[test]
+fn single_vertex_without_edges() {
+    use configure::RankingType;
+
+    let mut graph = StableDiGraph::<(), ()>::new();
+    let v = graph.add_node(());
+    for ranking_type in [
+        RankingType::Original,
+        RankingType::MinimizeEdgeLength,
+        RankingType::Up,
+        RankingType::Down,
+        RankingType::CoffmanGraham,
+    ] {
+        for optimize_area in [false, true] {
+            let config = Config {
+                ranking_type,
+                optimize_area,
+                ..Default::default()
+            };
+            let layouts = layout_from_graph(&graph, &|_, _| (10.0, 10.0), &config);
+            assert_eq!(layouts.len(), 1);
+            assert_eq!(layouts[0].layers(), [vec![v]]);
+            assert_eq!(layouts[0].ranks()[&v], 0);
+            assert_eq!(layouts[0].coordinates(), [(v, (0.0, 0.0))]);
+            assert!(layouts[0].routed_edges().is_empty());
+            assert_eq!(layouts[0].simplex_iterations(), 0);
+        }
+    }
+}
+
+#[test]
+fn from_nodes_and_edges_contains_payloads() {
+    let nodes = vec!["input", "filter", "output"];
+    let layouts =
+        layout_from_nodes_and_edges(nodes, &[(0, 1), (1, 2)], &Config::default()).unwrap();
+    assert_eq!(layouts.len(), 1);
+    assert_eq!(
+        layouts[0].layers(),
+        [vec!["input"], vec!["filter"], vec!["output"]]
+    );
+    let route = &layouts[0].routed_edges()[0];
+    assert_eq!((route.tail, route.head), ("input", "filter"));
+
+    assert_eq!(
+        from_nodes_and_edges(vec!["input"], &[(0, 1)], &Config::default()).unwrap_err(),
+        LayoutError::UnknownVertex
+    );
+}
+
+#[test]
+fn layout_ego_contains_direct_neighbors() {
+    let mut graph = StableDiGraph::<(), ()>::new();
+    let v = (0..6).map(|_| graph.add_node(())).collect::<Vec<_>>();
+    graph.add_edge(v[0], v[1], ());
+    graph.add_edge(v[1], v[2], ());
+    graph.add_edge(v[3], v[1], ());
+    graph.add_edge(v[2], v[4], ());
+    graph.add_edge(v[5], v[3], ());
+
+    let layout = layout_ego(&graph, &|_, _| (0.0, 0.0), v[1], 1, &Config::default()).unwrap();
+    let mut vertices = layout
+        .coordinates()
+        .iter()
+        .map(|(v, _)| *v)
+        .collect::<Vec<_>>();
+    vertices.sort();
+    assert_eq!(vertices, [v[0], v[1], v[2], v[3]]);
+    assert!(layout.coordinates().contains(&(v[1], (0.0, 0.0))));
+    let mut truncated = layout.truncated_edges().to_vec();
+    truncated.sort();
+    assert_eq!(truncated, [(v[2], v[4]), (v[5], v[3])]);
+
+    graph.remove_node(v[5]);
+    assert_eq!(
+        layout_ego(&graph, &|_, _| (0.0, 0.0), v[5], 1, &Config::default()),
+        Err(LayoutError::UnknownVertex)
+    );
+}
+
+#[test]
+fn diff_contains_vertex_moved_by_new_edge() {
+    let previous = layout_from_edges(&[(0, 1), (0, 2), (0, 3)], &Config::default()).remove(0);
+    let layout = layout_from_edges(&[(0, 1), (0, 2), (0, 3), (3, 1)], &Config::default()).remove(0);
+    let diff = layout.diff(&previous);
+    assert!(diff.added.is_empty());
+    assert!(diff.removed.is_empty());
+    assert_eq!(diff.moved.len(), 1);
+    let (old, new) = diff.moved[0];
+    assert_eq!(old.id, 1);
+    assert_eq!((old.rank, new.rank), (1, 2));
+
+    let diff = previous.diff(&layout_from_edges(&[(0, 1), (0, 2), (0, 4)], &Config::default())[0]);
+    assert_eq!(diff.added, [3]);
+    assert_eq!(diff.removed, [4]);
+}
+
+#[test]
+fn inputs_and_outputs_are_pinned_in_order() {
+    let edges = [
+        (0, 1),
+        (0, 3),
+        (1, 3),
+        (3, 4),
+        (4, 6),
+        (4, 7),
+        (2, 7),
+        (0, 8),
+    ];
+    let layouts =
+        layout_from_edges_with_io(&edges, &[2, 0], &[8, 7, 6], &Config::default()).unwrap();
+    assert_eq!(layouts.len(), 1);
+    let layers = layouts[0].layers();
+    assert_eq!(layers[0], [2, 0]);
+    assert_eq!(layers[layers.len() - 1], [8, 7, 6]);
+
+    assert_eq!(
+        layout_from_edges_with_io(&edges, &[1], &[], &Config::default()).unwrap_err(),
+        LayoutError::InvalidPin
+    );
+    assert_eq!(
+        layout_from_edges_with_io(&edges, &[], &[6, 9], &Config::default()).unwrap_err(),
+        LayoutError::InvalidPin
+    );
+    assert_eq!(
+        layout_from_edges_with_io(&edges, &[2, 0, 2], &[], &Config::default()).unwrap_err(),
+        LayoutError::DuplicatePin
+    );
+}
+
+#[test]
+fn reversed_minimum_length_applies_to_reversed_edges() {
+    let edges = [(0, 1), (1, 2), (2, 0)];
+    let spans = |config: &Config| {
+        let layout = layout_from_edges(&edges, config).remove(0);
+        let rank = |v| layout.layers().iter().position(|l| l.contains(&v)).unwrap() as i32;
+        let (reversed, forward): (Vec<_>, Vec<_>) =
+            layout.routed_edges().iter().partition(|e| e.reversed);
+        let span = |e: &&layout::RoutedEdge<usize>| (rank(e.head) - rank(e.tail)).abs();
+        (
+            reversed.iter().map(span).collect::<Vec<_>>(),
+            forward.iter().map(span).collect::<Vec<_>>(),
+        )
+    };
+
+    let (reversed, forward) = spans(&Config::default());
+    assert_eq!(reversed, [2]);
+    assert_eq!(forward, [1, 1]);
+
+    let (reversed, mut forward) = spans(&Config {
+        reversed_minimum_length: Some(3),
+        ..Default::default()
+    });
+    assert_eq!(reversed, [3]);
+    forward.sort();
+    assert_eq!(forward, [1, 2]);
+}
+
+#[test]
+fn to_mermaid_contains_each_edge() {
+    let edges = [(0, 1), (0, 2), (1, 3), (2, 3), (3, 0), (3, 3)];
+    let layouts = layout_from_edges(&edges, &Config::default());
+    let mermaid = layouts[0].to_mermaid();
+    assert!(mermaid.starts_with("flowchart"));
+    assert_eq!(mermaid.lines().filter(|l| l.contains("-->")).count(), 6);
+    assert!(mermaid.contains("[\"3\"]"));
+    let self_loop = |l: &str| l.trim().split_once(" --> ").is_some_and(|(t, h)| t == h);
+    assert_eq!(mermaid.lines().filter(|l| self_loop(l)).count(), 1);
+}
+
+#[test]
+fn rank_bounds_of_diamond() {
+    let edges = [(0, 1), (0, 2), (1, 3), (2, 3)];
+    let layouts = layout_from_edges(&edges, &Config::default());
+    assert_eq!(layouts[0].rank_bounds(), (0, 2));
+}
+
+#[test]
+fn layers_with_dummies_contain_dummies_of_long_edge() {
+    let edges = [(0, 1), (1, 2), (2, 3), (0, 3)];
+    let layouts = layout_from_edges(&edges, &Config::default());
+    let layers = layouts[0].iter_layers_with_dummies().collect::<Vec<_>>();
+    assert_eq!(layers.len(), 4);
+    assert_eq!(layers[0], [(0, false)]);
+    assert_eq!(layers[3], [(3, false)]);
+    for (rank, layer) in layers.iter().enumerate().take(3).skip(1) {
+        assert_eq!(layer.len(), 2);
+        assert_eq!(layer.iter().filter(|(_, is_dummy)| *is_dummy).count(), 1);
+        assert!(layer.contains(&(rank, false)));
     }
+    // the dummies are at the positions the long edge is routed through
+    let route = layouts[0]
+        .routed_edges()
+        .iter()
+        .find(|e| e.tail == 0 && e.head == 3)
+        .unwrap();
+    assert_eq!(route.points.len(), 4);
+}
 
-    for (tail, head) in edges {
-        graph.add_edge(
-            *id_map.get(tail).unwrap(),
-            *id_map.get(head).unwrap(),
-            Edge::default(),
+#[test]
+fn from_graph_payload_without_default() {
+    // neither vertex nor edge weights need to implement any traits
+    struct Payload(#[allow(dead_code)] &'static str);
+
+    let mut g = StableDiGraph::new();
+    let a = g.add_node(Payload("a"));
+    let b = g.add_node(Payload("b"));
+    let c = g.add_node(Payload("c"));
+    g.add_edge(a, b, Payload("ab"));
+    g.add_edge(a, c, Payload("ac"));
+
+    let layouts = from_graph(&g, &|_, _| (1.0, 1.0), &Config::default());
+    assert_eq!(layouts.len(), 1);
+    assert_eq!(layouts[0].0.len(), 3);
+}
+
+#[test]
+fn layout_from_digraph_keeps_indices() {
+    struct Payload(&'static str);
+
+    let mut g = DiGraph::new();
+    let a = g.add_node(Payload("a"));
+    let b = g.add_node(Payload("bb"));
+    let c = g.add_node(Payload("c"));
+    g.add_edge(a, b, Payload("ab"));
+    g.add_edge(b, c, Payload("bc"));
+
+    let layouts = layout_from_digraph(
+        &g,
+        &|_, v| (10.0 * v.0.len() as f64, 10.0),
+        &Config::default(),
+    );
+    assert_eq!(layouts.len(), 1);
+    let ranks = layouts[0].ranks();
+    assert_eq!(ranks[&a], 0);
+    assert_eq!(ranks[&b], 1);
+    assert_eq!(ranks[&c], 2);
+}
+
+#[cfg(feature = "trace")]
+#[test]
+fn simplex_trace_contains_vertices_of_graph() {
+    // the initial feasible tree of the first graph is optimal, the one of
+    // the second graph is not
+    let graphs = [
+        [
+            (1, 7),
+            (3, 5),
+            (4, 5),
+            (0, 3),
+            (2, 6),
+            (0, 5),
+            (0, 6),
+            (0, 7),
+            (2, 3),
+        ],
+        [
+            (2, 5),
+            (0, 3),
+            (0, 4),
+            (1, 5),
+            (4, 5),
+            (3, 4),
+            (0, 2),
+            (1, 4),
+            (1, 2),
+        ],
+    ];
+    for (i, edges) in graphs.iter().enumerate() {
+        let layouts = layout_from_edges(edges, &Config::default());
+        let trace = layouts[0].simplex_trace();
+        assert_eq!(trace.is_empty(), i == 0);
+        for ((a, b), (c, d)) in trace {
+            assert!(edges.contains(&(*a as u32, *b as u32)));
+            assert!(edges.contains(&(*c as u32, *d as u32)));
+        }
+        let layouts = layout_from_edges(
+            edges,
+            &Config {
+                ranking_type: configure::RankingType::Up,
+                ..Default::default()
+            },
         );
+        assert!(layouts[0].simplex_trace().is_empty());
     }
+}
 
-    algorithm::start(graph, config)
+#[cfg(feature = "timing")]
+#[test]
+fn timings_record_all_phases() {
+    let edges = [
+        (0, 1),
+        (0, 2),
+        (1, 3),
+        (2, 3),
+        (3, 4),
+        (1, 4),
+        (0, 4),
+        (4, 0),
+    ];
+    let layouts = layout_from_edges(&edges, &Config::default());
+    let timings = layouts[0].timings();
+
+    assert!(!timings.cycle_removal.is_zero());
+    assert!(!timings.ranking.is_zero());
+    assert!(!timings.crossing_reduction.is_zero());
+    assert!(!timings.coordinate_assignment.is_zero());
+    assert_eq!(
+        timings.total(),
+        timings.cycle_removal
+            + timings.ranking
+            + timings.crossing_reduction
+            + timings.coordinate_assignment
+    );
 }
 
 #[test]
-fn run_algo_empty_graph() {
-    let edges = [];
-    let g = from_edges(&edges, &Config::default());
-    assert!(g.is_empty());
+fn layout_from_graph_partitions_components() {
+    let mut graph = StableDiGraph::<(), ()>::new();
+    let v = (0..5).map(|_| graph.add_node(())).collect::<Vec<_>>();
+    graph.add_edge(v[0], v[1], ());
+    graph.add_edge(v[1], v[2], ());
+    graph.add_edge(v[3], v[4], ());
+
+    let layouts = layout_from_graph(&graph, &|_, _| (10.0, 10.0), &Config::default());
+    assert_eq!(layouts.len(), 2);
+    let mut components = layouts
+        .iter()
+        .map(|l| {
+            let mut ids = l
+                .coordinates()
+                .iter()
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>();
+            ids.sort();
+            ids
+        })
+        .collect::<Vec<_>>();
+    components.sort();
+    assert_eq!(components, [vec![v[0], v[1], v[2]], vec![v[3], v[4]]]);
+    for layout in &layouts {
+        assert_eq!(layout.layers()[0].len(), 1);
+    }
 }
 
 #[cfg(test)]
@@ -417,3 +2348,888 @@ mod check_visuals {
         println!("{layout:?}");
     }
 }
+
+#[test]
+fn heavy_edge_is_shorter_and_straighter() {
+    let config = Config {
+        transpose: false,
+        ..Default::default()
+    };
+    let ranks = |layout: &Layout<NodeIndex>| {
+        layout
+            .layers()
+            .iter()
+            .enumerate()
+            .flat_map(|(rank, layer)| layer.iter().map(move |v| (*v, rank)))
+            .collect::<HashMap<_, _>>()
+    };
+    let x = |layout: &Layout<NodeIndex>, v: NodeIndex| {
+        layout
+            .coordinates()
+            .iter()
+            .find(|(id, _)| *id == v)
+            .unwrap()
+            .1
+             .0
+    };
+
+    // vertex 4 is pulled down by its two outgoing edges, unless its incoming
+    // edge is heavy
+    let graph = StableDiGraph::<(), ()>::from_edges([
+        (0, 1),
+        (1, 2),
+        (2, 3),
+        (2, 5),
+        (0, 4),
+        (4, 3),
+        (4, 5),
+    ]);
+    let size = |_, _: &()| (10.0, 10.0);
+    let heavy = graph.find_edge(0.into(), 4.into()).unwrap();
+    let weight = |id, _: &()| if id == heavy { 8 } else { 1 };
+    let regular = layout_from_graph(&graph, &size, &config);
+    let input = LayoutInput::new(&size).edge_weight(&weight);
+    let marked = layout_from_graph_with(&graph, &input, &config).unwrap();
+    assert_eq!(ranks(&regular[0])[&4.into()], 2);
+    assert_eq!(ranks(&marked[0])[&4.into()], 1);
+
+    // vertex 3 is aligned with its median upper neighbor 1, unless the edge
+    // from 0 is heavy
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 3), (1, 3), (2, 3)]);
+    let heavy = graph.find_edge(0.into(), 3.into()).unwrap();
+    let weight = |id, _: &()| if id == heavy { 8 } else { 1 };
+    let regular = layout_from_graph(&graph, &size, &config);
+    let input = LayoutInput::new(&size).edge_weight(&weight);
+    let marked = layout_from_graph_with(&graph, &input, &config).unwrap();
+    let offset = |layout: &Layout<NodeIndex>| (x(layout, 0.into()) - x(layout, 3.into())).abs();
+    assert!(offset(&marked[0]) < offset(&regular[0]));
+}
+
+#[test]
+fn estimate_layers_is_lower_bound() {
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (0, 2), (1, 3), (2, 3), (3, 0)]);
+    let config = Config::default();
+    let layouts = layout_from_graph(&graph, &|_, _| (10.0, 10.0), &config);
+    assert_eq!(estimate_layers(&graph, &config), 3);
+    assert_eq!(layouts[0].layers().len(), 3);
+
+    // splitting wide layers adds layers the estimate doesn't know about
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3), (0, 4)]);
+    let config = Config {
+        max_layer_width: Some(2),
+        ..Default::default()
+    };
+    let layouts = layout_from_graph(&graph, &|_, _| (10.0, 10.0), &config);
+    assert_eq!(estimate_layers(&graph, &config), 2);
+    assert!(layouts[0].layers().len() > 2);
+    assert_eq!(estimate_layers(&StableDiGraph::<(), ()>::new(), &config), 0);
+}
+
+#[test]
+fn estimate_layers_of_chain_and_diamond() {
+    let config = Config::default();
+    let chain = StableDiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (3, 4)]);
+    assert_eq!(estimate_layers(&chain, &config), 5);
+    // the shortcut doesn't shorten the longest path
+    let diamond = StableDiGraph::<(), ()>::from_edges([(0, 1), (0, 2), (1, 3), (2, 3), (0, 3)]);
+    assert_eq!(estimate_layers(&diamond, &config), 3);
+    for graph in [chain, diamond] {
+        let layouts = layout_from_graph(&graph, &|_, _| (10.0, 10.0), &config);
+        assert!(layouts[0].layers().len() >= estimate_layers(&graph, &config));
+    }
+}
+
+#[test]
+fn target_aspect_makes_fan_out_taller() {
+    let graph = StableDiGraph::<(), ()>::from_edges((1..=12).map(|i| (0, i)));
+    let shape = |config: &Config| {
+        let layouts = layout_from_graph(&graph, &|_, _| (10.0, 10.0), config);
+        let layers = layouts[0].layers();
+        let widest = layers.iter().map(Vec::len).max().unwrap();
+        (widest, layers.len())
+    };
+    let (default_width, default_height) = shape(&Config::default());
+    let (width, height) = shape(&Config::default().target_aspect(Some(0.5)));
+    assert_eq!((default_width, default_height), (12, 2));
+    assert!(width < default_width);
+    assert!(height > default_height);
+    assert!(width as f64 / height as f64 <= 0.5);
+    // already narrow enough
+    assert_eq!(shape(&Config::default().target_aspect(Some(6.0))), (12, 2));
+}
+
+#[test]
+fn category_minimum_length_sets_rank_gaps() {
+    let mut graph = StableDiGraph::<(), &str>::new();
+    let v = (0..4).map(|_| graph.add_node(())).collect::<Vec<_>>();
+    graph.add_edge(v[0], v[1], "inherits");
+    graph.add_edge(v[1], v[2], "calls");
+    graph.add_edge(v[0], v[3], "calls");
+
+    let size = |_, _: &()| (10.0, 10.0);
+    let category_minimum_length = HashMap::from([("inherits", 3)]);
+    let minimum_length = |_, category: &&str| category_minimum_length.get(category).copied();
+    let input = LayoutInput::new(&size).edge_minimum_length(&minimum_length);
+    let layouts = layout_from_graph_with(&graph, &input, &Config::default()).unwrap();
+    let rank = |vertex| {
+        layouts[0]
+            .layers()
+            .iter()
+            .position(|layer| layer.contains(&vertex))
+            .unwrap()
+    };
+    assert_eq!(rank(v[1]) - rank(v[0]), 3);
+    assert_eq!(rank(v[2]) - rank(v[1]), 1);
+    assert_eq!(rank(v[3]) - rank(v[0]), 1);
+}
+
+#[test]
+fn edge_minimum_lengths_from_same_source() {
+    let mut graph = StableDiGraph::<(), u32>::new();
+    let v = (0..3).map(|_| graph.add_node(())).collect::<Vec<_>>();
+    graph.add_edge(v[0], v[1], 1);
+    graph.add_edge(v[0], v[2], 3);
+
+    let size = |_, _: &()| (10.0, 10.0);
+    let minimum_length = |_, minimum_length: &u32| Some(*minimum_length);
+    let input = LayoutInput::new(&size).edge_minimum_length(&minimum_length);
+    let layouts = layout_from_graph_with(&graph, &input, &Config::default()).unwrap();
+    let rank = |vertex| {
+        layouts[0]
+            .layers()
+            .iter()
+            .position(|layer| layer.contains(&vertex))
+            .unwrap()
+    };
+    assert_eq!(rank(v[1]) - rank(v[0]), 1);
+    assert_eq!(rank(v[2]) - rank(v[0]), 3);
+}
+
+#[test]
+fn minimum_lengths_out_of_range_are_capped() {
+    assert_eq!(capped_minimum_length(3, 2), 3);
+    assert_eq!(capped_minimum_length(u32::MAX, 2), i32::MAX / 3);
+    assert_eq!(capped_minimum_length(1 << 31, 0), i32::MAX);
+    assert_eq!(capped_minimum_length(1 << 31, usize::MAX), 1);
+}
+
+#[test]
+fn component_count_of_two_triangles() {
+    let graph =
+        StableDiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (3, 5)]);
+    assert_eq!(component_count(&graph), 2);
+    assert_eq!(
+        components(&graph),
+        [
+            [0.into(), 1.into(), 2.into()],
+            [3.into(), 4.into(), 5.into()]
+        ]
+    );
+    assert_eq!(component_count(&StableDiGraph::<(), ()>::new()), 0);
+}
+
+#[test]
+fn optimize_area_reduces_area() {
+    let edges = [(0, 1), (1, 2), (0, 3), (3, 4), (3, 5), (2, 4)];
+    let area = |config: &Config| {
+        let layouts = layout_from_edges(&edges, config);
+        let ((min_x, min_y), (max_x, max_y)) = layouts[0].bounding_box();
+        (max_x - min_x) * (max_y - min_y)
+    };
+    let config = Config {
+        optimize_area: true,
+        ..Default::default()
+    };
+    assert!(area(&config) < area(&Config::default()));
+
+    // the vertex spacing is kept
+    let layout = &layout_from_edges(&edges, &config)[0];
+    let coordinates = layout
+        .coordinates()
+        .iter()
+        .copied()
+        .collect::<HashMap<_, _>>();
+    for layer in layout.layers() {
+        let mut xs = layer.iter().map(|v| coordinates[v].0).collect::<Vec<_>>();
+        xs.sort_by(f64::total_cmp);
+        assert!(xs.windows(2).all(|w| w[1] - w[0] >= config.vertex_spacing));
+    }
+}
+
+#[test]
+fn align_with_places_vertex_above_target() {
+    // without a hint, 0 is centered above its three children
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (0, 2), (0, 3), (1, 4)]);
+    let size = |_, _: &()| (10.0, 10.0);
+    let align_with = |id: NodeIndex, _: &()| (id == 0.into()).then_some(3.into());
+    let input = LayoutInput::new(&size).align_with(&align_with);
+    let layouts = layout_from_graph_with(&graph, &input, &Config::default()).unwrap();
+    let x = |v: NodeIndex| {
+        layouts[0]
+            .coordinates()
+            .iter()
+            .find(|(id, _)| *id == v)
+            .unwrap()
+            .1
+             .0
+    };
+    assert_eq!(x(0.into()), x(3.into()));
+}
+
+#[test]
+fn to_svg_interactive_tags_each_vertex() {
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (0, 2), (1, 3), (2, 3)]);
+    let layouts = layout_from_graph(&graph, &|_, _| (10.0, 10.0), &Config::default());
+    let svg = layouts[0].to_svg_interactive(|v| v.index().to_string());
+    let rects = svg
+        .lines()
+        .filter(|l| l.trim_start().starts_with("<rect"))
+        .collect::<Vec<_>>();
+    assert_eq!(rects.len(), 4);
+    for (rank, layer) in layouts[0].layers().iter().enumerate() {
+        for (order, v) in layer.iter().enumerate() {
+            let attributes = format!(
+                "data-node-id=\"{}\" data-rank=\"{rank}\" data-order=\"{order}\"",
+                v.index()
+            );
+            assert_eq!(rects.iter().filter(|r| r.contains(&attributes)).count(), 1);
+        }
+    }
+    assert_eq!(svg.matches("<polyline data-edge-id=").count(), 4);
+}
+
+#[test]
+fn heavier_edge_wins_alignment() {
+    // both children compete for being aligned with the root
+    for heavy_child in [1, 2] {
+        let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (0, 2)]);
+        let heavy = graph.find_edge(0.into(), heavy_child.into()).unwrap();
+        let size = |_, _: &()| (10.0, 10.0);
+        let weight = |id, _: &()| if id == heavy { 8 } else { 1 };
+        let input = LayoutInput::new(&size).edge_weight(&weight);
+        let layouts = layout_from_graph_with(&graph, &input, &Config::default()).unwrap();
+        let x = |v: NodeIndex| {
+            layouts[0]
+                .coordinates()
+                .iter()
+                .find(|(id, _)| *id == v)
+                .unwrap()
+                .1
+                 .0
+        };
+        assert_eq!(x(0.into()), x(heavy_child.into()));
+    }
+}
+
+#[test]
+fn spine_with_priority_is_kept_vertical() {
+    // the spine 0 -> 1 -> 2 -> 3, where each spine vertex also has side
+    // parents, which would be the median upper neighbor
+    let graph = StableDiGraph::<(), ()>::from_edges([
+        (0, 1),
+        (1, 2),
+        (2, 3),
+        (4, 1),
+        (5, 1),
+        (6, 2),
+        (7, 2),
+        (8, 3),
+        (9, 3),
+    ]);
+    let spine = [(0, 1), (1, 2), (2, 3)].map(|(t, h)| graph.find_edge(t.into(), h.into()).unwrap());
+    let x = |layout: &Layout<NodeIndex>, v: u32| {
+        layout
+            .coordinates()
+            .iter()
+            .find(|(id, _)| *id == v.into())
+            .unwrap()
+            .1
+             .0
+    };
+    let is_straight = |layout: &Layout<NodeIndex>| (1..4).all(|v| x(layout, v) == x(layout, 0));
+
+    let size = |_, _: &()| (10.0, 10.0);
+    let priority = |id, _: &()| if spine.contains(&id) { 1 } else { 0 };
+    let regular = layout_from_graph(&graph, &size, &Config::default());
+    let input = LayoutInput::new(&size).edge_priority(&priority);
+    let prioritized = layout_from_graph_with(&graph, &input, &Config::default()).unwrap();
+    assert!(!is_straight(&regular[0]));
+    assert!(is_straight(&prioritized[0]));
+    // the priority doesn't change the ranking
+    assert_eq!(regular[0].ranks(), prioritized[0].ranks());
+}
+
+#[test]
+fn acyclic_graph_contains_reversed_edges() {
+    let edges = [(0, 1), (1, 2), (2, 0), (2, 3), (3, 1)];
+    let layouts = layout_from_edges(&edges, &Config::default());
+    let acyclic = layouts[0].acyclic_graph();
+    assert!(!petgraph::algo::is_cyclic_directed(&acyclic));
+    assert_eq!(acyclic.node_count(), 4);
+    assert_eq!(acyclic.edge_count(), edges.len());
+
+    let reversed = layouts[0].reversed_edges();
+    assert!(!reversed.is_empty());
+    for (tail, head) in reversed {
+        let (tail, head) = (
+            acyclic
+                .node_indices()
+                .find(|v| acyclic[*v] == tail)
+                .unwrap(),
+            acyclic
+                .node_indices()
+                .find(|v| acyclic[*v] == head)
+                .unwrap(),
+        );
+        assert!(acyclic.contains_edge(head, tail));
+    }
+}
+
+#[test]
+fn move_node_within_slack() {
+    // 3 can be on layer 1 or 2, but not on layer 0 or 3
+    let edges = [(0, 1), (1, 2), (2, 4), (0, 3), (3, 4)];
+    let mut layouts = layout_from_edges(&edges, &Config::default());
+    let layout = &mut layouts[0];
+    let rank = |layout: &Layout<usize>, v| layout.layers().iter().position(|l| l.contains(&v));
+    let other_rank = 3 - rank(layout, 3).unwrap();
+
+    layout.move_node_to_rank(3, other_rank).unwrap();
+    assert_eq!(rank(layout, 3), Some(other_rank));
+    // vertex 1 is on layer 1 and vertex 2 on layer 2
+    let coordinates = layout
+        .coordinates()
+        .iter()
+        .copied()
+        .collect::<HashMap<_, _>>();
+    assert_eq!(coordinates[&3].1, coordinates[&other_rank].1);
+    assert!((coordinates[&3].0 - coordinates[&other_rank].0).abs() >= 10.0);
+    for edge in layout
+        .routed_edges()
+        .iter()
+        .filter(|e| e.head == 3 || e.tail == 3)
+    {
+        let span = rank(layout, edge.head).unwrap() - rank(layout, edge.tail).unwrap();
+        assert_eq!(edge.points.len(), span + 1);
+    }
+    // the long edge of 3 moved to the other side, passing a single dummy
+    // vertex, without crossing 1 or 2
+    let dummies = layout
+        .iter_layers_with_dummies()
+        .map(|l| l.iter().filter(|(_, is_dummy)| *is_dummy).count())
+        .collect::<Vec<_>>();
+    assert_eq!(dummies, [0, other_rank - 1, 2 - other_rank, 0]);
+    assert_eq!(layout.count_crossings(), 0);
+
+    assert_eq!(
+        layout.move_node_to_rank(3, 0),
+        Err(LayoutError::InfeasibleRank)
+    );
+    assert_eq!(
+        layout.move_node_to_rank(3, 3),
+        Err(LayoutError::InfeasibleRank)
+    );
+    assert_eq!(
+        layout.move_node_to_rank(3, 4),
+        Err(LayoutError::UnknownLayer)
+    );
+    assert_eq!(
+        layout.move_node_to_rank(5, 1),
+        Err(LayoutError::UnknownVertex)
+    );
+    assert_eq!(rank(layout, 3), Some(other_rank));
+
+    // moving it back restores the layers
+    let before = layouts[0].clone();
+    let layout = &mut layouts[0];
+    layout.move_node_to_rank(3, 3 - other_rank).unwrap();
+    layout.move_node_to_rank(3, other_rank).unwrap();
+    assert_eq!(layout.layers(), before.layers());
+    assert_eq!(
+        layout.iter_layers_with_dummies().collect::<Vec<_>>(),
+        before.iter_layers_with_dummies().collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn move_node_keeps_minimum_length() {
+    // 4 could be placed on layer 1 or 2, but (4, 3) spans at least two layers
+    let mut graph = StableDiGraph::<(), u32>::new();
+    let v = (0..5).map(|_| graph.add_node(())).collect::<Vec<_>>();
+    graph.extend_with_edges([
+        (v[0], v[1], 1),
+        (v[1], v[2], 1),
+        (v[2], v[3], 1),
+        (v[0], v[4], 1),
+        (v[4], v[3], 2),
+    ]);
+    let size = |_, _: &()| (10.0, 10.0);
+    let minimum_length = |_, minimum_length: &u32| Some(*minimum_length);
+    let input = LayoutInput::new(&size).edge_minimum_length(&minimum_length);
+    let mut layouts = layout_from_graph_with(&graph, &input, &Config::default()).unwrap();
+    assert_eq!(layouts[0].ranks()[&v[4]], 1);
+    assert_eq!(
+        layouts[0].move_node_to_rank(v[4], 2),
+        Err(LayoutError::InfeasibleRank)
+    );
+}
+
+#[test]
+fn moved_node_does_not_overlap_its_new_layer() {
+    // 5 can be placed next to 1 or next to 2 and 3, which are spread around
+    // the x coordinate of 1. Without dummy vertices, no space is reserved
+    // for 5 in the other layer.
+    let graph = StableDiGraph::<(), ()>::from_edges([
+        (0, 1),
+        (1, 2),
+        (1, 3),
+        (2, 4),
+        (3, 4),
+        (0, 5),
+        (5, 4),
+    ]);
+    let size = |v: NodeIndex, _: &()| {
+        if v.index() == 5 {
+            (100.0, 10.0)
+        } else {
+            (10.0, 10.0)
+        }
+    };
+    let config = Config {
+        dummy_vertices: false,
+        ..Default::default()
+    };
+    let mut layouts = layout_from_graph(&graph, &size, &config);
+    let layout = &mut layouts[0];
+    let v = NodeIndex::new(5);
+    let target = 3 - layout.ranks()[&v] as usize;
+    layout.move_node_to_rank(v, target).unwrap();
+
+    let coordinates = layout
+        .coordinates()
+        .iter()
+        .copied()
+        .zip(layout.sizes.iter().copied())
+        .map(|((v, (x, _)), (width, _))| (v, (x, width)))
+        .collect::<HashMap<_, _>>();
+    for layer in layout.layers() {
+        for (i, a) in layer.iter().enumerate() {
+            for b in &layer[i + 1..] {
+                let ((xa, wa), (xb, wb)) = (coordinates[a], coordinates[b]);
+                assert!((xa - xb).abs() >= (wa + wb) / 2.0, "{a:?} overlaps {b:?}");
+            }
+        }
+    }
+    // the routes of all edges still end at their vertices
+    for edge in layout.routed_edges() {
+        assert_eq!(edge.points[0].0, coordinates[&edge.tail].0);
+        assert_eq!(edge.points.last().unwrap().0, coordinates[&edge.head].0);
+    }
+}
+
+#[test]
+fn layer_bands_are_contiguous() {
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (0, 2), (1, 3), (2, 3)]);
+    let size = |id: NodeIndex, _: &()| {
+        if id == 1.into() {
+            (10.0, 40.0)
+        } else {
+            (10.0, 10.0)
+        }
+    };
+    let layouts = layout_from_graph(&graph, &size, &Config::default());
+    let bands = layouts[0].layer_bands();
+    assert_eq!(bands.len(), 3);
+    for (top, bottom) in &bands {
+        assert!(top < bottom);
+    }
+    for w in bands.windows(2) {
+        assert_eq!(w[0].1, w[1].0);
+    }
+    let height = |(top, bottom): (f64, f64)| bottom - top;
+    assert!(height(bands[1]) > height(bands[0]));
+    assert!(height(bands[1]) > height(bands[2]));
+    // each vertex lies within its band
+    for ((v, (_, y)), (_, h)) in layouts[0].coordinates().iter().zip(&layouts[0].sizes) {
+        let rank = layouts[0]
+            .layers()
+            .iter()
+            .position(|l| l.contains(v))
+            .unwrap();
+        assert!(bands[rank].0 <= y - h / 2.0 && y + h / 2.0 <= bands[rank].1);
+    }
+}
+
+#[test]
+fn stable_source_order_orders_sources_by_index() {
+    let edges = [(0, 3), (0, 4), (1, 4), (2, 4), (2, 5)];
+    let config = Config {
+        stable_source_order: true,
+        ..Default::default()
+    };
+    let layouts = layout_from_edges(&edges, &config);
+    assert_eq!(layouts[0].layers()[0], [0, 1, 2]);
+
+    // the order is only a tie-break, so it doesn't add crossings
+    let edges = [(0, 4), (1, 3), (2, 3), (2, 4), (0, 5)];
+    let layouts = layout_from_edges(&edges, &config);
+    assert_eq!(layouts[0].count_crossings(), 0);
+
+    // order constraints take precedence
+    let layouts = layout_from_edges_with_order(&edges, &[(2, 0)], &config).unwrap();
+    let top = &layouts[0].layers()[0];
+    let position = |v| top.iter().position(|w| *w == v).unwrap();
+    assert!(position(2) < position(0));
+}
+
+#[test]
+fn cyclic_graph_keeps_original_edge_directions() {
+    let edges = [(0, 1), (1, 2), (2, 0), (2, 2), (1, 3), (3, 1)];
+    let layouts = layout_from_edges(&edges, &Config::default());
+    assert_eq!(layouts.len(), 1);
+    assert_eq!(layouts[0].coordinates().len(), 4);
+    let routed = layouts[0]
+        .routed_edges()
+        .iter()
+        .map(|e| (e.tail as u32, e.head as u32))
+        .collect::<HashSet<_>>();
+    for edge in edges.iter().filter(|(t, h)| t != h) {
+        assert!(routed.contains(edge));
+    }
+    assert_eq!(layouts[0].self_loops().len(), 1);
+}
+
+#[test]
+fn feedback_edge_reports_span() {
+    let edges = [(0, 1), (1, 2), (2, 3), (3, 0)];
+    let layouts = layout_from_edges(&edges, &Config::default());
+    let feedback = layouts[0].feedback_edges();
+    assert_eq!(feedback.len(), 1);
+    let (id, span) = feedback[0];
+    let edge = &layouts[0].routed_edges()[id];
+    assert!(edge.reversed);
+    assert_eq!(span, 3);
+}
+
+#[test]
+fn max_fan_out_inserts_distributors() {
+    let edges = (1..=16).map(|c| (0, c)).collect::<Vec<_>>();
+    let config = Config {
+        max_fan_out: Some(4),
+        ..Default::default()
+    };
+    let layout = &layout_from_edges(&edges, &config)[0];
+    let distributors = layout
+        .iter_layers_with_dummies()
+        .nth(1)
+        .unwrap()
+        .iter()
+        .filter(|(_, is_dummy)| *is_dummy)
+        .count();
+    assert_eq!(distributors, 4);
+    assert_eq!(layout.layers()[2].len(), 16);
+    assert_eq!(layout.routed_edges().len(), 16);
+    assert!(layout.routed_edges().iter().all(|e| e.points.len() == 3));
+}
+
+#[test]
+fn max_fan_out_without_dummy_vertices_keeps_distributors() {
+    let config = Config {
+        max_fan_out: Some(2),
+        dummy_vertices: false,
+        ..Default::default()
+    };
+    let layout = &layout_from_edges(&[(0, 1), (0, 2), (0, 3)], &config)[0];
+    let layers = layout.iter_layers_with_dummies().collect::<Vec<_>>();
+    assert_eq!(layers.len(), 3);
+    assert!(layers.iter().all(|l| !l.is_empty()));
+    assert_eq!(layout.routed_edges().len(), 3);
+}
+
+#[test]
+fn density_matrix_of_grid_is_superdiagonal() {
+    // 3x3 grid with edges to the right and down, layered along the diagonals
+    let id = |r: u32, c: u32| r * 3 + c;
+    let mut edges = Vec::new();
+    for r in 0..3 {
+        for c in 0..3 {
+            if c < 2 {
+                edges.push((id(r, c), id(r, c + 1)));
+            }
+            if r < 2 {
+                edges.push((id(r, c), id(r + 1, c)));
+            }
+        }
+    }
+    let layout = &layout_from_edges(&edges, &Config::default())[0];
+    let matrix = layout.density_matrix();
+    assert_eq!(matrix.len(), 5);
+    for (i, row) in matrix.iter().enumerate() {
+        for (j, count) in row.iter().enumerate() {
+            assert_eq!(*count > 0, j == i + 1);
+        }
+    }
+    assert_eq!(matrix.iter().flatten().sum::<usize>(), edges.len());
+}
+
+#[test]
+fn isolated_vertex_gets_its_own_layout() {
+    let vertices = [0, 1, 2, 3].map(|v| (v, (10.0, 10.0)));
+    let edges = [(0, 1), (1, 2), (0, 2)];
+    let layouts = layout_from_vertices_and_edges(&vertices, &edges, &Config::default());
+    assert_eq!(layouts.len(), 2);
+    let isolated = layouts.iter().find(|l| l.layers()[0] == [3]).unwrap();
+    assert_eq!(isolated.layers().len(), 1);
+    assert_eq!(isolated.coordinates().len(), 1);
+}
+
+#[test]
+fn merged_components_are_separated_by_gap() {
+    let edges = [(0, 1), (1, 2), (0, 2), (3, 4), (3, 5)];
+    let vertices = (0..6).map(|v| (v, (20.0, 10.0))).collect::<Vec<_>>();
+    let layouts = layout_from_vertices_and_edges(&vertices, &edges, &Config::default());
+    assert_eq!(layouts.len(), 2);
+    let merged = Layout::merge(layouts.clone(), 15.0);
+    assert_eq!(merged.coordinates().len(), 6);
+    assert_eq!(merged.routed_edges().len(), edges.len());
+
+    // right side of the first component and left side of the second
+    let component = |layout: &Layout<usize>| {
+        layout
+            .coordinates()
+            .iter()
+            .map(|(v, _)| *v)
+            .collect::<HashSet<_>>()
+    };
+    let (first, second) = (component(&layouts[0]), component(&layouts[1]));
+    let extent = |vertices: &HashSet<usize>| {
+        merged
+            .coordinates()
+            .iter()
+            .filter(|(v, _)| vertices.contains(v))
+            .fold(
+                (f64::INFINITY, f64::NEG_INFINITY),
+                |(min, max), (_, (x, _))| (min.min(x - 10.0), max.max(x + 10.0)),
+            )
+    };
+    let ((_, first_right), (second_left, _)) = (extent(&first), extent(&second));
+    assert!(second_left - first_right >= 15.0 - 1e-9);
+}
+
+#[test]
+fn to_svg_interactive_rounds_coordinates() {
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (0, 2), (1, 3), (2, 3), (0, 3)]);
+    let layouts = layout_from_graph(
+        &graph,
+        &|v, _| (10.0 / 3.0 + v.index() as f64, 7.0 / 3.0),
+        &Config::default(),
+    );
+    let decimals = |svg: &str| {
+        svg.split(|c: char| !c.is_ascii_digit() && c != '.')
+            .filter_map(|n| n.split_once('.'))
+            .map(|(_, decimals)| decimals.len())
+            .max()
+            .unwrap_or(0)
+    };
+    let svg = layouts[0].to_svg_interactive(|v| v.index().to_string());
+    assert!(decimals(&svg) > 1);
+    let svg = layouts[0].to_svg_interactive_with_precision(|v| v.index().to_string(), Some(1));
+    assert!(decimals(&svg) <= 1);
+    assert_eq!(svg.lines().count(), 2 + 4 + 5);
+}
+
+#[test]
+fn svg_and_dot_writers_share_escaping_and_precision() {
+    let layouts = layout_from_edges(&[(0, 1), (1, 2), (0, 2)], &Config::default());
+    let options = layout::SvgOptions {
+        node_radius: Some(5.0),
+        coordinate_precision: Some(0),
+        ..Default::default()
+    };
+    assert!(layouts[0]
+        .to_svg(&options)
+        .contains(r#"<circle cx="8" cy="10" r="5""#));
+    assert!(layouts[0]
+        .to_dot_with_precision(false, Some(0))
+        .contains(r#"[label="1", pos="8,-10!"]"#));
+
+    let svg = layouts[0].to_svg_interactive(|v| format!("\"{v}<&"));
+    assert!(svg.contains(r#"data-node-id="&quot;1&lt;&amp;""#));
+
+    let empty = Layout::<usize>::merge([], 0.0);
+    let view_box = r#"viewBox="0 0 0 0""#;
+    assert!(empty
+        .to_svg_interactive(|v| v.to_string())
+        .contains(view_box));
+    let options = layout::SvgOptions {
+        margin: 0.0,
+        ..Default::default()
+    };
+    assert!(empty.to_svg(&options).contains(view_box));
+}
+
+#[test]
+fn uncrossed_edge_is_not_crossed() {
+    let graph =
+        StableDiGraph::<(), ()>::from_edges([(0, 3), (0, 4), (1, 3), (1, 5), (2, 4), (2, 5)]);
+    let uncrossed = graph.find_edge(1.into(), 3.into()).unwrap();
+    let route = |layouts: &[Layout<NodeIndex>]| {
+        layouts[0]
+            .routed_edges()
+            .iter()
+            .position(|e| e.tail == 1.into() && e.head == 3.into())
+            .unwrap()
+    };
+    let size = |_, _: &()| (10.0, 10.0);
+    let plain = layout_from_graph(&graph, &size, &Config::default());
+    assert!(plain[0].is_edge_crossed(route(&plain)));
+
+    let is_uncrossed = |e, _: &()| e == uncrossed;
+    let input = LayoutInput::new(&size).uncrossed(&is_uncrossed);
+    let layouts = layout_from_graph_with(&graph, &input, &Config::default()).unwrap();
+    assert!(!layouts[0].is_edge_crossed(route(&layouts)));
+    // other edges may still cross
+    assert!((0..graph.edge_count()).any(|e| layouts[0].is_edge_crossed(e)));
+}
+
+#[test]
+fn crossing_uncrossed_edges_is_an_error() {
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 2), (0, 3), (1, 2), (1, 3)]);
+    let size = |_, _: &()| (10.0, 10.0);
+    let input = LayoutInput::new(&size).uncrossed(&|_, _| true);
+    let layouts = layout_from_graph_with(&graph, &input, &Config::default());
+    assert_eq!(layouts, Err(LayoutError::CannotSatisfyNonCrossing));
+}
+
+#[test]
+fn ranks_of_chain() {
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    let layouts = layout_from_graph(&graph, &|_, _| (10.0, 10.0), &Config::default());
+    let ranks = layouts[0].ranks();
+    assert_eq!(ranks.len(), 3);
+    for v in 0..3 {
+        assert_eq!(ranks[&NodeIndex::from(v)], v as i32);
+    }
+}
+
+#[test]
+fn coffman_graham_limits_layer_width() {
+    let edges = (1..=6).map(|c| (0, c)).collect::<Vec<_>>();
+    let config = Config {
+        ranking_type: configure::RankingType::CoffmanGraham,
+        max_layer_width: Some(2),
+        ..Default::default()
+    };
+    let layout = &layout_from_edges(&edges, &config)[0];
+    let ranks = layout.ranks();
+    assert_eq!(ranks[&0], 0);
+    let child_ranks = (1..=6).map(|c| ranks[&c]).collect::<HashSet<_>>();
+    assert_eq!(child_ranks, HashSet::from([1, 2, 3]));
+    assert!(layout.layers().iter().all(|l| l.len() <= 2));
+}
+
+#[test]
+fn partial_ranks_force_span() {
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (0, 4), (4, 3)]);
+    let size = |_, _: &()| (10.0, 10.0);
+    let fixed_ranks = HashMap::from([(0.into(), 0), (2.into(), 3)]);
+    let input = LayoutInput::new(&size).fixed_ranks(&fixed_ranks);
+    let layouts = layout_from_graph_with(&graph, &input, &Config::default()).unwrap();
+    let ranks = layouts[0].ranks();
+    assert_eq!(ranks[&0.into()], 0);
+    assert_eq!(ranks[&2.into()], 3);
+    // the remaining vertices are still placed tightly
+    assert_eq!(ranks[&3.into()], 4);
+
+    let fixed_ranks = HashMap::from([(0.into(), 2), (2.into(), 1)]);
+    let input = LayoutInput::new(&size).fixed_ranks(&fixed_ranks);
+    let layouts = layout_from_graph_with(&graph, &input, &Config::default());
+    assert_eq!(layouts, Err(LayoutError::InvalidPin));
+}
+
+#[test]
+fn layout_input_combines_attributes() {
+    // 4 is pulled up to the heavy edge from the root, while 2 is kept two
+    // ranks below 1
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (1, 2), (2, 3), (0, 4), (4, 3)]);
+    let size = |_, _: &()| (10.0, 10.0);
+    let heavy = graph.find_edge(0.into(), 4.into()).unwrap();
+    let weight = |id, _: &()| if id == heavy { 8 } else { 1 };
+    let fixed_ranks = HashMap::from([(1.into(), 1), (2.into(), 3)]);
+    let input = LayoutInput::new(&size)
+        .edge_weight(&weight)
+        .fixed_ranks(&fixed_ranks)
+        .root(0.into());
+    let layouts = layout_from_graph_with(&graph, &input, &Config::default()).unwrap();
+    let ranks = layouts[0].ranks();
+    assert_eq!(ranks[&0.into()], 0);
+    assert_eq!(ranks[&2.into()], 3);
+    assert_eq!(ranks[&4.into()], 1);
+    assert_eq!(ranks[&3.into()], 4);
+}
+
+#[test]
+fn balance_ranks_centers_vertex_with_slack() {
+    // 5 can be placed anywhere between rank 1 and 3
+    let edges = [(0, 1), (1, 2), (2, 3), (3, 4), (0, 5), (5, 4)];
+    let rank_of_5 = |balance_ranks| {
+        let config = Config {
+            balance_ranks,
+            ..Default::default()
+        };
+        layout_from_edges(&edges, &config)[0].ranks()[&5]
+    };
+    assert_ne!(rank_of_5(false), 2);
+    assert_eq!(rank_of_5(true), 2);
+}
+
+#[test]
+fn positions_of_chain_are_layer_spacing_apart() {
+    let graph = StableDiGraph::<(), ()>::from_edges([(0, 1), (1, 2)]);
+    let positions =
+        positions_from_graph(&graph, &|_, _| (10.0, 30.0), 5.0, 50.0, &Config::default());
+    assert_eq!(positions.len(), 3);
+    assert_eq!(positions[&0.into()].1, 0.0);
+    assert_eq!(positions[&1.into()].1, 50.0);
+    assert_eq!(positions[&2.into()].1, 100.0);
+    // a chain is drawn as a straight line
+    assert_eq!(positions[&0.into()].0, positions[&2.into()].0);
+}
+
+#[test]
+fn edge_paths_pass_through_dummies() {
+    let edges = [(0, 1), (1, 2), (2, 3), (0, 3)];
+    let layouts = layout_from_edges(&edges, &Config::default());
+    let coordinates = layouts[0]
+        .coordinates()
+        .iter()
+        .copied()
+        .collect::<HashMap<_, _>>();
+    let paths = layouts[0].edge_paths();
+    assert_eq!(paths.len(), 4);
+    assert_eq!(paths[&(0, 1)], [coordinates[&0], coordinates[&1]]);
+    let long_edge = &paths[&(0, 3)];
+    assert_eq!(long_edge.len(), 4);
+    assert_eq!(long_edge[0], coordinates[&0]);
+    assert_eq!(long_edge[3], coordinates[&3]);
+}
+
+#[test]
+fn orthogonal_edge_paths_step_between_layers() {
+    // the long edge passes its dummy vertex next to 1, so it has to bend
+    let layouts = layout_from_edges(&[(0, 1), (1, 2), (0, 2)], &Config::default());
+    let polyline = &layouts[0].edge_paths()[&(0, 2)];
+    assert_eq!(polyline.len(), 3);
+    let straight = &layouts[0].edge_paths_with(layout::EdgeRouting::Straight)[&(0, 2)];
+    assert_eq!(straight, &[polyline[0], polyline[2]]);
+
+    let orthogonal = &layouts[0].edge_paths_with(layout::EdgeRouting::Orthogonal)[&(0, 2)];
+    assert!(orthogonal.len() > polyline.len());
+    assert_eq!(orthogonal.first(), polyline.first());
+    assert_eq!(orthogonal.last(), polyline.last());
+    for w in orthogonal.windows(2) {
+        assert!(w[0].0 == w[1].0 || w[0].1 == w[1].1);
+    }
+    // all points of the polyline are kept
+    assert!(polyline.iter().all(|p| orthogonal.contains(p)));
+}
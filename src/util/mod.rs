@@ -1,7 +1,12 @@
 use std::collections::HashSet;
 
 use log::{debug, info};
-use petgraph::stable_graph::{NodeIndex, StableDiGraph};
+use petgraph::{
+    algo::toposort,
+    graphmap::{DiGraphMap, NodeTrait},
+    stable_graph::{NodeIndex, StableDiGraph},
+    visit::Dfs,
+};
 
 pub fn weakly_connected_components<V: Copy, E: Copy>(
     graph: StableDiGraph<V, E>,
@@ -37,10 +42,24 @@ pub fn weakly_connected_components<V: Copy, E: Copy>(
     components
 }
 
-fn component_dfs<V: Copy, E: Copy>(
-    start: NodeIndex,
-    graph: &StableDiGraph<V, E>,
-) -> HashSet<NodeIndex> {
+/// Returns the vertices of each weakly connected component, in the order of
+/// their smallest [NodeIndex].
+pub fn components<V, E>(graph: &StableDiGraph<V, E>) -> Vec<Vec<NodeIndex>> {
+    let mut components = Vec::new();
+    let mut visited = HashSet::new();
+    for node in graph.node_indices() {
+        if visited.contains(&node) {
+            continue;
+        }
+        let mut component = component_dfs(node, graph).into_iter().collect::<Vec<_>>();
+        component.sort();
+        visited.extend(component.iter().copied());
+        components.push(component);
+    }
+    components
+}
+
+fn component_dfs<V, E>(start: NodeIndex, graph: &StableDiGraph<V, E>) -> HashSet<NodeIndex> {
     let mut queue = vec![start];
     let mut visited = HashSet::new();
 
@@ -71,9 +90,60 @@ fn into_weakly_connected_components_two_components() {
     assert!(sgs[1].contains_edge(4.into(), 6.into()));
 }
 
-// TODO: refactor into trait
-// disable warnings, since we might still need this someday
-#[allow(dead_code)]
+/// Calculates the transitive closure of a relation given as a list of pairs,
+/// i.e. all pairs `(a, b)` where `b` can be reached from `a`.
+///
+/// Returns [None] if the relation contains a cycle.
+pub fn transitive_closure<T: NodeTrait>(pairs: &[(T, T)]) -> Option<HashSet<(T, T)>> {
+    let graph = DiGraphMap::<T, ()>::from_edges(pairs);
+    toposort(&graph, None).ok()?;
+    let mut closure = HashSet::new();
+    for a in graph.nodes() {
+        let mut dfs = Dfs::new(&graph, a);
+        while let Some(b) = dfs.next(&graph) {
+            if a != b {
+                closure.insert((a, b));
+            }
+        }
+    }
+    Some(closure)
+}
+
+#[test]
+fn transitive_closure_chain() {
+    let closure = transitive_closure(&[(0, 1), (1, 2), (2, 3)]).unwrap();
+    assert_eq!(closure.len(), 6);
+    assert!(closure.contains(&(0, 3)));
+    assert!(!closure.contains(&(3, 0)));
+}
+
+#[test]
+fn transitive_closure_cycle() {
+    assert!(transitive_closure(&[(0, 1), (1, 2), (2, 0)]).is_none());
+}
+
+#[test]
+fn transitive_closure_long_cycle() {
+    let pairs = (0..100_000u32)
+        .map(|v| (v, (v + 1) % 100_000))
+        .collect::<Vec<_>>();
+    assert!(transitive_closure(&pairs).is_none());
+}
+
+/// Returns a pseudo random number generator for building test graphs. Each
+/// call returns a number below its argument, and the sequence only depends on
+/// `seed`.
+#[cfg(test)]
+pub(crate) fn random_numbers(seed: u64) -> impl FnMut(u32) -> u32 {
+    let mut state = seed;
+    move |n| {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (state >> 33) as u32 % n
+    }
+}
+
 pub(super) fn iterate(dir: IterDir, length: usize) -> impl Iterator<Item = usize> {
     let (mut start, step) = match dir {
         IterDir::Forward => (usize::MAX, 1), // up corresponds to left to right